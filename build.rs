@@ -0,0 +1,16 @@
+fn main() {
+    #[cfg(feature = "ffi-header")]
+    {
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+
+        cbindgen::Builder::new()
+            .with_crate(&crate_dir)
+            .with_config(cbindgen::Config::from_file(format!("{crate_dir}/cbindgen.toml")).unwrap())
+            .generate()
+            .expect("Unable to generate C bindings")
+            .write_to_file(format!("{crate_dir}/include/todolist.h"));
+    }
+
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+}
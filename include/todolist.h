@@ -0,0 +1,294 @@
+#ifndef TODOLIST_H
+#define TODOLIST_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum TodoDeadlineKind {
+  TODO_DEADLINE_KIND_NONE = 0,
+  // All-day deadline, read with `todolist_task_deadline_date`.
+  TODO_DEADLINE_KIND_DATE = 1,
+  TODO_DEADLINE_KIND_DATE_TIME = 2,
+  // Read with `todolist_task_deadline_window`.
+  TODO_DEADLINE_KIND_WINDOW = 3,
+} TodoDeadlineKind;
+
+typedef enum TodoPriority {
+  TODO_PRIORITY_COMMON = 0,
+  TODO_PRIORITY_GREEN = 1,
+  TODO_PRIORITY_YELLOW = 2,
+  TODO_PRIORITY_RED = 3,
+} TodoPriority;
+
+// Result code returned by every fallible function.
+typedef enum TodoResult {
+  TODO_RESULT_OK = 0,
+  TODO_RESULT_NULL_POINTER = 1,
+  TODO_RESULT_INVALID_UTF8 = 2,
+  TODO_RESULT_INVALID_ARGUMENT = 3,
+  TODO_RESULT_NOT_FOUND = 4,
+  TODO_RESULT_ALREADY_EXISTS = 5,
+} TodoResult;
+
+typedef enum TodoStatus {
+  TODO_STATUS_CREATED = 0,
+  TODO_STATUS_PROGRESS = 1,
+  TODO_STATUS_COMPLETED = 2,
+  TODO_STATUS_ARCHIVED = 3,
+} TodoStatus;
+
+typedef struct TodoTask TodoTask;
+
+typedef struct TodoList TodoList;
+
+// Snapshot of the tasks matched by a filter.
+//
+// The pointers it yields borrow from the list and are only valid while the
+// list is alive and not modified.
+typedef struct TodoTaskIter TodoTaskIter;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Returns the message of the last failed call on this thread, or null.
+// The string must be released with `todolist_string_free`.
+char *todolist_last_error(void);
+
+// # Safety
+// `value` must be null or a string returned by this library.
+void todolist_string_free(char *value);
+
+struct TodoList *todolist_new(void);
+
+// # Safety
+// `list` must be null or a handle returned by `todolist_new`.
+void todolist_free(struct TodoList *list);
+
+// # Safety
+// `list` must be a valid list handle.
+uintptr_t todolist_task_count(const struct TodoList *list);
+
+// Borrows the task at `index`, or returns null when out of range.
+//
+// # Safety
+// `list` must be a valid list handle.
+const struct TodoTask *todolist_task_at(const struct TodoList *list, uintptr_t index);
+
+// Borrows the task with the given id for modification, or returns null.
+//
+// # Safety
+// `list` must be a valid list handle and `id` a NUL-terminated string.
+struct TodoTask *todolist_task_by_id(struct TodoList *list, const char *id);
+
+// Moves `task` into the list. On success the task handle must not be freed
+// or used again; on failure ownership stays with the caller.
+//
+// # Safety
+// `list` must be a valid list handle and `task` a handle from `todolist_task_new`.
+enum TodoResult todolist_add_task(struct TodoList *list, struct TodoTask *task);
+
+// # Safety
+// `list` must be a valid list handle and `id` a NUL-terminated string.
+enum TodoResult todolist_remove_task(struct TodoList *list, const char *id);
+
+// # Safety
+// `list` must be a valid list handle and `title` a NUL-terminated string.
+enum TodoResult todolist_new_category(struct TodoList *list, const char *title);
+
+// # Safety
+// `list` must be a valid list handle and `title` a NUL-terminated string.
+enum TodoResult todolist_new_tag(struct TodoList *list, const char *title);
+
+// # Safety
+// `list` must be a valid list handle.
+struct TodoTaskIter *todolist_tasks_all(const struct TodoList *list);
+
+// # Safety
+// `list` must be a valid list handle and `title` a NUL-terminated string.
+struct TodoTaskIter *todolist_tasks_by_category(const struct TodoList *list, const char *title);
+
+// # Safety
+// `list` must be a valid list handle and `title` a NUL-terminated string.
+struct TodoTaskIter *todolist_tasks_by_tag(const struct TodoList *list, const char *title);
+
+// `priority` is one of the `TodoPriority` values.
+//
+// # Safety
+// `list` must be a valid list handle.
+struct TodoTaskIter *todolist_tasks_by_priority(const struct TodoList *list, int32_t priority);
+
+// `status` is one of the `TodoStatus` values.
+//
+// # Safety
+// `list` must be a valid list handle.
+struct TodoTaskIter *todolist_tasks_by_status(const struct TodoList *list, int32_t status);
+
+// Returns the next matched task, or null once the iterator is exhausted.
+//
+// # Safety
+// `iter` must be a valid iterator handle whose list is still alive and unmodified.
+const struct TodoTask *todolist_iter_next(struct TodoTaskIter *iter);
+
+// # Safety
+// `iter` must be null or a valid iterator handle.
+uintptr_t todolist_iter_len(const struct TodoTaskIter *iter);
+
+// # Safety
+// `iter` must be null or a handle returned by one of the `todolist_tasks_*` functions.
+void todolist_iter_free(struct TodoTaskIter *iter);
+
+// Creates a standalone task and stores its handle in `out`.
+//
+// # Safety
+// `title` must be a NUL-terminated string and `out` a valid pointer.
+enum TodoResult todolist_task_new(const char *title, struct TodoTask **out);
+
+// # Safety
+// `task` must be null or a standalone handle from `todolist_task_new`,
+// never a pointer borrowed from a list.
+void todolist_task_free(struct TodoTask *task);
+
+// # Safety
+// `task` must be a valid task pointer.
+char *todolist_task_id(const struct TodoTask *task);
+
+// # Safety
+// `task` must be a valid task pointer.
+char *todolist_task_title(const struct TodoTask *task);
+
+// # Safety
+// `task` must be a valid task pointer.
+char *todolist_task_description(const struct TodoTask *task);
+
+// # Safety
+// `task` must be a valid task pointer.
+char *todolist_task_category(const struct TodoTask *task);
+
+// Returns the tags joined by a single space, without the leading `#`.
+//
+// # Safety
+// `task` must be a valid task pointer.
+char *todolist_task_tags(const struct TodoTask *task);
+
+// # Safety
+// `task` must be a valid task pointer.
+enum TodoPriority todolist_task_priority(const struct TodoTask *task);
+
+// # Safety
+// `task` must be a valid task pointer.
+enum TodoStatus todolist_task_status(const struct TodoTask *task);
+
+// Unix timestamp in seconds.
+//
+// # Safety
+// `task` must be a valid task pointer.
+int64_t todolist_task_created_at(const struct TodoTask *task);
+
+// Unix timestamp in seconds.
+//
+// # Safety
+// `task` must be a valid task pointer.
+int64_t todolist_task_updated_at(const struct TodoTask *task);
+
+// # Safety
+// `task` must be a valid task pointer.
+enum TodoDeadlineKind todolist_task_deadline_kind(const struct TodoTask *task);
+
+// Stores the deadline as a unix timestamp in `out` (the end of a window),
+// `NotFound` if the task has no deadline and `InvalidArgument` if it is an
+// all-day deadline.
+//
+// # Safety
+// `task` must be a valid task pointer and `out` a valid pointer.
+enum TodoResult todolist_task_deadline(const struct TodoTask *task, int64_t *out);
+
+// Stores the start and end of a window deadline as unix timestamps.
+//
+// # Safety
+// `task` must be a valid task pointer, `start` and `end` valid pointers.
+enum TodoResult todolist_task_deadline_window(const struct TodoTask *task,
+                                              int64_t *start,
+                                              int64_t *end);
+
+// Stores the day of an all-day deadline.
+//
+// # Safety
+// `task` must be a valid task pointer, `year`, `month` and `day` valid pointers.
+enum TodoResult todolist_task_deadline_date(const struct TodoTask *task,
+                                            int32_t *year,
+                                            uint32_t *month,
+                                            uint32_t *day);
+
+// # Safety
+// `task` must be a valid mutable task pointer and `title` a NUL-terminated string.
+enum TodoResult todolist_task_set_title(struct TodoTask *task, const char *title);
+
+// # Safety
+// `task` must be a valid mutable task pointer and `description` a NUL-terminated string.
+enum TodoResult todolist_task_set_description(struct TodoTask *task, const char *description);
+
+// # Safety
+// `task` must be a valid mutable task pointer and `title` a NUL-terminated string.
+enum TodoResult todolist_task_set_category(struct TodoTask *task, const char *title);
+
+// # Safety
+// `task` must be a valid mutable task pointer and `title` a NUL-terminated string.
+enum TodoResult todolist_task_add_tag(struct TodoTask *task, const char *title);
+
+// # Safety
+// `task` must be a valid mutable task pointer and `title` a NUL-terminated string.
+enum TodoResult todolist_task_remove_tag(struct TodoTask *task, const char *title);
+
+// `priority` is one of the `TodoPriority` values.
+//
+// # Safety
+// `task` must be a valid mutable task pointer.
+enum TodoResult todolist_task_set_priority(struct TodoTask *task, int32_t priority);
+
+// `status` is one of the `TodoStatus` values.
+//
+// # Safety
+// `task` must be a valid mutable task pointer.
+enum TodoResult todolist_task_set_status(struct TodoTask *task, int32_t status);
+
+// `deadline` is a unix timestamp in seconds.
+//
+// # Safety
+// `task` must be a valid mutable task pointer.
+enum TodoResult todolist_task_set_deadline(struct TodoTask *task, int64_t deadline);
+
+// # Safety
+// `task` must be a valid mutable task pointer.
+enum TodoResult todolist_task_set_deadline_date(struct TodoTask *task,
+                                                int32_t year,
+                                                uint32_t month,
+                                                uint32_t day);
+
+// `start` and `end` are unix timestamps in seconds.
+//
+// # Safety
+// `task` must be a valid mutable task pointer.
+enum TodoResult todolist_task_set_deadline_window(struct TodoTask *task,
+                                                  int64_t start,
+                                                  int64_t end);
+
+// # Safety
+// `task` must be a valid mutable task pointer.
+enum TodoResult todolist_task_clear_deadline(struct TodoTask *task);
+
+// Moves the deadline by `seconds`, whole days for an all-day deadline.
+//
+// # Safety
+// `task` must be a valid mutable task pointer.
+enum TodoResult todolist_task_shift_deadline(struct TodoTask *task, int64_t seconds);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* TODOLIST_H */
@@ -0,0 +1,82 @@
+# Run with `maturin develop && python -m unittest discover python/tests`
+import datetime
+import unittest
+
+import todolist
+from todolist import Category, Priority, Tag, Task, TaskStatus, TodoList
+
+
+class TodoListTest(unittest.TestCase):
+    def test_build_task(self):
+        deadline = datetime.datetime(2030, 1, 1, 12, tzinfo=datetime.timezone.utc)
+        task = (Task.new("First")
+                .description("description ...")
+                .priority(Priority.Red)
+                .category(Category("Work"))
+                .tags([Tag("rust"), Tag("code")])
+                .deadline(deadline)
+                .build())
+
+        self.assertEqual(task.title, "First")
+        self.assertEqual(task.priority, Priority.Red)
+        self.assertEqual(task.category, Category("Work"))
+        self.assertEqual([tag.title for tag in task.tags], ["rust", "code"])
+        self.assertEqual(task.deadline, deadline)
+        self.assertIsInstance(task.created_at, datetime.datetime)
+
+    def test_deadline_kinds(self):
+        task = Task.new("First").deadline(datetime.date(2030, 1, 1)).build()
+        self.assertEqual(task.deadline, datetime.date(2030, 1, 1))
+
+        start = datetime.datetime(2030, 1, 1, 9, tzinfo=datetime.timezone.utc)
+        end = datetime.datetime(2030, 1, 1, 12, tzinfo=datetime.timezone.utc)
+        task.deadline = (start, end)
+        self.assertEqual(task.deadline, (start, end))
+
+        with self.assertRaises(ValueError):
+            task.deadline = (end, start)
+        with self.assertRaises(TypeError):
+            task.deadline = datetime.datetime(2030, 1, 1)
+
+        task.shift_deadline(datetime.timedelta(days=1))
+        self.assertEqual(task.deadline[0], start + datetime.timedelta(days=1))
+
+        task.deadline = None
+        self.assertIsNone(task.deadline)
+        with self.assertRaises(ValueError):
+            task.shift_deadline(datetime.timedelta(days=1))
+
+    def test_errors(self):
+        with self.assertRaises(ValueError):
+            Task.new("  ")
+
+        task = Task.new("First").tags([Tag("rust")]).build()
+        with self.assertRaises(todolist.AlreadyExistsError):
+            task.add_tag(Tag("rust"))
+
+    def test_added_task_is_a_view(self):
+        tasks = TodoList()
+        task = Task.new("First").build()
+        tasks.add_task(task)
+
+        task.status = TaskStatus.Completed
+        self.assertEqual([t.status for t in tasks], [TaskStatus.Completed])
+
+        tasks.remove_task(task)
+        self.assertEqual(len(tasks), 0)
+        self.assertEqual(task.status, TaskStatus.Completed)
+
+    def test_get_tasks_by(self):
+        tasks = TodoList()
+        for title, priority in [("First", Priority.Red), ("Second", Priority.Green)]:
+            tasks.add_task(Task.new(title).priority(priority).build())
+
+        self.assertEqual([t.title for t in tasks.get_tasks_by(Priority.Red)], ["First"])
+        self.assertEqual(list(tasks.get_tasks_by(TaskStatus.Archived)), [])
+
+        with self.assertRaises(TypeError):
+            tasks.get_tasks_by("Red")
+
+
+if __name__ == "__main__":
+    unittest.main()
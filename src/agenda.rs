@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+
+use chrono::{Days, NaiveDate, TimeZone};
+
+use crate::prelude::*;
+use crate::TodoList;
+
+/// Available tasks with a deadline grouped by when they are due, relative to
+/// the current day in a timezone, all taken at the time of the list clock
+/// whatever the clocks of the tasks. Deferred tasks and tasks scheduled to start
+/// later are left out, see `Task::is_available`. Each group is sorted by deadline, then by
+/// priority (highest first).
+pub struct Agenda<'a> {
+    overdue: Vec<&'a Task>,
+    today: Vec<&'a Task>,
+    tomorrow: Vec<&'a Task>,
+    next_seven_days: Vec<&'a Task>,
+    later: Vec<&'a Task>
+}
+
+impl<'a> Agenda<'a> {
+    pub fn get_overdue (&self) -> &[&'a Task] {
+        &self.overdue
+    }
+
+    /// Due today and not overdue yet.
+    pub fn get_today (&self) -> &[&'a Task] {
+        &self.today
+    }
+
+    pub fn get_tomorrow (&self) -> &[&'a Task] {
+        &self.tomorrow
+    }
+
+    /// Due from the day after tomorrow up to a week from today.
+    pub fn get_next_seven_days (&self) -> &[&'a Task] {
+        &self.next_seven_days
+    }
+
+    pub fn get_later (&self) -> &[&'a Task] {
+        &self.later
+    }
+}
+
+/// Sorts tasks that all have a deadline by it, then by priority (highest first).
+pub(crate) fn sort_by_deadline<Tz: TimeZone> (tasks: &mut [&Task], tz: &Tz) {
+    tasks.sort_by(|a, b| a.get_deadline().unwrap().cmp_in(b.get_deadline().unwrap(), tz)
+        .then_with(|| b.get_priority().partial_cmp(a.get_priority()).unwrap()));
+}
+
+impl TodoList {
+    /// Splits the available tasks with a deadline into overdue, today,
+    /// tomorrow, next seven days and later, see `Agenda`.
+    pub fn agenda<Tz: TimeZone> (&self, tz: &Tz) -> Agenda<'_> {
+        let now = self.now();
+        let today = now.with_timezone(tz).date_naive();
+        let tomorrow = today + Days::new(1);
+        let week = today + Days::new(7);
+
+        let mut agenda = Agenda {
+            overdue: vec![],
+            today: vec![],
+            tomorrow: vec![],
+            next_seven_days: vec![],
+            later: vec![]
+        };
+
+        for task in self.tasks.iter().filter(|task| task.is_available_at(tz, now)) {
+            let Some(deadline) = task.get_deadline() else {
+                continue;
+            };
+
+            let date = deadline.date_in(tz);
+
+            if task.is_overdue_at(tz, now) {
+                agenda.overdue.push(task);
+            } else if date <= today {
+                agenda.today.push(task);
+            } else if date == tomorrow {
+                agenda.tomorrow.push(task);
+            } else if date <= week {
+                agenda.next_seven_days.push(task);
+            } else {
+                agenda.later.push(task);
+            }
+        }
+
+        for group in [&mut agenda.overdue, &mut agenda.today, &mut agenda.tomorrow, &mut agenda.next_seven_days, &mut agenda.later] {
+            sort_by_deadline(group, tz);
+        }
+
+        agenda
+    }
+
+    /// Every day from `from` to `to` (inclusive) with the tasks due on it in
+    /// `tz`, whatever their status. A window deadline is listed on each day it
+    /// spans. Days without tasks map to an empty list.
+    pub fn calendar<Tz: TimeZone> (&self, tz: &Tz, from: NaiveDate, to: NaiveDate) -> BTreeMap<NaiveDate, Vec<&Task>> {
+        let mut calendar: BTreeMap<NaiveDate, Vec<&Task>> = from.iter_days()
+            .take_while(|day| *day <= to)
+            .map(|day| (day, vec![]))
+            .collect();
+
+        for task in self.tasks.iter() {
+            let Some(deadline) = task.get_deadline() else {
+                continue;
+            };
+
+            let first = match deadline {
+                Deadline::Window { start, .. } => start.with_timezone(tz).date_naive(),
+                _ => deadline.date_in(tz)
+            };
+
+            let (first, last) = (first.max(from), deadline.date_in(tz).min(to));
+
+            if first <= last {
+                for tasks in calendar.range_mut(first..=last).map(|(_, tasks)| tasks) {
+                    tasks.push(task);
+                }
+            }
+        }
+
+        for tasks in calendar.values_mut() {
+            sort_by_deadline(tasks, tz);
+        }
+
+        calendar
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{DateTime, Duration, Utc};
+
+    use super::*;
+
+    // Monday 10:00
+    fn now () -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 6, 10, 0, 0).unwrap()
+    }
+
+    fn date (day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    fn todolist () -> TodoList {
+        let mut todolist = TodoList::default().with_clock(Arc::new(FixedClock::new(now())));
+
+        let tasks: Vec<(&str, Deadline, Priority)> = vec![
+            ("Yesterday", date(5).into(), Priority::Common),
+            ("This morning", (now() - Duration::hours(1)).into(), Priority::Red),
+            ("Today", date(6).into(), Priority::Green),
+            ("Today urgent", date(6).into(), Priority::Red),
+            ("Tonight", (now() + Duration::hours(9)).into(), Priority::Common),
+            ("Tomorrow", date(7).into(), Priority::Common),
+            ("Friday", date(10).into(), Priority::Common),
+            ("Next monday", date(13).into(), Priority::Common),
+            ("Next tuesday", date(14).into(), Priority::Common)
+        ];
+
+        for (title, deadline, priority) in tasks {
+            todolist.add_task(todolist.new_task(title).unwrap().deadline(deadline).priority(priority).build()).unwrap();
+        }
+
+        todolist.add_task(todolist.new_task("No deadline").unwrap().build()).unwrap();
+        todolist.add_task(todolist.new_task("Deferred").unwrap().deadline(date(6)).hidden_until(now() + Duration::hours(1)).build()).unwrap();
+        todolist.add_task(todolist.new_task("Starts tomorrow").unwrap().deadline(date(8)).scheduled(date(7)).build()).unwrap();
+        todolist.add_task(todolist.new_task("Done").unwrap().deadline(date(5)).status(Status::Completed).build()).unwrap();
+
+        todolist
+    }
+
+    fn titles (tasks: &[&Task]) -> Vec<String> {
+        tasks.iter().map(|task| task.get_title().to_owned()).collect()
+    }
+
+    #[test]
+    fn agenda_groups () {
+        let todolist = todolist();
+        let agenda = todolist.agenda(&Utc);
+
+        assert_eq!(titles(agenda.get_overdue()), vec!["Yesterday", "This morning"]);
+        assert_eq!(titles(agenda.get_today()), vec!["Tonight", "Today urgent", "Today"]);
+        assert_eq!(titles(agenda.get_tomorrow()), vec!["Tomorrow"]);
+        assert_eq!(titles(agenda.get_next_seven_days()), vec!["Friday", "Next monday"]);
+        assert_eq!(titles(agenda.get_later()), vec!["Next tuesday"]);
+    }
+
+    #[test]
+    fn agenda_in_other_timezone () {
+        let todolist = todolist();
+        // 10:00 UTC is already 01:00 on Tuesday in UTC+15
+        let agenda = todolist.agenda(&chrono::FixedOffset::east_opt(15 * 3600).unwrap());
+
+        assert_eq!(titles(agenda.get_overdue()), vec!["Yesterday", "This morning", "Today urgent", "Today"]);
+        assert_eq!(titles(agenda.get_today()), vec!["Tonight", "Tomorrow"]);
+    }
+
+    #[test]
+    fn agenda_uses_list_clock () {
+        let mut todolist = todolist();
+        let tomorrow = Arc::new(FixedClock::new(now() + Duration::days(1)));
+        todolist.add_task(Task::new("Own clock").unwrap().clock(tomorrow.clone()).deadline(now() + Duration::hours(2)).build()).unwrap();
+        todolist.add_task(Task::new("Hidden").unwrap().clock(tomorrow).deadline(date(7)).hidden_until(now() + Duration::hours(1)).build()).unwrap();
+
+        let agenda = todolist.agenda(&Utc);
+
+        assert_eq!(titles(agenda.get_overdue()), vec!["Yesterday", "This morning"]);
+        assert_eq!(titles(agenda.get_today()), vec!["Own clock", "Tonight", "Today urgent", "Today"]);
+        assert_eq!(titles(agenda.get_tomorrow()), vec!["Tomorrow"]);
+    }
+
+    #[test]
+    fn calendar () {
+        let mut todolist = todolist();
+        let start = Utc.with_ymd_and_hms(2024, 5, 8, 22, 0, 0).unwrap();
+        todolist.add_task(todolist.new_task("Offsite").unwrap().deadline(Deadline::window(start, start + Duration::days(1)).unwrap()).build()).unwrap();
+        let decade = Deadline::window(start - Duration::days(3650), start + Duration::days(3650)).unwrap();
+        todolist.add_task(todolist.new_task("Decade").unwrap().deadline(decade).build()).unwrap();
+
+        let calendar = todolist.calendar(&Utc, date(5), date(10));
+
+        assert_eq!(calendar.len(), 6);
+        assert_eq!(titles(&calendar[&date(5)]), vec!["Yesterday", "Done", "Decade"]);
+        assert_eq!(titles(&calendar[&date(7)]), vec!["Tomorrow", "Decade"]);
+        assert_eq!(titles(&calendar[&date(8)]), vec!["Starts tomorrow", "Offsite", "Decade"]);
+        assert_eq!(titles(&calendar[&date(9)]), vec!["Offsite", "Decade"]);
+        assert_eq!(titles(&calendar[&date(10)]), vec!["Friday", "Decade"]);
+        assert!(todolist.calendar(&Utc, date(10), date(5)).is_empty());
+    }
+}
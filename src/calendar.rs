@@ -0,0 +1,394 @@
+use std::collections::BTreeSet;
+use std::io;
+use std::path::Path;
+
+use chrono::{DateTime, Datelike, Days, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+
+/// Which days and hours count as working time, for business-day date math.
+///
+/// The default works Monday to Friday from 09:00 to 17:00 without holidays.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkingCalendar {
+    weekdays: Vec<Weekday>,
+    start: NaiveTime,
+    end: NaiveTime,
+    holidays: BTreeSet<NaiveDate>
+}
+
+impl Default for WorkingCalendar {
+    fn default() -> Self {
+        Self {
+            weekdays: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+            start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            holidays: BTreeSet::new()
+        }
+    }
+}
+
+impl WorkingCalendar {
+    pub fn new () -> Self {
+        Self::default()
+    }
+
+    pub fn working_days (mut self, weekdays: &[Weekday]) -> Result<Self, &'static str> {
+        if weekdays.is_empty() {
+            return Err("The calendar has no working days");
+        }
+
+        self.weekdays = weekdays.to_vec();
+        Ok(self)
+    }
+
+    /// Fails unless the working hours last at least a second.
+    pub fn working_hours (mut self, start: NaiveTime, end: NaiveTime) -> Result<Self, &'static str> {
+        if end <= start {
+            return Err("The working hours end before they start");
+        }
+        if end - start < Duration::seconds(1) {
+            return Err("The working hours last less than a second");
+        }
+
+        self.start = start;
+        self.end = end;
+        Ok(self)
+    }
+
+    pub fn holiday (mut self, date: NaiveDate) -> Self {
+        self.holidays.insert(date);
+        self
+    }
+
+    pub fn holidays (mut self, dates: impl IntoIterator<Item = NaiveDate>) -> Self {
+        self.holidays.extend(dates);
+        self
+    }
+
+    /// Adds the holidays from a file, either an iCalendar file (see
+    /// `parse_ics_holidays`) or a plain list (see `parse_holidays`).
+    pub fn load_holidays (self, path: impl AsRef<Path>) -> io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+
+        let holidays = if text.trim_start().starts_with("BEGIN:VCALENDAR") {
+            parse_ics_holidays(&text)
+        } else {
+            parse_holidays(&text)
+        };
+
+        holidays
+            .map(|holidays| self.holidays(holidays))
+            .map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))
+    }
+
+    pub fn get_working_days (&self) -> &[Weekday] {
+        &self.weekdays
+    }
+
+    pub fn get_working_hours (&self) -> (NaiveTime, NaiveTime) {
+        (self.start, self.end)
+    }
+
+    pub fn get_holidays (&self) -> &BTreeSet<NaiveDate> {
+        &self.holidays
+    }
+
+    pub fn is_holiday (&self, date: NaiveDate) -> bool {
+        self.holidays.contains(&date)
+    }
+
+    pub fn is_working_day (&self, date: NaiveDate) -> bool {
+        self.weekdays.contains(&date.weekday()) && !self.is_holiday(date)
+    }
+
+    /// Whether `at` falls on a working day within working hours.
+    pub fn is_working_time<Tz: TimeZone> (&self, at: &DateTime<Tz>) -> bool {
+        let local = at.naive_local();
+
+        self.is_working_day(local.date()) && (self.start..self.end).contains(&local.time())
+    }
+
+    /// The first working day strictly after `date`, none past the last date.
+    pub fn next_working_day (&self, date: NaiveDate) -> Option<NaiveDate> {
+        date.iter_days().skip(1).find(|day| self.is_working_day(*day))
+    }
+
+    /// Counts `days` working days after `date`: two working days after a
+    /// Friday is the Tuesday. None past the last date.
+    pub fn add_working_days (&self, date: NaiveDate, days: u32) -> Option<NaiveDate> {
+        let mut date = date;
+        let mut remaining = u64::from(days);
+
+        while remaining > 0 {
+            // whole weeks up to the next holiday hold the same working days
+            let weeks = ((remaining - 1) / self.days_per_week()).min(self.weeks_before_holiday(date));
+
+            if weeks > 0 {
+                date = date.checked_add_days(Days::new(7 * weeks))?;
+                remaining -= weeks * self.days_per_week();
+            } else {
+                date = self.next_working_day(date)?;
+                remaining -= 1;
+            }
+        }
+
+        Some(date)
+    }
+
+    /// Working days after `from` up to and including `to`, negative when `to`
+    /// is before `from`.
+    pub fn working_days_between (&self, from: NaiveDate, to: NaiveDate) -> i64 {
+        if to < from {
+            return -self.working_days_between(to, from);
+        }
+
+        from.iter_days().skip(1).take_while(|day| *day <= to).filter(|day| self.is_working_day(*day)).count() as i64
+    }
+
+    /// Adds `duration` of working time to `at`, counting only working hours
+    /// on working days in the timezone of `at`. Starting outside working
+    /// hours counts from the next start of work. None for a negative
+    /// `duration` and past the last date.
+    pub fn add_working_time<Tz: TimeZone> (&self, at: &DateTime<Tz>, duration: Duration) -> Option<DateTime<Utc>> {
+        if duration < Duration::zero() {
+            return None;
+        }
+
+        let mut local = at.naive_local();
+        let mut remaining = duration;
+        let week = (self.end - self.start) * self.days_per_week() as i32;
+
+        loop {
+            let date = local.date();
+            // a week from any time holds a week of working time, unless a holiday is in it
+            let weeks = (remaining.num_seconds().checked_div(week.num_seconds()).unwrap_or(0) - 1).max(0) as u64;
+            let weeks = weeks.min(self.weeks_before_holiday(date));
+
+            if weeks > 0 {
+                local = local.checked_add_days(Days::new(7 * weeks))?;
+                remaining -= week * weeks as i32;
+                continue;
+            }
+
+            if self.is_working_day(date) {
+                local = local.max(date.and_time(self.start));
+                let available = date.and_time(self.end) - local;
+
+                if remaining <= available {
+                    return Some(resolve(&at.timezone(), local.checked_add_signed(remaining)?));
+                }
+
+                remaining -= available.max(Duration::zero());
+            }
+
+            local = date.succ_opt()?.and_time(NaiveTime::MIN);
+        }
+    }
+
+    fn days_per_week (&self) -> u64 {
+        self.weekdays.iter().map(Weekday::num_days_from_monday).collect::<BTreeSet<_>>().len() as u64
+    }
+
+    /// The most whole weeks from `date` on, including the day they end on,
+    /// without a holiday.
+    fn weeks_before_holiday (&self, date: NaiveDate) -> u64 {
+        match self.holidays.range(date..).next() {
+            Some(holiday) => ((*holiday - date).num_days() as u64).saturating_sub(1) / 7,
+            None => u64::MAX
+        }
+    }
+}
+
+/// A local time in `tz` as UTC, moving forward over a DST gap.
+fn resolve<Tz: TimeZone> (tz: &Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    (0..=2)
+        .find_map(|hours| tz.from_local_datetime(&local.checked_add_signed(Duration::hours(hours))?).earliest())
+        .map(|at| at.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&local))
+}
+
+/// Parses one holiday per line as `YYYY-MM-DD`, optionally followed by a
+/// name. Empty lines and lines starting with `#` are skipped.
+pub fn parse_holidays (text: &str) -> Result<Vec<NaiveDate>, &'static str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let date = line.split_whitespace().next().unwrap();
+            NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| "Invalid holiday date")
+        })
+        .collect()
+}
+
+/// Reads the days covered by every `VEVENT` of an iCalendar file, from
+/// `DTSTART` up to `DTEND` (exclusive) or the start day alone. Times are
+/// dropped, recurrence rules and other properties are ignored.
+pub fn parse_ics_holidays (text: &str) -> Result<Vec<NaiveDate>, &'static str> {
+    let mut holidays = vec![];
+    let mut event: Option<(Option<NaiveDate>, Option<NaiveDate>)> = None;
+
+    for (_, line) in unfold(text) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let name = name.split(';').next().unwrap().to_ascii_uppercase();
+
+        match (name.as_str(), event.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => event = Some((None, None)),
+            ("DTSTART", Some((start, _))) => *start = Some(parse_ics_date(value)?),
+            ("DTEND", Some((_, end))) => *end = Some(parse_ics_date(value)?),
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VEVENT") => {
+                let (start, end) = event.take().unwrap();
+                let start = start.ok_or("The event has no start")?;
+
+                match end.filter(|end| *end > start) {
+                    Some(end) => holidays.extend(start.iter_days().take_while(|day| *day < end)),
+                    None => holidays.push(start)
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(holidays)
+}
+
+fn parse_ics_date (value: &str) -> Result<NaiveDate, &'static str> {
+    value.get(..8)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+        .ok_or("Invalid event date")
+}
+
+/// Joins iCalendar content lines folded over several physical lines, each
+/// with the line it starts on, counted from 1.
+pub(crate) fn unfold (text: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = vec![];
+
+    for (index, line) in text.lines().enumerate() {
+        match line.strip_prefix([' ', '\t']) {
+            Some(rest) if !lines.is_empty() => lines.last_mut().unwrap().1.push_str(rest),
+            _ => lines.push((index + 1, line.to_owned()))
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::FixedOffset;
+
+    use super::*;
+
+    fn date (day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    #[test]
+    fn working_days () {
+        let calendar = WorkingCalendar::new().holiday(date(14));
+
+        // Friday the 10th
+        assert_eq!(calendar.add_working_days(date(10), 2), Some(date(15)));
+        assert_eq!(calendar.next_working_day(date(11)), Some(date(13)));
+        assert_eq!(calendar.add_working_days(date(10), 0), Some(date(10)));
+        assert_eq!(calendar.working_days_between(date(10), date(15)), 2);
+        assert_eq!(calendar.working_days_between(date(15), date(10)), -2);
+
+        let weekend = WorkingCalendar::new().working_days(&[Weekday::Sat, Weekday::Sun]).unwrap();
+        assert_eq!(weekend.next_working_day(date(6)), Some(date(11)));
+        assert!(WorkingCalendar::new().working_days(&[]).is_err());
+    }
+
+    #[test]
+    fn working_time () {
+        let calendar = WorkingCalendar::new();
+        let east = FixedOffset::east_opt(3 * 3600).unwrap();
+        let at = |day, hour| east.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap();
+
+        assert!(calendar.is_working_time(&at(10, 9)));
+        assert!(!calendar.is_working_time(&at(10, 17)));
+        assert!(!calendar.is_working_time(&at(11, 12)));
+
+        assert_eq!(calendar.add_working_time(&at(10, 15), Duration::hours(1)), Some(at(10, 16).to_utc()));
+        // Friday 15:00 plus four working hours is Monday 11:00
+        assert_eq!(calendar.add_working_time(&at(10, 15), Duration::hours(4)), Some(at(13, 11).to_utc()));
+        // Saturday counts from Monday morning
+        assert_eq!(calendar.add_working_time(&at(11, 12), Duration::hours(8)), Some(at(13, 17).to_utc()));
+
+        assert!(WorkingCalendar::new().working_hours(at(10, 17).time(), at(10, 9).time()).is_err());
+        assert!(WorkingCalendar::new().working_hours(at(10, 9).time(), at(10, 9).time()).is_err());
+
+        let short = at(10, 9).time() + Duration::milliseconds(500);
+        assert!(WorkingCalendar::new().working_hours(at(10, 9).time(), short).is_err());
+
+        assert_eq!(calendar.add_working_time(&at(10, 15), Duration::hours(-1)), None);
+    }
+
+    #[test]
+    fn whole_weeks_match_day_by_day () {
+        let calendar = WorkingCalendar::new().holidays([date(14), date(31), date(10) + Days::new(40)]);
+        let at = Utc.with_ymd_and_hms(2024, 5, 8, 11, 30, 0).unwrap();
+
+        let mut day = date(8);
+        let mut time = at;
+
+        for count in 1..200 {
+            day = calendar.next_working_day(day).unwrap();
+            time = calendar.add_working_time(&time, Duration::hours(1)).unwrap();
+
+            assert_eq!(calendar.add_working_days(date(8), count), Some(day), "{} days", count);
+            assert_eq!(calendar.add_working_time(&at, Duration::hours(count.into())), Some(time), "{} hours", count);
+        }
+    }
+
+    #[test]
+    fn out_of_range () {
+        let calendar = WorkingCalendar::new();
+
+        assert_eq!(calendar.add_working_days(date(8), u32::MAX), None);
+        assert_eq!(calendar.add_working_time(&Utc::now(), Duration::hours(4_000_000_000)), None);
+        assert_eq!(calendar.next_working_day(NaiveDate::MAX), None);
+        assert!(calendar.add_working_days(date(8), 1_000_000).is_some());
+    }
+
+    #[test]
+    fn parse_holiday_list () {
+        let holidays = parse_holidays("# 2024\n2024-05-09 Ascension\n\n2024-05-20\n").unwrap();
+        assert_eq!(holidays, vec![date(9), date(20)]);
+        assert!(parse_holidays("May 9").is_err());
+    }
+
+    #[test]
+    fn parse_ics () {
+        let ics = "BEGIN:VCALENDAR\r\n\
+            VERSION:2.0\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART;VALUE=DATE:20240509\r\n\
+            DTEND;VALUE=DATE:20240511\r\n\
+            SUMMARY:Long\r\n  weekend\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            DTSTART:20240520T000000Z\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+
+        assert_eq!(parse_ics_holidays(ics).unwrap(), vec![date(9), date(10), date(20)]);
+        assert_eq!(unfold(ics)[5].1, "SUMMARY:Long weekend");
+        assert!(parse_ics_holidays("BEGIN:VEVENT\nDTSTART:May\nEND:VEVENT").is_err());
+    }
+
+    #[test]
+    fn load_holidays_from_file () {
+        let path = std::env::temp_dir().join(format!("todolist-holidays-{}.ics", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "BEGIN:VCALENDAR\nBEGIN:VEVENT\nDTSTART;VALUE=DATE:20240509\nEND:VEVENT\nEND:VCALENDAR\n").unwrap();
+
+        let calendar = WorkingCalendar::new().load_holidays(&path).unwrap();
+        assert!(calendar.is_holiday(date(9)));
+        assert!(!calendar.is_working_day(date(9)));
+
+        std::fs::write(&path, "2024-05-09\nnot a date\n").unwrap();
+        assert_eq!(WorkingCalendar::new().load_holidays(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,167 @@
+//! Sources of time and ids, injectable into `TodoList` and `TaskBuilder`
+//! so tests and imports can be made deterministic.
+
+use std::sync::{Arc, Mutex, OnceLock};
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+pub trait Clock: Send + Sync {
+    fn now (&self) -> DateTime<Utc>;
+}
+
+pub trait IdGenerator: Send + Sync {
+    fn next_id (&self) -> Uuid;
+}
+
+/// Wall clock time, the default.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now (&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that only moves when told to.
+pub struct FixedClock {
+    now: Mutex<DateTime<Utc>>
+}
+
+impl FixedClock {
+    pub fn new (now: DateTime<Utc>) -> Self {
+        Self { now: Mutex::new(now) }
+    }
+
+    pub fn set (&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    pub fn advance (&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
+
+impl Clock for FixedClock {
+    fn now (&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
+
+/// Random v4 uuids, the default.
+pub struct RandomIds;
+
+impl IdGenerator for RandomIds {
+    fn next_id (&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// Uuids counting up from a start value: `00000000-0000-0000-0000-000000000001`,
+/// `...0002` and so on.
+pub struct SequentialIds {
+    next: Mutex<u128>
+}
+
+impl SequentialIds {
+    pub fn new () -> Self {
+        Self::starting_at(1)
+    }
+
+    pub fn starting_at (start: u128) -> Self {
+        Self { next: Mutex::new(start) }
+    }
+}
+
+impl Default for SequentialIds {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IdGenerator for SequentialIds {
+    fn next_id (&self) -> Uuid {
+        let mut next = self.next.lock().unwrap();
+        *next += 1;
+
+        Uuid::from_u128(*next - 1)
+    }
+}
+
+/// Shared clock stored in tasks and lists. It takes no part in equality and
+/// is not serialized, deserialized values use the system clock.
+#[derive(Clone)]
+pub(crate) struct ClockHandle(pub(crate) Arc<dyn Clock>);
+
+static SYSTEM: OnceLock<Arc<dyn Clock>> = OnceLock::new();
+
+fn system () -> &'static Arc<dyn Clock> {
+    SYSTEM.get_or_init(|| Arc::new(SystemClock))
+}
+
+impl ClockHandle {
+    pub(crate) fn now (&self) -> DateTime<Utc> {
+        self.0.now()
+    }
+
+    /// Whether no clock was given, so the shared system clock is used.
+    pub(crate) fn is_default (&self) -> bool {
+        Arc::ptr_eq(&self.0, system())
+    }
+}
+
+impl Default for ClockHandle {
+    fn default() -> Self {
+        Self(system().clone())
+    }
+}
+
+impl PartialEq for ClockHandle {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct IdsHandle(pub(crate) Arc<dyn IdGenerator>);
+
+impl IdsHandle {
+    pub(crate) fn next_id (&self) -> Uuid {
+        self.0.next_id()
+    }
+}
+
+impl Default for IdsHandle {
+    fn default() -> Self {
+        Self(Arc::new(RandomIds))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn fixed_clock_moves_only_when_told () {
+        let start = Utc.with_ymd_and_hms(2024, 5, 6, 10, 0, 0).unwrap();
+        let clock = FixedClock::new(start);
+        assert_eq!(clock.now(), start);
+
+        clock.advance(Duration::minutes(5));
+        assert_eq!(clock.now(), start + Duration::minutes(5));
+
+        clock.set(start);
+        assert_eq!(clock.now(), start);
+    }
+
+    #[test]
+    fn sequential_ids () {
+        let ids = SequentialIds::new();
+
+        assert_eq!(ids.next_id().to_string(), "00000000-0000-0000-0000-000000000001");
+        assert_eq!(ids.next_id().to_string(), "00000000-0000-0000-0000-000000000002");
+        assert_eq!(SequentialIds::starting_at(255).next_id().to_string(), "00000000-0000-0000-0000-0000000000ff");
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+
+use chrono::{Duration, TimeZone};
+
+use crate::TodoList;
+
+/// How much the deadlines of a group of tasks slipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DeadlineDrift {
+    tasks: usize,
+    slipped_tasks: usize,
+    slips: usize,
+    total_slip: Duration
+}
+
+impl DeadlineDrift {
+    /// Tasks that have or had a deadline.
+    pub fn get_tasks (&self) -> usize {
+        self.tasks
+    }
+
+    /// Tasks whose deadline was moved later at least once.
+    pub fn get_slipped_tasks (&self) -> usize {
+        self.slipped_tasks
+    }
+
+    pub fn get_slips (&self) -> usize {
+        self.slips
+    }
+
+    pub fn get_total_slip (&self) -> Duration {
+        self.total_slip
+    }
+
+    /// The average slip per slipped task.
+    pub fn get_average_slip (&self) -> Duration {
+        match self.slipped_tasks {
+            0 => Duration::zero(),
+            tasks => self.total_slip / tasks as i32
+        }
+    }
+}
+
+impl TodoList {
+    /// Deadline drift per category title, all-day deadlines evaluated in
+    /// `tz`. Categories without any deadline are left out.
+    pub fn get_deadline_drift<Tz: TimeZone> (&self, tz: &Tz) -> BTreeMap<String, DeadlineDrift> {
+        let mut drift: BTreeMap<String, DeadlineDrift> = BTreeMap::new();
+
+        for task in self.tasks.iter() {
+            if !task.has_deadline() && task.get_deadline_changes().is_empty() {
+                continue;
+            }
+
+            let slips = task.get_slip_count(tz);
+            let entry = drift.entry(task.get_category().get_title().to_owned()).or_default();
+
+            entry.tasks += 1;
+            entry.slips += slips;
+            entry.total_slip += task.get_total_slip(tz);
+
+            if slips > 0 {
+                entry.slipped_tasks += 1;
+            }
+        }
+
+        drift
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::Utc;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn drift_per_category () {
+        let now = Utc.with_ymd_and_hms(2024, 5, 6, 10, 0, 0).unwrap();
+        let mut todolist = TodoList::default().with_clock(Arc::new(FixedClock::new(now)));
+        let work = Category::new("Work").unwrap();
+
+        let mut tasks = vec![
+            todolist.new_task("Report").unwrap().category(work.clone()).deadline(now).build(),
+            todolist.new_task("Review").unwrap().category(work.clone()).deadline(now).build(),
+            todolist.new_task("Slides").unwrap().category(work).deadline(now).build(),
+            todolist.new_task("Groceries").unwrap().deadline(now).build(),
+            todolist.new_task("Someday").unwrap().build()
+        ];
+
+        tasks[0].shift_deadline(Duration::days(1)).unwrap();
+        tasks[0].shift_deadline(Duration::days(2)).unwrap();
+        tasks[1].shift_deadline(Duration::hours(12)).unwrap();
+        tasks[2].shift_deadline(Duration::days(-1)).unwrap();
+        tasks[3].clear_deadline();
+
+        for task in tasks {
+            todolist.add_task(task).unwrap();
+        }
+
+        let drift = todolist.get_deadline_drift(&Utc);
+        assert_eq!(drift.len(), 2);
+
+        let work = drift["Work"];
+        assert_eq!(work.get_tasks(), 3);
+        assert_eq!(work.get_slipped_tasks(), 2);
+        assert_eq!(work.get_slips(), 3);
+        assert_eq!(work.get_total_slip(), Duration::hours(84));
+        assert_eq!(work.get_average_slip(), Duration::hours(42));
+
+        let default = drift[Category::default().get_title()];
+        assert_eq!(default.get_tasks(), 1);
+        assert_eq!(default.get_slips(), 0);
+        assert_eq!(default.get_average_slip(), Duration::zero());
+    }
+}
@@ -0,0 +1,952 @@
+//! C ABI over `TodoList` and `Task`.
+//!
+//! Every handle is an opaque pointer owned by the caller until it is passed to
+//! the matching `*_free` function (or moved into a list by `todolist_add_task`).
+//! Strings returned by getters are owned copies and must be released with
+//! `todolist_string_free`. Fallible functions return a `TodoResult` code, the
+//! message of the last failure on the current thread is available through
+//! `todolist_last_error`.
+//!
+//! The header lives in `include/todolist.h` and is regenerated with
+//! `cargo build --features ffi-header`.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::ptr;
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use uuid::Uuid;
+
+use crate::prelude::*;
+use crate::{By, TodoList};
+
+/// Result code returned by every fallible function.
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TodoResult {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidUtf8 = 2,
+    InvalidArgument = 3,
+    NotFound = 4,
+    AlreadyExists = 5
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TodoPriority {
+    Common = 0,
+    Green = 1,
+    Yellow = 2,
+    Red = 3
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TodoStatus {
+    Created = 0,
+    Progress = 1,
+    Completed = 2,
+    Archived = 3
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TodoDeadlineKind {
+    None = 0,
+    /// All-day deadline, read with `todolist_task_deadline_date`.
+    Date = 1,
+    DateTime = 2,
+    /// Read with `todolist_task_deadline_window`.
+    Window = 3
+}
+
+/// Snapshot of the tasks matched by a filter.
+///
+/// The pointers it yields borrow from the list and are only valid while the
+/// list is alive and not modified.
+pub struct TodoTaskIter {
+    tasks: Vec<*const Task>,
+    position: usize
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error (message: &str) {
+    LAST_ERROR.with(|cell| *cell.borrow_mut() = CString::new(message).ok());
+}
+
+fn fail (code: TodoResult, message: &str) -> TodoResult {
+    set_last_error(message);
+    code
+}
+
+impl From<Priority> for TodoPriority {
+    fn from(priority: Priority) -> Self {
+        match priority {
+            Priority::Common => Self::Common,
+            Priority::Green => Self::Green,
+            Priority::Yellow => Self::Yellow,
+            Priority::Red => Self::Red
+        }
+    }
+}
+
+impl From<Status> for TodoStatus {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::Created => Self::Created,
+            Status::Progress => Self::Progress,
+            Status::Completed => Self::Completed,
+            Status::Archived => Self::Archived
+        }
+    }
+}
+
+fn priority_from_raw (raw: i32) -> Option<Priority> {
+    match raw {
+        0 => Some(Priority::Common),
+        1 => Some(Priority::Green),
+        2 => Some(Priority::Yellow),
+        3 => Some(Priority::Red),
+        _ => None
+    }
+}
+
+fn status_from_raw (raw: i32) -> Option<Status> {
+    match raw {
+        0 => Some(Status::Created),
+        1 => Some(Status::Progress),
+        2 => Some(Status::Completed),
+        3 => Some(Status::Archived),
+        _ => None
+    }
+}
+
+/// Borrows a C string as `&str`, recording the failure reason.
+unsafe fn read_str<'a> (value: *const c_char) -> Result<&'a str, TodoResult> {
+    if value.is_null() {
+        return Err(fail(TodoResult::NullPointer, "string argument is null"));
+    }
+
+    CStr::from_ptr(value).to_str().map_err(|_| fail(TodoResult::InvalidUtf8, "string argument is not valid UTF-8"))
+}
+
+unsafe fn read_id (id: *const c_char) -> Result<Uuid, TodoResult> {
+    Uuid::parse_str(read_str(id)?).map_err(|_| fail(TodoResult::InvalidArgument, "id is not a valid uuid"))
+}
+
+fn owned_string (value: &str) -> *mut c_char {
+    // Interior NUL bytes cannot be represented in a C string, drop them.
+    CString::new(value.replace('\0', "")).unwrap_or_default().into_raw()
+}
+
+macro_rules! try_ffi {
+    ($expr:expr) => {
+        match $expr {
+            Ok(value) => value,
+            Err(code) => return code
+        }
+    };
+}
+
+macro_rules! deref_or {
+    ($ptr:expr, $default:expr) => {
+        match $ptr.as_ref() {
+            Some(value) => value,
+            None => {
+                set_last_error("handle is null");
+                return $default;
+            }
+        }
+    };
+}
+
+macro_rules! deref_mut_or {
+    ($ptr:expr, $default:expr) => {
+        match $ptr.as_mut() {
+            Some(value) => value,
+            None => {
+                set_last_error("handle is null");
+                return $default;
+            }
+        }
+    };
+}
+
+/// Returns the message of the last failed call on this thread, or null.
+/// The string must be released with `todolist_string_free`.
+#[no_mangle]
+pub extern "C" fn todolist_last_error () -> *mut c_char {
+    LAST_ERROR.with(|cell| match cell.borrow().as_ref() {
+        Some(message) => message.clone().into_raw(),
+        None => ptr::null_mut()
+    })
+}
+
+/// # Safety
+/// `value` must be null or a string returned by this library.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_string_free (value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn todolist_new () -> *mut TodoList {
+    Box::into_raw(Box::default())
+}
+
+/// # Safety
+/// `list` must be null or a handle returned by `todolist_new`.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_free (list: *mut TodoList) {
+    if !list.is_null() {
+        drop(Box::from_raw(list));
+    }
+}
+
+/// # Safety
+/// `list` must be a valid list handle.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_count (list: *const TodoList) -> usize {
+    deref_or!(list, 0).get_tasks().len()
+}
+
+/// Borrows the task at `index`, or returns null when out of range.
+///
+/// # Safety
+/// `list` must be a valid list handle.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_at (list: *const TodoList, index: usize) -> *const Task {
+    match deref_or!(list, ptr::null()).get_tasks().get(index) {
+        Some(task) => task,
+        None => ptr::null()
+    }
+}
+
+/// Borrows the task with the given id for modification, or returns null.
+///
+/// # Safety
+/// `list` must be a valid list handle and `id` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_by_id (list: *mut TodoList, id: *const c_char) -> *mut Task {
+    let list = deref_mut_or!(list, ptr::null_mut());
+    let id = match read_id(id) {
+        Ok(id) => id,
+        Err(_) => return ptr::null_mut()
+    };
+
+    match list.get_task_mut(&id) {
+        Some(task) => task,
+        None => {
+            set_last_error("The task is not exists");
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Moves `task` into the list. On success the task handle must not be freed
+/// or used again; on failure ownership stays with the caller.
+///
+/// # Safety
+/// `list` must be a valid list handle and `task` a handle from `todolist_task_new`.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_add_task (list: *mut TodoList, task: *mut Task) -> TodoResult {
+    let list = deref_mut_or!(list, TodoResult::NullPointer);
+    let id = *deref_or!(task, TodoResult::NullPointer).get_id();
+
+    if list.get_task(&id).is_some() {
+        return fail(TodoResult::AlreadyExists, "The task is already exists");
+    }
+
+    match list.add_task(*Box::from_raw(task)) {
+        Ok(()) => TodoResult::Ok,
+        Err(message) => fail(TodoResult::AlreadyExists, message)
+    }
+}
+
+/// # Safety
+/// `list` must be a valid list handle and `id` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_remove_task (list: *mut TodoList, id: *const c_char) -> TodoResult {
+    let list = deref_mut_or!(list, TodoResult::NullPointer);
+    let id = try_ffi!(read_id(id));
+
+    match list.remove_task_by_id(&id) {
+        Ok(_) => TodoResult::Ok,
+        Err(message) => fail(TodoResult::NotFound, message)
+    }
+}
+
+/// # Safety
+/// `list` must be a valid list handle and `title` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_new_category (list: *mut TodoList, title: *const c_char) -> TodoResult {
+    let list = deref_mut_or!(list, TodoResult::NullPointer);
+    let title = try_ffi!(read_str(title));
+
+    if let Err(message) = Category::new(title) {
+        return fail(TodoResult::InvalidArgument, message);
+    }
+
+    match list.new_category(title) {
+        Ok(()) => TodoResult::Ok,
+        Err(message) => fail(TodoResult::AlreadyExists, message)
+    }
+}
+
+/// # Safety
+/// `list` must be a valid list handle and `title` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_new_tag (list: *mut TodoList, title: *const c_char) -> TodoResult {
+    let list = deref_mut_or!(list, TodoResult::NullPointer);
+    let title = try_ffi!(read_str(title));
+
+    if let Err(message) = Tag::new(title) {
+        return fail(TodoResult::InvalidArgument, message);
+    }
+
+    match list.new_tag(title) {
+        Ok(()) => TodoResult::Ok,
+        Err(message) => fail(TodoResult::AlreadyExists, message)
+    }
+}
+
+fn iter_from (tasks: Vec<&Task>) -> *mut TodoTaskIter {
+    Box::into_raw(Box::new(TodoTaskIter {
+        tasks: tasks.into_iter().map(|task| task as *const Task).collect(),
+        position: 0
+    }))
+}
+
+/// # Safety
+/// `list` must be a valid list handle.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_tasks_all (list: *const TodoList) -> *mut TodoTaskIter {
+    iter_from(deref_or!(list, ptr::null_mut()).get_tasks().iter().collect())
+}
+
+/// # Safety
+/// `list` must be a valid list handle and `title` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_tasks_by_category (list: *const TodoList, title: *const c_char) -> *mut TodoTaskIter {
+    let list = deref_or!(list, ptr::null_mut());
+    let category = match read_str(title).map(Category::new) {
+        Ok(Ok(category)) => category,
+        Ok(Err(message)) => {
+            set_last_error(message);
+            return ptr::null_mut();
+        }
+        Err(_) => return ptr::null_mut()
+    };
+
+    iter_from(list.get_tasks_by(By::Category(&category)))
+}
+
+/// # Safety
+/// `list` must be a valid list handle and `title` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_tasks_by_tag (list: *const TodoList, title: *const c_char) -> *mut TodoTaskIter {
+    let list = deref_or!(list, ptr::null_mut());
+    let tag = match read_str(title).map(Tag::new) {
+        Ok(Ok(tag)) => tag,
+        Ok(Err(message)) => {
+            set_last_error(message);
+            return ptr::null_mut();
+        }
+        Err(_) => return ptr::null_mut()
+    };
+
+    iter_from(list.get_tasks_by(By::Tag(&tag)))
+}
+
+/// `priority` is one of the `TodoPriority` values.
+///
+/// # Safety
+/// `list` must be a valid list handle.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_tasks_by_priority (list: *const TodoList, priority: i32) -> *mut TodoTaskIter {
+    let list = deref_or!(list, ptr::null_mut());
+
+    match priority_from_raw(priority) {
+        Some(priority) => iter_from(list.get_tasks_by(By::Priority(priority))),
+        None => {
+            set_last_error("unknown priority");
+            ptr::null_mut()
+        }
+    }
+}
+
+/// `status` is one of the `TodoStatus` values.
+///
+/// # Safety
+/// `list` must be a valid list handle.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_tasks_by_status (list: *const TodoList, status: i32) -> *mut TodoTaskIter {
+    let list = deref_or!(list, ptr::null_mut());
+
+    match status_from_raw(status) {
+        Some(status) => iter_from(list.get_tasks_by(By::Status(status))),
+        None => {
+            set_last_error("unknown status");
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Returns the next matched task, or null once the iterator is exhausted.
+///
+/// # Safety
+/// `iter` must be a valid iterator handle whose list is still alive and unmodified.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_iter_next (iter: *mut TodoTaskIter) -> *const Task {
+    let iter = deref_mut_or!(iter, ptr::null());
+
+    match iter.tasks.get(iter.position) {
+        Some(task) => {
+            iter.position += 1;
+            *task
+        }
+        None => ptr::null()
+    }
+}
+
+/// # Safety
+/// `iter` must be null or a valid iterator handle.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_iter_len (iter: *const TodoTaskIter) -> usize {
+    deref_or!(iter, 0).tasks.len()
+}
+
+/// # Safety
+/// `iter` must be null or a handle returned by one of the `todolist_tasks_*` functions.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_iter_free (iter: *mut TodoTaskIter) {
+    if !iter.is_null() {
+        drop(Box::from_raw(iter));
+    }
+}
+
+/// Creates a standalone task and stores its handle in `out`.
+///
+/// # Safety
+/// `title` must be a NUL-terminated string and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_new (title: *const c_char, out: *mut *mut Task) -> TodoResult {
+    if out.is_null() {
+        return fail(TodoResult::NullPointer, "output pointer is null");
+    }
+
+    match Task::new(try_ffi!(read_str(title))) {
+        Ok(builder) => {
+            *out = Box::into_raw(Box::new(builder.build()));
+            TodoResult::Ok
+        }
+        Err(message) => fail(TodoResult::InvalidArgument, message)
+    }
+}
+
+/// # Safety
+/// `task` must be null or a standalone handle from `todolist_task_new`,
+/// never a pointer borrowed from a list.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_free (task: *mut Task) {
+    if !task.is_null() {
+        drop(Box::from_raw(task));
+    }
+}
+
+/// # Safety
+/// `task` must be a valid task pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_id (task: *const Task) -> *mut c_char {
+    owned_string(&deref_or!(task, ptr::null_mut()).get_id().to_string())
+}
+
+/// # Safety
+/// `task` must be a valid task pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_title (task: *const Task) -> *mut c_char {
+    owned_string(deref_or!(task, ptr::null_mut()).get_title())
+}
+
+/// # Safety
+/// `task` must be a valid task pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_description (task: *const Task) -> *mut c_char {
+    owned_string(deref_or!(task, ptr::null_mut()).get_description())
+}
+
+/// # Safety
+/// `task` must be a valid task pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_category (task: *const Task) -> *mut c_char {
+    owned_string(deref_or!(task, ptr::null_mut()).get_category().get_title())
+}
+
+/// Returns the tags joined by a single space, without the leading `#`.
+///
+/// # Safety
+/// `task` must be a valid task pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_tags (task: *const Task) -> *mut c_char {
+    let task = deref_or!(task, ptr::null_mut());
+    let tags: Vec<&str> = task.get_tags().iter().map(|tag| tag.get_title()).collect();
+
+    owned_string(&tags.join(" "))
+}
+
+/// # Safety
+/// `task` must be a valid task pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_priority (task: *const Task) -> TodoPriority {
+    (*deref_or!(task, TodoPriority::Common).get_priority()).into()
+}
+
+/// # Safety
+/// `task` must be a valid task pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_status (task: *const Task) -> TodoStatus {
+    (*deref_or!(task, TodoStatus::Created).get_status()).into()
+}
+
+/// Unix timestamp in seconds.
+///
+/// # Safety
+/// `task` must be a valid task pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_created_at (task: *const Task) -> i64 {
+    deref_or!(task, 0).get_created_at().timestamp()
+}
+
+/// Unix timestamp in seconds.
+///
+/// # Safety
+/// `task` must be a valid task pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_updated_at (task: *const Task) -> i64 {
+    deref_or!(task, 0).get_updated_at().timestamp()
+}
+
+/// # Safety
+/// `task` must be a valid task pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_deadline_kind (task: *const Task) -> TodoDeadlineKind {
+    match deref_or!(task, TodoDeadlineKind::None).get_deadline() {
+        None => TodoDeadlineKind::None,
+        Some(Deadline::Date(_)) => TodoDeadlineKind::Date,
+        Some(Deadline::DateTime(_)) => TodoDeadlineKind::DateTime,
+        Some(Deadline::Window { .. }) => TodoDeadlineKind::Window
+    }
+}
+
+/// Stores the deadline as a unix timestamp in `out` (the end of a window),
+/// `NotFound` if the task has no deadline and `InvalidArgument` if it is an
+/// all-day deadline.
+///
+/// # Safety
+/// `task` must be a valid task pointer and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_deadline (task: *const Task, out: *mut i64) -> TodoResult {
+    let task = deref_or!(task, TodoResult::NullPointer);
+
+    if out.is_null() {
+        return fail(TodoResult::NullPointer, "output pointer is null");
+    }
+
+    match task.get_deadline() {
+        Some(Deadline::DateTime(at)) | Some(Deadline::Window { end: at, .. }) => {
+            *out = at.timestamp();
+            TodoResult::Ok
+        }
+        Some(Deadline::Date(_)) => fail(TodoResult::InvalidArgument, "the task has an all-day deadline"),
+        None => fail(TodoResult::NotFound, "the task has no deadline")
+    }
+}
+
+/// Stores the start and end of a window deadline as unix timestamps.
+///
+/// # Safety
+/// `task` must be a valid task pointer, `start` and `end` valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_deadline_window (task: *const Task, start: *mut i64, end: *mut i64) -> TodoResult {
+    let task = deref_or!(task, TodoResult::NullPointer);
+
+    if start.is_null() || end.is_null() {
+        return fail(TodoResult::NullPointer, "output pointer is null");
+    }
+
+    match task.get_deadline() {
+        Some(Deadline::Window { start: from, end: to }) => {
+            *start = from.timestamp();
+            *end = to.timestamp();
+            TodoResult::Ok
+        }
+        Some(_) => fail(TodoResult::InvalidArgument, "the deadline is not a window"),
+        None => fail(TodoResult::NotFound, "the task has no deadline")
+    }
+}
+
+/// Stores the day of an all-day deadline.
+///
+/// # Safety
+/// `task` must be a valid task pointer, `year`, `month` and `day` valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_deadline_date (task: *const Task, year: *mut i32, month: *mut u32, day: *mut u32) -> TodoResult {
+    let task = deref_or!(task, TodoResult::NullPointer);
+
+    if year.is_null() || month.is_null() || day.is_null() {
+        return fail(TodoResult::NullPointer, "output pointer is null");
+    }
+
+    match task.get_deadline() {
+        Some(Deadline::Date(date)) => {
+            *year = date.year();
+            *month = date.month();
+            *day = date.day();
+            TodoResult::Ok
+        }
+        Some(_) => fail(TodoResult::InvalidArgument, "the deadline is not an all-day deadline"),
+        None => fail(TodoResult::NotFound, "the task has no deadline")
+    }
+}
+
+/// # Safety
+/// `task` must be a valid mutable task pointer and `title` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_set_title (task: *mut Task, title: *const c_char) -> TodoResult {
+    let task = deref_mut_or!(task, TodoResult::NullPointer);
+
+    match task.set_title(try_ffi!(read_str(title))) {
+        Ok(()) => TodoResult::Ok,
+        Err(message) => fail(TodoResult::InvalidArgument, message)
+    }
+}
+
+/// # Safety
+/// `task` must be a valid mutable task pointer and `description` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_set_description (task: *mut Task, description: *const c_char) -> TodoResult {
+    let task = deref_mut_or!(task, TodoResult::NullPointer);
+    task.set_description(try_ffi!(read_str(description)));
+
+    TodoResult::Ok
+}
+
+/// # Safety
+/// `task` must be a valid mutable task pointer and `title` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_set_category (task: *mut Task, title: *const c_char) -> TodoResult {
+    let task = deref_mut_or!(task, TodoResult::NullPointer);
+
+    match Category::new(try_ffi!(read_str(title))) {
+        Ok(category) => {
+            task.set_category(category);
+            TodoResult::Ok
+        }
+        Err(message) => fail(TodoResult::InvalidArgument, message)
+    }
+}
+
+/// # Safety
+/// `task` must be a valid mutable task pointer and `title` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_add_tag (task: *mut Task, title: *const c_char) -> TodoResult {
+    let task = deref_mut_or!(task, TodoResult::NullPointer);
+    let tag = match Tag::new(try_ffi!(read_str(title))) {
+        Ok(tag) => tag,
+        Err(message) => return fail(TodoResult::InvalidArgument, message)
+    };
+
+    match task.add_tag(tag) {
+        Ok(()) => TodoResult::Ok,
+        Err(message) => fail(TodoResult::AlreadyExists, message)
+    }
+}
+
+/// # Safety
+/// `task` must be a valid mutable task pointer and `title` a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_remove_tag (task: *mut Task, title: *const c_char) -> TodoResult {
+    let task = deref_mut_or!(task, TodoResult::NullPointer);
+
+    match Tag::new(try_ffi!(read_str(title))) {
+        Ok(tag) => {
+            task.remove_tag(tag);
+            TodoResult::Ok
+        }
+        Err(message) => fail(TodoResult::InvalidArgument, message)
+    }
+}
+
+/// `priority` is one of the `TodoPriority` values.
+///
+/// # Safety
+/// `task` must be a valid mutable task pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_set_priority (task: *mut Task, priority: i32) -> TodoResult {
+    let task = deref_mut_or!(task, TodoResult::NullPointer);
+
+    match priority_from_raw(priority) {
+        Some(priority) => {
+            task.set_priority(priority);
+            TodoResult::Ok
+        }
+        None => fail(TodoResult::InvalidArgument, "unknown priority")
+    }
+}
+
+/// `status` is one of the `TodoStatus` values.
+///
+/// # Safety
+/// `task` must be a valid mutable task pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_set_status (task: *mut Task, status: i32) -> TodoResult {
+    let task = deref_mut_or!(task, TodoResult::NullPointer);
+
+    match status_from_raw(status) {
+        Some(status) => {
+            task.set_status(status);
+            TodoResult::Ok
+        }
+        None => fail(TodoResult::InvalidArgument, "unknown status")
+    }
+}
+
+/// `deadline` is a unix timestamp in seconds.
+///
+/// # Safety
+/// `task` must be a valid mutable task pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_set_deadline (task: *mut Task, deadline: i64) -> TodoResult {
+    let task = deref_mut_or!(task, TodoResult::NullPointer);
+
+    match DateTime::<Utc>::from_timestamp(deadline, 0) {
+        Some(deadline) => {
+            task.set_deadline(deadline);
+            TodoResult::Ok
+        }
+        None => fail(TodoResult::InvalidArgument, "deadline is out of range")
+    }
+}
+
+/// # Safety
+/// `task` must be a valid mutable task pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_set_deadline_date (task: *mut Task, year: i32, month: u32, day: u32) -> TodoResult {
+    let task = deref_mut_or!(task, TodoResult::NullPointer);
+
+    match NaiveDate::from_ymd_opt(year, month, day) {
+        Some(date) => {
+            task.set_deadline(date);
+            TodoResult::Ok
+        }
+        None => fail(TodoResult::InvalidArgument, "invalid date")
+    }
+}
+
+/// `start` and `end` are unix timestamps in seconds.
+///
+/// # Safety
+/// `task` must be a valid mutable task pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_set_deadline_window (task: *mut Task, start: i64, end: i64) -> TodoResult {
+    let task = deref_mut_or!(task, TodoResult::NullPointer);
+
+    let (Some(start), Some(end)) = (DateTime::<Utc>::from_timestamp(start, 0), DateTime::<Utc>::from_timestamp(end, 0)) else {
+        return fail(TodoResult::InvalidArgument, "deadline is out of range");
+    };
+
+    match Deadline::window(start, end) {
+        Ok(deadline) => {
+            task.set_deadline(deadline);
+            TodoResult::Ok
+        }
+        Err(message) => fail(TodoResult::InvalidArgument, message)
+    }
+}
+
+/// # Safety
+/// `task` must be a valid mutable task pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_clear_deadline (task: *mut Task) -> TodoResult {
+    let task = deref_mut_or!(task, TodoResult::NullPointer);
+    task.clear_deadline();
+
+    TodoResult::Ok
+}
+
+/// Moves the deadline by `seconds`, whole days for an all-day deadline.
+///
+/// # Safety
+/// `task` must be a valid mutable task pointer.
+#[no_mangle]
+pub unsafe extern "C" fn todolist_task_shift_deadline (task: *mut Task, seconds: i64) -> TodoResult {
+    let task = deref_mut_or!(task, TodoResult::NullPointer);
+
+    let Some(by) = chrono::Duration::try_seconds(seconds) else {
+        return fail(TodoResult::InvalidArgument, "shift is out of range");
+    };
+
+    if task.get_deadline().is_none() {
+        return fail(TodoResult::NotFound, "The task has no deadline");
+    }
+
+    match task.shift_deadline(by) {
+        Ok(()) => TodoResult::Ok,
+        Err(message) => fail(TodoResult::InvalidArgument, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c (value: &str) -> CString {
+        CString::new(value).unwrap()
+    }
+
+    unsafe fn take (value: *mut c_char) -> String {
+        let result = CStr::from_ptr(value).to_str().unwrap().to_owned();
+        todolist_string_free(value);
+        result
+    }
+
+    #[test]
+    fn create_task_and_read_back () {
+        unsafe {
+            let mut task = ptr::null_mut();
+            assert_eq!(todolist_task_new(c("  First ").as_ptr(), &mut task), TodoResult::Ok);
+
+            assert_eq!(todolist_task_set_priority(task, 3), TodoResult::Ok);
+            assert_eq!(todolist_task_add_tag(task, c("Rust").as_ptr()), TodoResult::Ok);
+            assert_eq!(todolist_task_add_tag(task, c("code").as_ptr()), TodoResult::Ok);
+
+            assert_eq!(take(todolist_task_title(task)), "First");
+            assert_eq!(take(todolist_task_tags(task)), "Rust code");
+            assert_eq!(todolist_task_priority(task), TodoPriority::Red);
+
+            let mut deadline = 0;
+            assert_eq!(todolist_task_deadline(task, &mut deadline), TodoResult::NotFound);
+            assert_eq!(todolist_task_set_deadline(task, 1_700_000_000), TodoResult::Ok);
+            assert_eq!(todolist_task_deadline(task, &mut deadline), TodoResult::Ok);
+            assert_eq!(deadline, 1_700_000_000);
+            assert_eq!(todolist_task_deadline_kind(task), TodoDeadlineKind::DateTime);
+
+            assert_eq!(todolist_task_shift_deadline(task, 3600), TodoResult::Ok);
+            assert_eq!(todolist_task_deadline(task, &mut deadline), TodoResult::Ok);
+            assert_eq!(deadline, 1_700_003_600);
+            assert_eq!(todolist_task_shift_deadline(task, i64::MAX / 1000), TodoResult::InvalidArgument);
+            assert_eq!(todolist_task_clear_deadline(task), TodoResult::Ok);
+            assert_eq!(todolist_task_deadline_kind(task), TodoDeadlineKind::None);
+            assert_eq!(todolist_task_shift_deadline(task, 3600), TodoResult::NotFound);
+
+            let (mut year, mut month, mut day) = (0, 0, 0);
+            assert_eq!(todolist_task_set_deadline_date(task, 2024, 2, 30), TodoResult::InvalidArgument);
+            assert_eq!(todolist_task_set_deadline_date(task, 2024, 5, 10), TodoResult::Ok);
+            assert_eq!(todolist_task_deadline(task, &mut deadline), TodoResult::InvalidArgument);
+            assert_eq!(todolist_task_deadline_date(task, &mut year, &mut month, &mut day), TodoResult::Ok);
+            assert_eq!((year, month, day), (2024, 5, 10));
+
+            let (mut start, mut end) = (0, 0);
+            assert_eq!(todolist_task_set_deadline_window(task, 20, 10), TodoResult::InvalidArgument);
+            assert_eq!(todolist_task_set_deadline_window(task, 10, 20), TodoResult::Ok);
+            assert_eq!(todolist_task_deadline_window(task, &mut start, &mut end), TodoResult::Ok);
+            assert_eq!((start, end), (10, 20));
+
+            todolist_task_free(task);
+        }
+    }
+
+    #[test]
+    fn errors_are_reported () {
+        unsafe {
+            let mut task = ptr::null_mut();
+            assert_eq!(todolist_task_new(c("  ").as_ptr(), &mut task), TodoResult::InvalidArgument);
+            assert!(task.is_null());
+            assert_eq!(take(todolist_last_error()), "Title is empty.");
+
+            assert_eq!(todolist_task_new(ptr::null(), &mut task), TodoResult::NullPointer);
+            assert_eq!(todolist_task_set_status(ptr::null_mut(), 1), TodoResult::NullPointer);
+        }
+    }
+
+    #[test]
+    fn list_filter_iteration () {
+        unsafe {
+            let list = todolist_new();
+
+            for (title, priority) in [("First", 3), ("Second", 0), ("Third", 3)] {
+                let mut task = ptr::null_mut();
+                todolist_task_new(c(title).as_ptr(), &mut task);
+                todolist_task_set_priority(task, priority);
+                assert_eq!(todolist_add_task(list, task), TodoResult::Ok);
+            }
+
+            assert_eq!(todolist_task_count(list), 3);
+
+            let iter = todolist_tasks_by_priority(list, 3);
+            assert_eq!(todolist_iter_len(iter), 2);
+
+            let mut titles = vec![];
+            loop {
+                let task = todolist_iter_next(iter);
+                if task.is_null() {
+                    break;
+                }
+                titles.push(take(todolist_task_title(task)));
+            }
+            todolist_iter_free(iter);
+
+            assert_eq!(titles, vec!["First", "Third"]);
+            assert!(todolist_tasks_by_priority(list, 9).is_null());
+
+            todolist_free(list);
+        }
+    }
+
+    #[test]
+    fn modify_and_remove_task_in_list () {
+        unsafe {
+            let list = todolist_new();
+            let mut task = ptr::null_mut();
+            todolist_task_new(c("First").as_ptr(), &mut task);
+            todolist_add_task(list, task);
+
+            let id = take(todolist_task_id(todolist_task_at(list, 0)));
+            let task = todolist_task_by_id(list, c(&id).as_ptr());
+            assert_eq!(todolist_task_set_status(task, 2), TodoResult::Ok);
+            assert_eq!(todolist_task_status(todolist_task_at(list, 0)), TodoStatus::Completed);
+
+            assert_eq!(todolist_remove_task(list, c(&id).as_ptr()), TodoResult::Ok);
+            assert_eq!(todolist_remove_task(list, c(&id).as_ptr()), TodoResult::NotFound);
+            assert_eq!(todolist_remove_task(list, c("nope").as_ptr()), TodoResult::InvalidArgument);
+            assert_eq!(todolist_task_count(list), 0);
+
+            todolist_free(list);
+        }
+    }
+
+    #[test]
+    fn duplicate_category_and_tag () {
+        unsafe {
+            let list = todolist_new();
+
+            assert_eq!(todolist_new_category(list, c("Work").as_ptr()), TodoResult::Ok);
+            assert_eq!(todolist_new_category(list, c("Work").as_ptr()), TodoResult::AlreadyExists);
+            assert_eq!(todolist_new_category(list, c(" Work").as_ptr()), TodoResult::AlreadyExists);
+            assert_eq!(todolist_new_category(list, c(" ").as_ptr()), TodoResult::InvalidArgument);
+
+            assert_eq!(todolist_new_tag(list, c("rust").as_ptr()), TodoResult::Ok);
+            assert_eq!(todolist_new_tag(list, c("rust").as_ptr()), TodoResult::AlreadyExists);
+            assert_eq!(todolist_new_tag(list, c("rust ").as_ptr()), TodoResult::AlreadyExists);
+            assert_eq!(todolist_new_tag(list, c("").as_ptr()), TodoResult::InvalidArgument);
+
+            todolist_free(list);
+        }
+    }
+}
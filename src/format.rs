@@ -0,0 +1,122 @@
+//! Reading and writing task lists in the formats of other tools. Every
+//! reader implements `TodoListLoad`, so a list is imported with
+//! `TodoList::init`. The modules of formats that can be written have an
+//! `export` function, the export files of Trello, Todoist and GitHub are only
+//! read.
+
+pub mod csv;
+pub mod github;
+pub mod ical;
+pub mod markdown;
+pub mod org;
+pub mod taskwarrior;
+pub mod todoist;
+pub mod todotxt;
+pub mod trello;
+
+use uuid::Uuid;
+
+use crate::prelude::*;
+use crate::TodoList;
+
+/// The id of a task imported from the record `name` of another tool, the
+/// same on every import so a new export updates the tasks of the last one.
+fn stable_id (name: &str) -> Uuid {
+    Uuid::new_v5(&Uuid::NAMESPACE_URL, name.as_bytes())
+}
+
+/// Categories and tags used by `tasks`, each once, in order of appearance.
+fn collect_registries (tasks: &[Task]) -> (Vec<Category>, Vec<Tag>) {
+    let mut categories: Vec<Category> = vec![];
+    let mut tags: Vec<Tag> = vec![];
+
+    for task in tasks {
+        if !categories.contains(task.get_category()) {
+            categories.push(task.get_category().clone());
+        }
+
+        for tag in task.get_tags() {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+    }
+
+    (categories, tags)
+}
+
+/// What importing a set of tasks would change in a `TodoList`, matched by id.
+/// Only the fields the format reads are compared.
+pub struct ImportPreview<'a> {
+    created: Vec<&'a Task>,
+    updated: Vec<&'a Task>,
+    unchanged: Vec<&'a Task>
+}
+
+impl<'a> ImportPreview<'a> {
+    /// Compares the `fields` of `tasks` with the listed tasks.
+    pub fn new (tasks: &'a [Task], fields: &[TaskField], todolist: &TodoList) -> Self {
+        let mut preview = Self { created: vec![], updated: vec![], unchanged: vec![] };
+
+        for task in tasks {
+            match todolist.get_task(task.get_id()) {
+                None => preview.created.push(task),
+                Some(existing) if existing.same_fields(task, fields) => preview.unchanged.push(task),
+                Some(_) => preview.updated.push(task)
+            }
+        }
+
+        preview
+    }
+
+    /// Tasks not in the list yet.
+    pub fn get_created (&self) -> &[&'a Task] {
+        &self.created
+    }
+
+    /// Tasks in the list with other values, these would be copied to the listed ones.
+    pub fn get_updated (&self) -> &[&'a Task] {
+        &self.updated
+    }
+
+    pub fn get_unchanged (&self) -> &[&'a Task] {
+        &self.unchanged
+    }
+
+    pub fn is_empty (&self) -> bool {
+        self.created.is_empty() && self.updated.is_empty()
+    }
+}
+
+/// Adds the new `tasks` to `todolist` and copies the `fields` of the others
+/// to the listed tasks with the same id. Fields the format doesn't read, like
+/// reminders or the history, are kept. Categories and tags the list doesn't
+/// have yet are added to it, as `TodoList::init` does. Returns how many
+/// tasks were added or updated.
+pub fn apply (tasks: Vec<Task>, fields: &[TaskField], todolist: &mut TodoList) -> usize {
+    let mut changed = 0;
+
+    let (categories, tags) = collect_registries(&tasks);
+    for category in categories {
+        if !todolist.categories.contains(&category) {
+            todolist.categories.push(category);
+        }
+    }
+    for tag in tags {
+        if !todolist.tags.contains(&tag) {
+            todolist.tags.push(tag);
+        }
+    }
+
+    for task in tasks {
+        match todolist.get_task_mut(task.get_id()) {
+            Some(existing) if existing.same_fields(&task, fields) => continue,
+            Some(existing) => existing.import_fields(&task, fields),
+            None => todolist.add_task(task).unwrap()
+        }
+
+        changed += 1;
+    }
+
+    changed
+}
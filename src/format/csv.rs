@@ -0,0 +1,520 @@
+//! Comma separated values (RFC 4180), one task per row.
+//!
+//! Values are written as: ids as uuids, statuses and priorities by name
+//! (`Progress`, `Red`), tags joined by spaces, times in RFC 3339 and
+//! deadlines as `2024-05-10`, `2024-05-10T17:00:00Z` or `start/end` for a
+//! window. Importing also accepts tags separated by commas, any case for
+//! statuses and priorities and plain dates for times.
+
+use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use uuid::Uuid;
+
+use crate::prelude::*;
+use crate::prelude::load::TodoListLoad;
+use crate::TodoList;
+
+use super::ImportPreview;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Id,
+    Title,
+    Description,
+    Status,
+    Priority,
+    Category,
+    Tags,
+    CreatedAt,
+    UpdatedAt,
+    Deadline
+}
+
+impl Column {
+    pub const ALL: [Column; 10] = [
+        Self::Id, Self::Title, Self::Description, Self::Status, Self::Priority,
+        Self::Category, Self::Tags, Self::CreatedAt, Self::UpdatedAt, Self::Deadline
+    ];
+
+    /// The header name, `created_at` for `CreatedAt`.
+    pub fn name (&self) -> &'static str {
+        match self {
+            Self::Id => "id",
+            Self::Title => "title",
+            Self::Description => "description",
+            Self::Status => "status",
+            Self::Priority => "priority",
+            Self::Category => "category",
+            Self::Tags => "tags",
+            Self::CreatedAt => "created_at",
+            Self::UpdatedAt => "updated_at",
+            Self::Deadline => "deadline"
+        }
+    }
+
+    pub fn from_name (name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|column| column.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// The task field the column fills, none for `Id`, which matches tasks.
+    pub fn get_field (&self) -> Option<TaskField> {
+        match self {
+            Self::Id => None,
+            Self::Title => Some(TaskField::Title),
+            Self::Description => Some(TaskField::Description),
+            Self::Status => Some(TaskField::Status),
+            Self::Priority => Some(TaskField::Priority),
+            Self::Category => Some(TaskField::Category),
+            Self::Tags => Some(TaskField::Tags),
+            Self::CreatedAt => Some(TaskField::CreatedAt),
+            Self::UpdatedAt => Some(TaskField::UpdatedAt),
+            Self::Deadline => Some(TaskField::Deadline)
+        }
+    }
+}
+
+/// The tasks of `todolist` with a header row and the given `columns`.
+pub fn export (todolist: &TodoList, columns: &[Column]) -> String {
+    let mut rows = vec![columns.iter().map(|column| column.name().to_owned()).collect::<Vec<_>>()];
+
+    for task in todolist.get_tasks() {
+        rows.push(columns.iter().map(|column| match column {
+            Column::Id => task.get_id().to_string(),
+            Column::Title => task.get_title().to_owned(),
+            Column::Description => task.get_description().to_owned(),
+            Column::Status => task.get_status().to_string(),
+            Column::Priority => task.get_priority().to_string(),
+            Column::Category => task.get_category().get_title().to_owned(),
+            Column::Tags => task.get_tags().iter().map(Tag::get_title).collect::<Vec<_>>().join(" "),
+            Column::CreatedAt => format_datetime(task.get_created_at()),
+            Column::UpdatedAt => format_datetime(task.get_updated_at()),
+            Column::Deadline => task.get_deadline().map(format_deadline).unwrap_or_default()
+        }).collect());
+    }
+
+    rows.iter().map(|row| {
+        let cells: Vec<String> = row.iter().map(|cell| quote(cell)).collect();
+        cells.join(",") + "\r\n"
+    }).collect()
+}
+
+enum Source {
+    Name(String),
+    Index(usize)
+}
+
+/// Which CSV columns fill which task fields.
+///
+/// An empty mapping reads a header row whose cells are column names
+/// (`title`, `created_at`, ...) in any order, unknown columns are skipped.
+#[derive(Default)]
+pub struct CsvMapping {
+    columns: Vec<(Source, Column)>
+}
+
+impl CsvMapping {
+    pub fn new () -> Self {
+        Self::default()
+    }
+
+    /// Reads `column` from the CSV column with the header `name`.
+    pub fn column (mut self, name: &str, column: Column) -> Self {
+        self.columns.push((Source::Name(name.trim().to_owned()), column));
+        self
+    }
+
+    /// Reads `column` from the CSV column at `index`, counted from 0.
+    pub fn index (mut self, index: usize, column: Column) -> Self {
+        self.columns.push((Source::Index(index), column));
+        self
+    }
+
+    /// The first row is a header when each of its non-empty cells is a
+    /// mapped header name or a column name.
+    fn is_header (&self, row: &[String]) -> bool {
+        let known = |cell: &str| Column::from_name(cell).is_some() || self.columns.iter().any(|(source, _)| matches!(source, Source::Name(name) if name.eq_ignore_ascii_case(cell)));
+
+        row.iter().any(|cell| !cell.trim().is_empty())
+            && row.iter().map(|cell| cell.trim()).filter(|cell| !cell.is_empty()).all(known)
+    }
+
+    /// Column indexes of the fields, from the header if there is one.
+    fn resolve (&self, header: Option<&[String]>) -> Result<Vec<(usize, Column)>, &'static str> {
+        let position = |name: &str| header?.iter().position(|cell| cell.trim().eq_ignore_ascii_case(name));
+
+        let columns: Vec<(usize, Column)> = if self.columns.is_empty() {
+            let header = header.ok_or("The CSV has no header and no columns are mapped")?;
+            header.iter().enumerate().filter_map(|(index, cell)| Some((index, Column::from_name(cell)?))).collect()
+        } else {
+            self.columns.iter().map(|(source, column)| match source {
+                Source::Index(index) => Ok((*index, *column)),
+                Source::Name(name) => position(name).map(|index| (index, *column)).ok_or("A mapped column is not in the header")
+            }).collect::<Result<_, _>>()?
+        };
+
+        if !columns.iter().any(|(_, column)| *column == Column::Title) {
+            return Err("No column is mapped to the title");
+        }
+
+        Ok(columns)
+    }
+}
+
+/// A row (or a line of a todo.txt file) that could not be imported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowError {
+    line: usize,
+    message: &'static str
+}
+
+impl RowError {
+    pub(super) fn new (line: usize, message: &'static str) -> Self {
+        Self { line, message }
+    }
+
+    /// The line the row starts on, counted from 1.
+    pub fn get_line (&self) -> usize {
+        self.line
+    }
+
+    pub fn get_message (&self) -> &'static str {
+        self.message
+    }
+}
+
+impl std::fmt::Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Tasks read from a CSV file. Rows that fail are reported by `get_errors`
+/// and left out, the others can be previewed against a list before they are
+/// applied to it.
+pub struct CsvImport {
+    tasks: Vec<Task>,
+    fields: Vec<TaskField>,
+    errors: Vec<RowError>,
+    categories: Vec<Category>,
+    tags: Vec<Tag>
+}
+
+impl CsvImport {
+    /// Fails only when the file can't be read as CSV or the mapping doesn't fit it.
+    pub fn parse (text: &str, mapping: &CsvMapping) -> Result<Self, &'static str> {
+        let mut rows = read_rows(text)?.into_iter().peekable();
+
+        let header = rows.next_if(|(_, row)| mapping.is_header(row)).map(|(_, row)| row);
+        let columns = mapping.resolve(header.as_deref())?;
+
+        let mut tasks: Vec<Task> = vec![];
+        let mut errors = vec![];
+
+        for (line, row) in rows {
+            if row.iter().all(|cell| cell.trim().is_empty()) {
+                continue;
+            }
+
+            match read_task(&row, &columns) {
+                Ok(task) if tasks.iter().any(|item| item.get_id() == task.get_id()) => errors.push(RowError { line, message: "The task id is used twice" }),
+                Ok(task) => tasks.push(task),
+                Err(message) => errors.push(RowError { line, message })
+            }
+        }
+
+        let (categories, tags) = super::collect_registries(&tasks);
+
+        let fields = columns.iter().filter_map(|(_, column)| column.get_field()).collect();
+
+        Ok(Self { tasks, fields, errors, categories, tags })
+    }
+
+    pub fn get_tasks (&self) -> &Vec<Task> {
+        &self.tasks
+    }
+
+    pub fn get_errors (&self) -> &[RowError] {
+        &self.errors
+    }
+
+    /// What `apply` would change in `todolist`, without changing it.
+    pub fn dry_run<'a> (&'a self, todolist: &TodoList) -> ImportPreview<'a> {
+        ImportPreview::new(&self.tasks, &self.fields, todolist)
+    }
+
+    /// Adds the read tasks to `todolist`, updating the columns read in listed
+    /// tasks with the same id.
+    pub fn apply (self, todolist: &mut TodoList) -> usize {
+        super::apply(self.tasks, &self.fields, todolist)
+    }
+}
+
+impl TodoListLoad for CsvImport {
+    fn get_todolist_categories (&self) -> Vec<Category> {
+        self.categories.clone()
+    }
+
+    fn get_todolist_tasks (&self) -> Vec<Task> {
+        self.tasks.clone()
+    }
+
+    fn get_todolist_tags (&self) -> Vec<Tag> {
+        self.tags.clone()
+    }
+}
+
+fn read_task (row: &[String], columns: &[(usize, Column)]) -> Result<Task, &'static str> {
+    let cell = |wanted: Column| columns.iter()
+        .find(|(_, column)| *column == wanted)
+        .and_then(|(index, _)| row.get(*index))
+        .map(|cell| cell.trim())
+        .filter(|cell| !cell.is_empty());
+
+    let mut builder = Task::new(cell(Column::Title).unwrap_or_default())?;
+
+    if let Some(id) = cell(Column::Id) {
+        builder = builder.id(Uuid::parse_str(id).map_err(|_| "Invalid id")?);
+    }
+    if let Some(description) = cell(Column::Description) {
+        builder = builder.description(description);
+    }
+    if let Some(status) = cell(Column::Status) {
+        builder = builder.status(status.parse()?);
+    }
+    if let Some(priority) = cell(Column::Priority) {
+        builder = builder.priority(priority.parse()?);
+    }
+    if let Some(category) = cell(Column::Category) {
+        builder = builder.category(Category::new(category)?);
+    }
+    if let Some(tags) = cell(Column::Tags) {
+        let tags = tags.split([' ', ',']).filter(|tag| !tag.is_empty()).map(Tag::new).collect::<Result<Vec<_>, _>>()?;
+        builder = builder.tags(&tags);
+    }
+    if let Some(created_at) = cell(Column::CreatedAt) {
+        builder = builder.created_at(parse_datetime(created_at)?);
+    }
+    if let Some(updated_at) = cell(Column::UpdatedAt) {
+        builder = builder.updated_at(parse_datetime(updated_at)?);
+    }
+    if let Some(deadline) = cell(Column::Deadline) {
+        builder = builder.deadline(parse_deadline(deadline)?);
+    }
+
+    Ok(builder.build())
+}
+
+fn format_datetime (at: &DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+fn format_deadline (deadline: &Deadline) -> String {
+    match deadline {
+        Deadline::Date(date) => date.format("%Y-%m-%d").to_string(),
+        Deadline::DateTime(at) => format_datetime(at),
+        Deadline::Window { start, end } => format!("{}/{}", format_datetime(start), format_datetime(end))
+    }
+}
+
+fn parse_datetime (value: &str) -> Result<DateTime<Utc>, &'static str> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(value) {
+        return Ok(at.with_timezone(&Utc));
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+        .map_err(|_| "Invalid date-time")
+}
+
+fn parse_deadline (value: &str) -> Result<Deadline, &'static str> {
+    if let Some((start, end)) = value.split_once('/') {
+        return Deadline::window(parse_datetime(start)?, parse_datetime(end)?);
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.into());
+    }
+
+    DateTime::parse_from_rfc3339(value).map(Deadline::from).map_err(|_| "Invalid deadline")
+}
+
+fn quote (cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_owned()
+    }
+}
+
+/// Splits `text` into rows of cells, with the line each row starts on.
+pub(super) fn read_rows (text: &str) -> Result<Vec<(usize, Vec<String>)>, &'static str> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = text.strip_prefix('\u{feff}').unwrap_or(text).chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            line += 1;
+        }
+
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if cell.is_empty() => quoted = true,
+            _ if quoted => cell.push(c),
+            ',' => row.push(std::mem::take(&mut cell)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                row.push(std::mem::take(&mut cell));
+                rows.push((start, std::mem::take(&mut row)));
+                start = line;
+            }
+            _ => cell.push(c)
+        }
+    }
+
+    if quoted {
+        return Err("A quoted value is not closed");
+    }
+
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell);
+        rows.push((start, row));
+    }
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+
+    fn now () -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 6, 10, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn export_selected_columns () {
+        let mut todolist = TodoList::default().with_clock(Arc::new(FixedClock::new(now())));
+        todolist.add_task(todolist.new_task("Write \"the\" report, today").unwrap()
+            .tags(&[Tag::new("work").unwrap(), Tag::new("docs").unwrap()])
+            .priority(Priority::Red)
+            .deadline(NaiveDate::from_ymd_opt(2024, 5, 10).unwrap())
+            .build()).unwrap();
+        todolist.add_task(todolist.new_task("Call").unwrap().deadline(Deadline::window(now(), now() + Duration::hours(1)).unwrap()).build()).unwrap();
+
+        let csv = export(&todolist, &[Column::Title, Column::Priority, Column::Tags, Column::Deadline, Column::CreatedAt]);
+
+        assert_eq!(csv, "title,priority,tags,deadline,created_at\r\n\
+            \"Write \"\"the\"\" report, today\",Red,work docs,2024-05-10,2024-05-06T10:00:00Z\r\n\
+            Call,Common,,2024-05-06T10:00:00Z/2024-05-06T11:00:00Z,2024-05-06T10:00:00Z\r\n");
+    }
+
+    #[test]
+    fn round_trip () {
+        let mut todolist = TodoList::default().with_clock(Arc::new(FixedClock::new(now())));
+        todolist.add_task(todolist.new_task("First").unwrap()
+            .description("Two\nlines")
+            .category(Category::new("Work").unwrap())
+            .tags(&[Tag::new("a").unwrap()])
+            .status(Status::Progress)
+            .deadline(now())
+            .build()).unwrap();
+
+        let import = CsvImport::parse(&export(&todolist, &Column::ALL), &CsvMapping::new()).unwrap();
+        assert!(import.get_errors().is_empty());
+        assert!(import.get_tasks() == todolist.get_tasks());
+    }
+
+    #[test]
+    fn mapping_and_row_errors () {
+        let csv = "Task,Notes,Due,Prio,Labels\n\
+            Write report,,2024-05-10,high,work\n\
+            ,empty title,,,\n\
+            \n\
+            Call,\"multi\nline\",2024-05-11,red,\"home,phone\"\n\
+            Shop,,tomorrow,,\n";
+
+        let mapping = CsvMapping::new()
+            .column("task", Column::Title)
+            .column("Notes", Column::Description)
+            .column("Due", Column::Deadline)
+            .column("Prio", Column::Priority)
+            .column("Labels", Column::Tags);
+
+        let import = CsvImport::parse(csv, &mapping).unwrap();
+
+        let titles: Vec<&str> = import.get_tasks().iter().map(|task| task.get_title()).collect();
+        assert_eq!(titles, vec!["Call"]);
+        assert_eq!(import.get_tasks()[0].get_description(), "multi\nline");
+        assert_eq!(import.get_tasks()[0].get_tags(), &vec![Tag::new("home").unwrap(), Tag::new("phone").unwrap()]);
+
+        let errors: Vec<String> = import.get_errors().iter().map(|error| error.to_string()).collect();
+        assert_eq!(errors, vec!["line 2: Unknown priority", "line 3: Title is empty.", "line 7: Invalid deadline"]);
+    }
+
+    #[test]
+    fn header_detection () {
+        let mapping = CsvMapping::new().index(0, Column::Title).index(1, Column::Priority);
+
+        let import = CsvImport::parse("First,Red\nSecond,green\n", &mapping).unwrap();
+        assert_eq!(import.get_tasks().len(), 2);
+
+        let import = CsvImport::parse("title,priority\nFirst,Red\n", &mapping).unwrap();
+        assert_eq!(import.get_tasks().len(), 1);
+
+        assert!(CsvImport::parse("First,Red\n", &CsvMapping::new()).is_err());
+        assert!(CsvImport::parse("title\nFirst\n", &CsvMapping::new().column("name", Column::Title)).is_err());
+        assert!(CsvImport::parse("description\nFirst\n", &CsvMapping::new()).is_err());
+        assert!(CsvImport::parse("title\n\"First\n", &CsvMapping::new()).is_err());
+    }
+
+    #[test]
+    fn dry_run_and_apply () {
+        let mut todolist = TodoList::default().with_clock(Arc::new(FixedClock::new(now())));
+        todolist.add_task(todolist.new_task("Kept").unwrap().reminders(&[Reminder::parse("1h").unwrap()]).build()).unwrap();
+        let mut renamed = todolist.new_task("Renamed").unwrap().reminders(&[Reminder::parse("2h").unwrap()]).build();
+        renamed.set_status(Status::Progress);
+        todolist.add_task(renamed).unwrap();
+
+        let mut csv = export(&todolist, &Column::ALL);
+        csv = csv.replace(",Renamed,", ",Renamed again,") + "\r\n,New,,,,,,,,\r\n";
+
+        let import = CsvImport::parse(&csv, &CsvMapping::new()).unwrap();
+        let preview = import.dry_run(&todolist);
+
+        let titles = |tasks: &[&Task]| tasks.iter().map(|task| task.get_title().to_owned()).collect::<Vec<_>>();
+        assert_eq!(titles(preview.get_created()), vec!["New"]);
+        assert_eq!(titles(preview.get_updated()), vec!["Renamed again"]);
+        assert_eq!(titles(preview.get_unchanged()), vec!["Kept"]);
+        assert_eq!(todolist.get_tasks().len(), 2);
+
+        assert_eq!(import.apply(&mut todolist), 2);
+        assert_eq!(titles(&todolist.get_tasks().iter().collect::<Vec<_>>()), vec!["Kept", "Renamed again", "New"]);
+
+        let renamed = &todolist.get_tasks()[1];
+        assert_eq!(renamed.get_reminders(), &vec![Reminder::parse("2h").unwrap()]);
+        assert_eq!(renamed.get_status_history().len(), 1);
+
+        let csv = format!("id,title,status\r\n{},Renamed again,Completed\r\n", renamed.get_id());
+        let import = CsvImport::parse(&csv, &CsvMapping::new()).unwrap();
+        assert_eq!(import.dry_run(&todolist).get_updated().len(), 1);
+        import.apply(&mut todolist);
+
+        let renamed = &todolist.get_tasks()[1];
+        assert_eq!(renamed.get_title(), "Renamed again");
+        assert_eq!(renamed.get_status(), &Status::Completed);
+        assert_eq!(renamed.get_status_history().len(), 2);
+        assert_eq!(renamed.get_reminders().len(), 1);
+    }
+}
@@ -0,0 +1,233 @@
+//! GitHub issues saved as JSON, from the REST API (`gh api repos/OWNER/REPO/issues`)
+//! or from `gh issue list --json number,title,body,state,stateReason,labels,milestone,url,createdAt,updatedAt`.
+//!
+//! * The issue title and body are the title and description.
+//! * Open issues are `Created`, closed ones `Completed`, or `Archived` when
+//!   closed as not planned.
+//! * Labels are tags, the milestone is the category.
+//! * `created_at` and `updated_at` are kept.
+//!
+//! Tasks get the same id on every import, read from the issue URL, so a
+//! newer dump updates the tasks of the last one. Pull requests, which the
+//! REST API lists with the issues, are skipped. Assignees, comments and
+//! reactions are not read.
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::prelude::*;
+use crate::prelude::load::TodoListLoad;
+use crate::TodoList;
+
+use super::{stable_id, ImportPreview};
+
+/// The task fields read from the export, the others are kept on import.
+const FIELDS: &[TaskField] = &[
+    TaskField::Title, TaskField::Description, TaskField::Status, TaskField::Category, TaskField::Tags,
+    TaskField::CreatedAt, TaskField::UpdatedAt
+];
+
+#[derive(Deserialize)]
+struct Issue {
+    // the REST API has the API URL as `url`, gh the web one
+    html_url: Option<String>,
+    url: Option<String>,
+    title: String,
+    body: Option<String>,
+    state: String,
+    #[serde(alias = "stateReason")]
+    state_reason: Option<String>,
+    #[serde(default)]
+    labels: Vec<Label>,
+    milestone: Option<Milestone>,
+    #[serde(alias = "createdAt")]
+    created_at: DateTime<Utc>,
+    #[serde(alias = "updatedAt")]
+    updated_at: DateTime<Utc>,
+    pull_request: Option<serde::de::IgnoredAny>
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Label {
+    Name(String),
+    Object { name: String }
+}
+
+#[derive(Deserialize)]
+struct Milestone {
+    title: String
+}
+
+/// Tasks read from GitHub issues, to be previewed against a list before
+/// they are applied to it.
+pub struct GitHubIssues {
+    tasks: Vec<Task>,
+    categories: Vec<Category>,
+    tags: Vec<Tag>
+}
+
+impl GitHubIssues {
+    pub fn parse (text: &str) -> Result<Self, &'static str> {
+        let issues: Vec<Issue> = serde_json::from_str(text).map_err(|_| "Invalid JSON")?;
+        let mut tasks: Vec<Task> = vec![];
+
+        for issue in issues.into_iter().filter(|issue| issue.pull_request.is_none()) {
+            let status = match (issue.state.to_ascii_lowercase().as_str(), issue.state_reason.map(|reason| reason.to_ascii_lowercase())) {
+                ("open", _) => Status::Created,
+                ("closed", Some(reason)) if reason == "not_planned" => Status::Archived,
+                ("closed", _) => Status::Completed,
+                _ => return Err("Unknown issue state")
+            };
+
+            let tags = issue.labels.iter()
+                .map(|label| match label {
+                    Label::Name(name) | Label::Object { name } => Tag::new(name)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let url = issue.html_url.or(issue.url).ok_or("The issue has no URL")?;
+
+            let mut builder = Task::new(&issue.title)?
+                .id(stable_id(&url))
+                .description(issue.body.as_deref().unwrap_or_default())
+                .status(status)
+                .tags(&tags)
+                .created_at(issue.created_at)
+                .updated_at(issue.updated_at);
+
+            if let Some(milestone) = issue.milestone {
+                builder = builder.category(Category::new(&milestone.title)?);
+            }
+
+            let task = builder.build();
+
+            if tasks.iter().any(|item| item.get_id() == task.get_id()) {
+                return Err("The issue is listed twice");
+            }
+
+            tasks.push(task);
+        }
+
+        let (categories, tags) = super::collect_registries(&tasks);
+
+        Ok(Self { tasks, categories, tags })
+    }
+
+    pub fn get_tasks (&self) -> &Vec<Task> {
+        &self.tasks
+    }
+
+    /// What `apply` would change in `todolist`, without changing it.
+    pub fn dry_run<'a> (&'a self, todolist: &TodoList) -> ImportPreview<'a> {
+        ImportPreview::new(&self.tasks, FIELDS, todolist)
+    }
+
+    /// Adds the read tasks to `todolist`, updating the ones of an earlier import.
+    pub fn apply (self, todolist: &mut TodoList) -> usize {
+        super::apply(self.tasks, FIELDS, todolist)
+    }
+}
+
+impl TodoListLoad for GitHubIssues {
+    fn get_todolist_categories (&self) -> Vec<Category> {
+        self.categories.clone()
+    }
+
+    fn get_todolist_tasks (&self) -> Vec<Task> {
+        self.tasks.clone()
+    }
+
+    fn get_todolist_tags (&self) -> Vec<Tag> {
+        self.tags.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    const REST: &str = r#"[
+        {"number": 3, "url": "https://api.github.com/repos/acme/app/issues/3", "html_url": "https://github.com/acme/app/issues/3", "title": "Crash on start", "body": "Steps:\n1. Open",
+         "state": "open", "state_reason": null, "labels": [{"id": 1, "name": "bug"}, {"id": 2, "name": "good first issue"}],
+         "milestone": {"title": "v1.0", "due_on": null}, "created_at": "2024-05-01T08:00:00Z", "updated_at": "2024-05-02T09:00:00Z"},
+        {"number": 2, "url": "https://api.github.com/repos/acme/app/issues/2", "html_url": "https://github.com/acme/app/pull/2", "title": "Fix crash", "body": null, "state": "open",
+         "labels": [], "milestone": null, "created_at": "2024-05-01T08:00:00Z", "updated_at": "2024-05-01T08:00:00Z",
+         "pull_request": {"url": "https://api.github.com/repos/acme/app/pulls/2"}},
+        {"number": 1, "url": "https://api.github.com/repos/acme/app/issues/1", "html_url": "https://github.com/acme/app/issues/1", "title": "Dark mode", "body": null,
+         "state": "closed", "state_reason": "not_planned", "labels": [], "milestone": null,
+         "created_at": "2024-04-01T08:00:00Z", "updated_at": "2024-04-02T08:00:00Z"}
+    ]"#;
+
+    #[test]
+    fn parse () {
+        let todolist = TodoList::init(GitHubIssues::parse(REST).unwrap());
+        let tasks = todolist.get_tasks();
+
+        assert_eq!(tasks.len(), 2);
+
+        assert_eq!(tasks[0].get_title(), "Crash on start");
+        assert_eq!(tasks[0].get_description(), "Steps:\n1. Open");
+        assert_eq!(tasks[0].get_status(), &Status::Created);
+        assert_eq!(tasks[0].get_category().get_title(), "v1.0");
+        assert_eq!(tasks[0].get_tags(), &vec![Tag::new("bug").unwrap(), Tag::new("good first issue").unwrap()]);
+        assert_eq!(tasks[0].get_created_at(), &Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap());
+        assert_eq!(tasks[0].get_updated_at(), &Utc.with_ymd_and_hms(2024, 5, 2, 9, 0, 0).unwrap());
+
+        assert_eq!(tasks[1].get_status(), &Status::Archived);
+        assert_eq!(tasks[1].get_category(), &Category::default());
+    }
+
+    #[test]
+    fn parse_gh_cli () {
+        let text = r#"[
+            {"number": 3, "url": "https://github.com/acme/app/issues/3", "title": "Crash on start", "body": "Steps:\n1. Open",
+             "state": "CLOSED", "stateReason": "COMPLETED", "labels": [{"id": "LA_1", "name": "bug", "color": "d73a4a"}],
+             "milestone": null, "createdAt": "2024-05-01T08:00:00Z", "updatedAt": "2024-05-06T09:00:00Z"}
+        ]"#;
+
+        let rest = GitHubIssues::parse(REST).unwrap();
+        let cli = GitHubIssues::parse(text).unwrap();
+
+        assert_eq!(cli.get_tasks()[0].get_id(), rest.get_tasks()[0].get_id());
+        assert_eq!(cli.get_tasks()[0].get_status(), &Status::Completed);
+    }
+
+    #[test]
+    fn preview_against_earlier_import () {
+        let mut todolist = TodoList::init(GitHubIssues::parse(REST).unwrap());
+
+        let newer = REST.replace(
+            r#""state": "open", "state_reason": null"#,
+            r#""state": "closed", "state_reason": "completed""#
+        );
+        let import = GitHubIssues::parse(&newer).unwrap();
+        let preview = import.dry_run(&todolist);
+
+        assert!(preview.get_created().is_empty());
+        assert_eq!(preview.get_updated().len(), 1);
+        assert_eq!(preview.get_updated()[0].get_status(), &Status::Completed);
+        assert_eq!(preview.get_unchanged().len(), 1);
+
+        assert_eq!(import.apply(&mut todolist), 1);
+        assert_eq!(todolist.get_tasks()[0].get_status(), &Status::Completed);
+    }
+
+    #[test]
+    fn apply_registers_categories_and_tags () {
+        let mut todolist = TodoList::default();
+        todolist.new_tag("bug").unwrap();
+
+        assert_eq!(GitHubIssues::parse(REST).unwrap().apply(&mut todolist), 2);
+        assert!(todolist.get_categories().contains(&Category::new("v1.0").unwrap()));
+        assert_eq!(todolist.get_tags(), &vec![Tag::new("bug").unwrap(), Tag::new("good first issue").unwrap()]);
+    }
+
+    #[test]
+    fn invalid_input () {
+        assert!(GitHubIssues::parse("{}").is_err());
+        assert!(GitHubIssues::parse(&REST.replace(r#""state": "open""#, r#""state": "merged""#)).is_err());
+    }
+}
@@ -0,0 +1,545 @@
+//! iCalendar (RFC 5545) with one `VTODO` per task.
+//!
+//! | Task          | VTODO                                              |
+//! |---------------|----------------------------------------------------|
+//! | id            | `UID`                                              |
+//! | title         | `SUMMARY`                                          |
+//! | description   | `DESCRIPTION`                                      |
+//! | deadline      | `DUE`, a window also as `X-TODOLIST-WINDOW-START`  |
+//! | scheduled     | `DTSTART`                                          |
+//! | priority      | `PRIORITY`: Red 1, Yellow 5, Green 9, Common none  |
+//! | status        | `STATUS`: `NEEDS-ACTION`, `IN-PROCESS`, `COMPLETED`, `CANCELLED` (archived) |
+//! | category      | first value of `CATEGORIES`                        |
+//! | tags          | other values of `CATEGORIES`                       |
+//! | created_at    | `CREATED`                                          |
+//! | updated_at    | `LAST-MODIFIED`                                    |
+//!
+//! On import other properties and components (alarms, events) are ignored,
+//! priorities 1-4 read as Red and 6-9 as Green and a `UID` that is not a
+//! uuid gets an id derived from it, the same on every import. Times with a
+//! `TZID` are read in that IANA time zone, times without zone as UTC.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use uuid::Uuid;
+
+use crate::calendar::unfold;
+use crate::prelude::*;
+use crate::prelude::load::TodoListLoad;
+use crate::TodoList;
+
+use super::csv::RowError;
+
+/// A `VTODO` being read: the line it begins on, its properties and the
+/// first malformed line in it.
+struct Todo {
+    line: usize,
+    properties: Vec<Property>,
+    error: Option<RowError>
+}
+
+/// Tasks read from an iCalendar file. `VTODO`s that fail are reported by
+/// `get_errors` and left out.
+pub struct ICalendar {
+    tasks: Vec<Task>,
+    errors: Vec<RowError>,
+    categories: Vec<Category>,
+    tags: Vec<Tag>
+}
+
+impl ICalendar {
+    /// Malformed lines outside of a `VTODO` are ignored.
+    pub fn parse (text: &str) -> Self {
+        let mut tasks = vec![];
+        let mut errors = vec![];
+        let mut todo: Option<Todo> = None;
+        let mut nested = 0;
+
+        for (line, content) in unfold(text) {
+            if content.trim().is_empty() {
+                continue;
+            }
+
+            let property = match (Property::parse(&content), todo.as_mut()) {
+                (Ok(property), _) => property,
+                (Err(message), Some(todo)) => {
+                    todo.error.get_or_insert(RowError::new(line, message));
+                    continue;
+                }
+                (Err(_), None) => continue
+            };
+
+            match (property.name.as_str(), todo.as_mut()) {
+                ("BEGIN", None) if property.value.eq_ignore_ascii_case("VTODO") => todo = Some(Todo { line, properties: vec![], error: None }),
+                ("BEGIN", Some(_)) => nested += 1,
+                ("END", Some(_)) if nested > 0 => nested -= 1,
+                ("END", Some(_)) => {
+                    let Todo { line, properties, error } = todo.take().unwrap();
+                    let read = match error {
+                        Some(error) => Err(error),
+                        None => read_task(properties).map_err(|message| RowError::new(line, message))
+                    };
+
+                    match read {
+                        Ok(task) => tasks.push(task),
+                        Err(error) => errors.push(error)
+                    }
+                }
+                (_, Some(todo)) if nested == 0 => todo.properties.push(property),
+                _ => {}
+            }
+        }
+
+        if let Some(todo) = todo {
+            errors.push(RowError::new(todo.line, "The VTODO is not closed"));
+        }
+
+        let (categories, tags) = super::collect_registries(&tasks);
+
+        Self { tasks, errors, categories, tags }
+    }
+
+    pub fn get_tasks (&self) -> &Vec<Task> {
+        &self.tasks
+    }
+
+    /// The `VTODO`s that failed, by the line they begin on or the malformed
+    /// line in them.
+    pub fn get_errors (&self) -> &[RowError] {
+        &self.errors
+    }
+}
+
+impl TodoListLoad for ICalendar {
+    fn get_todolist_categories (&self) -> Vec<Category> {
+        self.categories.clone()
+    }
+
+    fn get_todolist_tasks (&self) -> Vec<Task> {
+        self.tasks.clone()
+    }
+
+    fn get_todolist_tags (&self) -> Vec<Tag> {
+        self.tags.clone()
+    }
+}
+
+/// The tasks of `todolist` as an iCalendar file.
+pub fn export (todolist: &TodoList) -> String {
+    let stamp = format_datetime(&todolist.now());
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        "PRODID:-//todolist//EN".to_owned()
+    ];
+
+    for task in todolist.get_tasks() {
+        lines.push("BEGIN:VTODO".to_owned());
+        lines.push(format!("UID:{}", task.get_id()));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("CREATED:{}", format_datetime(task.get_created_at())));
+        lines.push(format!("LAST-MODIFIED:{}", format_datetime(task.get_updated_at())));
+        lines.push(format!("SUMMARY:{}", escape(task.get_title())));
+
+        if !task.get_description().is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape(task.get_description())));
+        }
+
+        if let Some(scheduled) = task.get_scheduled() {
+            lines.push(format!("DTSTART{}", format_deadline(scheduled, |start, _| start)));
+        }
+
+        if let Some(deadline) = task.get_deadline() {
+            if let Deadline::Window { start, .. } = deadline {
+                lines.push(format!("X-TODOLIST-WINDOW-START:{}", format_datetime(start)));
+            }
+
+            lines.push(format!("DUE{}", format_deadline(deadline, |_, end| end)));
+        }
+
+        match task.get_priority() {
+            Priority::Red => lines.push("PRIORITY:1".to_owned()),
+            Priority::Yellow => lines.push("PRIORITY:5".to_owned()),
+            Priority::Green => lines.push("PRIORITY:9".to_owned()),
+            Priority::Common => {}
+        }
+
+        lines.push(format!("STATUS:{}", match task.get_status() {
+            Status::Created => "NEEDS-ACTION",
+            Status::Progress => "IN-PROCESS",
+            Status::Completed => "COMPLETED",
+            Status::Archived => "CANCELLED"
+        }));
+
+        let categories: Vec<String> = std::iter::once(task.get_category().get_title())
+            .chain(task.get_tags().iter().map(Tag::get_title))
+            .map(escape)
+            .collect();
+        lines.push(format!("CATEGORIES:{}", categories.join(",")));
+
+        lines.push("END:VTODO".to_owned());
+    }
+
+    lines.push("END:VCALENDAR".to_owned());
+
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+/// A content line: `NAME;PARAM=VALUE:value`.
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String
+}
+
+impl Property {
+    fn parse (line: &str) -> Result<Self, &'static str> {
+        let mut quoted = false;
+        let split = line.char_indices()
+            .find(|(_, c)| {
+                if *c == '"' {
+                    quoted = !quoted;
+                }
+                *c == ':' && !quoted
+            })
+            .map(|(index, _)| index)
+            .ok_or("Invalid iCalendar line")?;
+
+        let (head, value) = (&line[..split], &line[split + 1..]);
+        let mut parts = head.split(';');
+        let name = parts.next().unwrap().to_ascii_uppercase();
+
+        let params = parts
+            .filter_map(|param| param.split_once('='))
+            .map(|(key, value)| (key.to_ascii_uppercase(), value.trim_matches('"').to_owned()))
+            .collect();
+
+        Ok(Self { name, params, value: value.to_owned() })
+    }
+
+    fn is_date (&self) -> bool {
+        self.params.iter().any(|(key, value)| key == "VALUE" && value.eq_ignore_ascii_case("DATE"))
+            || self.value.len() == 8
+    }
+}
+
+fn read_task (properties: Vec<Property>) -> Result<Task, &'static str> {
+    let find = |name: &str| properties.iter().find(|property| property.name == name);
+
+    let title = find("SUMMARY").ok_or("The VTODO has no SUMMARY")?;
+    let mut builder = Task::new(&unescape(&title.value))?;
+
+    if let Some(uid) = find("UID") {
+        let uid = uid.value.trim();
+        builder = builder.id(Uuid::parse_str(uid).unwrap_or_else(|_| super::stable_id(uid)));
+    }
+
+    if let Some(description) = find("DESCRIPTION") {
+        builder = builder.description(&unescape(&description.value));
+    }
+
+    if let Some(due) = find("DUE") {
+        let due = parse_deadline(due)?;
+
+        builder = match (find("X-TODOLIST-WINDOW-START"), due) {
+            (Some(start), Deadline::DateTime(end)) => builder.deadline(Deadline::window(parse_datetime(start)?, end)?),
+            _ => builder.deadline(due)
+        };
+    }
+
+    if let Some(start) = find("DTSTART") {
+        builder = builder.scheduled(parse_deadline(start)?);
+    }
+
+    if let Some(priority) = find("PRIORITY") {
+        builder = builder.priority(match priority.value.trim().parse::<u8>() {
+            Ok(1..=4) => Priority::Red,
+            Ok(5) => Priority::Yellow,
+            Ok(6..=9) => Priority::Green,
+            _ => Priority::Common
+        });
+    }
+
+    if let Some(status) = find("STATUS") {
+        builder = builder.status(match status.value.trim().to_ascii_uppercase().as_str() {
+            "IN-PROCESS" => Status::Progress,
+            "COMPLETED" => Status::Completed,
+            "CANCELLED" => Status::Archived,
+            _ => Status::Created
+        });
+    }
+
+    let categories: Vec<String> = properties.iter()
+        .filter(|property| property.name == "CATEGORIES")
+        .flat_map(|property| split_list(&property.value))
+        .filter(|value| !value.trim().is_empty())
+        .collect();
+
+    if let Some((category, tags)) = categories.split_first() {
+        builder = builder.category(Category::new(category)?);
+
+        let tags = tags.iter().map(|tag| Tag::new(tag)).collect::<Result<Vec<_>, _>>()?;
+        builder = builder.tags(&tags);
+    }
+
+    if let Some(created) = find("CREATED") {
+        builder = builder.created_at(parse_datetime(created)?);
+    }
+
+    if let Some(modified) = find("LAST-MODIFIED") {
+        builder = builder.updated_at(parse_datetime(modified)?);
+    }
+
+    Ok(builder.build())
+}
+
+fn parse_deadline (property: &Property) -> Result<Deadline, &'static str> {
+    if property.is_date() {
+        return NaiveDate::parse_from_str(property.value.trim(), "%Y%m%d")
+            .map(Deadline::from)
+            .map_err(|_| "Invalid iCalendar date");
+    }
+
+    parse_datetime(property).map(Deadline::from)
+}
+
+/// A time in UTC, in the zone of its `TZID` or without zone.
+fn parse_datetime (property: &Property) -> Result<DateTime<Utc>, &'static str> {
+    let value = property.value.trim();
+    let (value, utc) = match value.strip_suffix('Z') {
+        Some(value) => (value, true),
+        None => (value, false)
+    };
+
+    let at = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").map_err(|_| "Invalid iCalendar date-time")?;
+    let zone = property.params.iter().find(|(key, _)| key == "TZID").map(|(_, zone)| zone.trim_start_matches('/'));
+
+    let zone = match zone {
+        Some(zone) if !utc => Tz::from_str(zone).map_err(|_| "Unknown iCalendar time zone")?,
+        _ => return Ok(at.and_utc())
+    };
+
+    // a time skipped by a DST transition takes the offset of the day before
+    match zone.from_local_datetime(&at).earliest() {
+        Some(at) => Ok(at.with_timezone(&Utc)),
+        None => {
+            let before = at.checked_sub_signed(Duration::days(1)).unwrap_or(at);
+            let offset = zone.offset_from_utc_datetime(&before).fix().local_minus_utc();
+
+            at.and_utc().checked_sub_signed(Duration::seconds(offset.into())).ok_or("Invalid iCalendar date-time")
+        }
+    }
+}
+
+fn format_datetime (at: &DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// The parameters and value of `deadline`, `pick` chooses the start or the
+/// end of a window.
+fn format_deadline (deadline: &Deadline, pick: fn(DateTime<Utc>, DateTime<Utc>) -> DateTime<Utc>) -> String {
+    match deadline {
+        Deadline::Date(date) => format!(";VALUE=DATE:{}", date.format("%Y%m%d")),
+        Deadline::DateTime(at) => format!(":{}", format_datetime(at)),
+        Deadline::Window { start, end } => format!(":{}", format_datetime(&pick(*start, *end)))
+    }
+}
+
+fn escape (text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape (text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n' | 'N') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => result.push('\\')
+        }
+    }
+
+    result
+}
+
+/// Splits a comma separated value, keeping escaped commas.
+fn split_list (value: &str) -> Vec<String> {
+    let mut items = vec![String::new()];
+    let mut escaped = false;
+
+    for c in value.chars() {
+        match c {
+            ',' if !escaped => items.push(String::new()),
+            _ => items.last_mut().unwrap().push(c)
+        }
+
+        escaped = c == '\\' && !escaped;
+    }
+
+    items.iter().map(|item| unescape(item)).collect()
+}
+
+/// Folds a line at 75 octets and terminates it with CRLF.
+fn fold (line: &str) -> String {
+    let mut result = String::new();
+    let mut width = 0;
+
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            result.push_str("\r\n ");
+            width = 1;
+        }
+
+        result.push(c);
+        width += c.len_utf8();
+    }
+
+    result.push_str("\r\n");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+
+    fn now () -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 6, 10, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn round_trip () {
+        let clock = Arc::new(FixedClock::new(now()));
+        let mut todolist = TodoList::default().with_clock(clock.clone());
+
+        let window = Deadline::window(now(), now() + Duration::hours(2)).unwrap();
+        let tasks = vec![
+            todolist.new_task("Write report; draft, then final").unwrap()
+                .description("Line one\nLine two, with \\ backslash")
+                .category(Category::new("Work").unwrap())
+                .tags(&[Tag::new("writing").unwrap(), Tag::new("a,b").unwrap()])
+                .priority(Priority::Red)
+                .status(Status::Progress)
+                .deadline(NaiveDate::from_ymd_opt(2024, 5, 10).unwrap())
+                .scheduled(now() + Duration::days(1))
+                .build(),
+            todolist.new_task("Meeting").unwrap().deadline(window).priority(Priority::Green).status(Status::Archived).build(),
+            todolist.new_task(&"Long title ".repeat(20)).unwrap().deadline(now()).status(Status::Completed).build()
+        ];
+
+        for task in tasks {
+            todolist.add_task(task).unwrap();
+        }
+
+        clock.advance(Duration::hours(1));
+        todolist.get_task_mut(&todolist.get_tasks()[1].get_id().to_owned()).unwrap().set_priority(Priority::Yellow);
+
+        let ics = export(&todolist);
+        assert!(ics.lines().all(|line| line.len() <= 76));
+        assert!(ics.contains("DUE;VALUE=DATE:20240510\r\n"));
+        assert!(ics.contains("CATEGORIES:Work,writing,a\\,b\r\n"));
+
+        let imported = TodoList::init(ICalendar::parse(&ics));
+        assert!(imported.get_tasks() == todolist.get_tasks());
+        assert_eq!(imported.get_categories().len(), 2);
+        assert_eq!(imported.get_tags().len(), 2);
+    }
+
+    #[test]
+    fn tolerates_unknown_properties () {
+        let ics = "BEGIN:VCALENDAR\n\
+            VERSION:2.0\n\
+            X-WR-CALNAME:Tasks\n\
+            BEGIN:VEVENT\n\
+            SUMMARY:Not a task\n\
+            END:VEVENT\n\
+            BEGIN:VTODO\n\
+            UID:some-app-1234@example.com\n\
+            SUMMARY:Pay rent\n\
+            DUE;X-LABEL=\"Rent: monthly\";TZID=Europe/Paris:20240510T090000\n\
+            PRIORITY:3\n\
+            CLASS:PRIVATE\n\
+            BEGIN:VALARM\n\
+            ACTION:DISPLAY\n\
+            SUMMARY:Alarm\n\
+            END:VALARM\n\
+            CATEGORIES:Home\n\
+            CATEGORIES:bills,monthly\n\
+            END:VTODO\n\
+            END:VCALENDAR\n";
+
+        let todolist = TodoList::init(ICalendar::parse(ics));
+        let task = &todolist.get_tasks()[0];
+
+        assert_eq!(todolist.get_tasks().len(), 1);
+        assert_eq!(task.get_title(), "Pay rent");
+        // 9:00 summer time in Paris
+        assert_eq!(task.get_deadline(), Some(&Utc.with_ymd_and_hms(2024, 5, 10, 7, 0, 0).unwrap().into()));
+        assert_eq!(task.get_priority(), &Priority::Red);
+        assert_eq!(task.get_status(), &Status::Created);
+        assert_eq!(task.get_category().get_title(), "Home");
+        assert_eq!(task.get_tags(), &vec![Tag::new("bills").unwrap(), Tag::new("monthly").unwrap()]);
+
+        let again = TodoList::init(ICalendar::parse(ics));
+        assert_eq!(again.get_tasks()[0].get_id(), task.get_id());
+    }
+
+    #[test]
+    fn time_zones () {
+        let due = |line: &str| {
+            let ics = format!("BEGIN:VTODO\nSUMMARY:Task\n{}\nEND:VTODO", line);
+            ICalendar::parse(&ics).get_tasks().first().and_then(|task| task.get_deadline().copied())
+        };
+
+        assert_eq!(due("DUE;TZID=America/New_York:20240115T090000"), Some(Utc.with_ymd_and_hms(2024, 1, 15, 14, 0, 0).unwrap().into()));
+        assert_eq!(due("DUE;TZID=/Europe/Berlin:20240115T090000"), Some(Utc.with_ymd_and_hms(2024, 1, 15, 8, 0, 0).unwrap().into()));
+        // skipped by the change to summer time, read with the winter offset
+        assert_eq!(due("DUE;TZID=Europe/Berlin:20240331T023000"), Some(Utc.with_ymd_and_hms(2024, 3, 31, 1, 30, 0).unwrap().into()));
+        assert_eq!(due("DUE;TZID=Europe/Berlin:20240115T090000Z"), Some(Utc.with_ymd_and_hms(2024, 1, 15, 9, 0, 0).unwrap().into()));
+        assert_eq!(due("DUE;TZID=W. Europe Standard Time:20240115T090000"), None);
+    }
+
+    #[test]
+    fn invalid_components_are_reported () {
+        let ics = "BEGIN:VCALENDAR\n\
+            BEGIN:VTODO\n\
+            DUE:20240510T090000Z\n\
+            END:VTODO\n\
+            BEGIN:VTODO\n\
+            SUMMARY:Task\n\
+            DUE:tomorrow\n\
+            END:VTODO\n\
+            BEGIN:VTODO\n\
+            SUMMARY:Kept\n\
+            END:VTODO\n\
+            BEGIN:VTODO\n\
+            SUMMARY:Malformed\n\
+            no colon here\n\
+            END:VTODO\n\
+            this line is ignored\n\
+            BEGIN:VTODO\n\
+            SUMMARY:Not closed";
+
+        let calendar = ICalendar::parse(ics);
+        let lines: Vec<usize> = calendar.get_errors().iter().map(RowError::get_line).collect();
+
+        assert_eq!(calendar.get_tasks().len(), 1);
+        assert_eq!(calendar.get_tasks()[0].get_title(), "Kept");
+        assert_eq!(lines, vec![2, 5, 14, 17]);
+    }
+}
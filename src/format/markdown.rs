@@ -0,0 +1,280 @@
+//! Markdown checklists grouped under headings, as kept in `TODO.md` files:
+//!
+//! ```markdown
+//! # Work
+//! - [ ] Write report #writing <!-- id:7f0c... -->
+//!   - [x] Draft <!-- id:2a91... -->
+//! ```
+//!
+//! * The nearest heading above an item is its category, items above the
+//!   first heading get the default one.
+//! * An item indented under another one is its subtask.
+//! * `#tags` in the text become tags.
+//! * `[x]` is `Completed`, `[ ]` is `Created`.
+//! * The `<!-- id:... -->` comment keeps the task id across round-trips,
+//!   items without it get a new id.
+//!
+//! Other lines are ignored on import. The export writes the tasks of the
+//! default category first, without heading, then one `#` heading per
+//! category. A subtask is nested under its parent when both share the
+//! category. Priorities, dates and descriptions are not written, `Progress`
+//! is written as open and `Archived` as checked.
+
+use uuid::Uuid;
+
+use crate::prelude::*;
+use crate::prelude::load::TodoListLoad;
+use crate::TodoList;
+
+/// Tasks read from a Markdown checklist.
+pub struct Markdown {
+    tasks: Vec<Task>,
+    categories: Vec<Category>,
+    tags: Vec<Tag>
+}
+
+impl Markdown {
+    pub fn parse (text: &str) -> Result<Self, &'static str> {
+        let mut tasks: Vec<Task> = vec![];
+        let mut category = Category::default();
+        // indent and id of the items the next one may be nested in
+        let mut parents: Vec<(usize, Uuid)> = vec![];
+
+        for line in text.lines() {
+            let trimmed = line.trim_start();
+
+            if trimmed.starts_with('#') {
+                let title = trimmed.trim_start_matches('#');
+
+                if title.starts_with(' ') || title.is_empty() {
+                    category = if title.trim().is_empty() { Category::default() } else { Category::new(title)? };
+                    parents.clear();
+                    continue;
+                }
+            }
+
+            let Some((checked, rest)) = parse_item(trimmed) else {
+                continue;
+            };
+
+            let indent = line.chars().take_while(|c| c.is_whitespace()).map(|c| if c == '\t' { 4 } else { 1 }).sum();
+
+            while parents.last().is_some_and(|(parent, _)| *parent >= indent) {
+                parents.pop();
+            }
+
+            let task = parse_task(rest, checked, category.clone(), parents.last().map(|(_, id)| *id))?;
+
+            if tasks.iter().any(|item| item.get_id() == task.get_id()) {
+                return Err("The task id is used twice");
+            }
+
+            parents.push((indent, *task.get_id()));
+            tasks.push(task);
+        }
+
+        let (categories, tags) = super::collect_registries(&tasks);
+
+        Ok(Self { tasks, categories, tags })
+    }
+}
+
+impl TodoListLoad for Markdown {
+    fn get_todolist_categories (&self) -> Vec<Category> {
+        self.categories.clone()
+    }
+
+    fn get_todolist_tasks (&self) -> Vec<Task> {
+        self.tasks.clone()
+    }
+
+    fn get_todolist_tags (&self) -> Vec<Tag> {
+        self.tags.clone()
+    }
+}
+
+/// The tasks of `todolist` as Markdown checklists grouped by category.
+pub fn export (todolist: &TodoList) -> String {
+    let mut categories: Vec<&Category> = vec![];
+
+    for task in todolist.get_tasks() {
+        if !categories.contains(&task.get_category()) {
+            categories.push(task.get_category());
+        }
+    }
+
+    categories.sort_by_key(|category| **category != Category::default());
+
+    let mut lines = vec![];
+
+    for category in categories {
+        let tasks: Vec<&Task> = todolist.get_tasks().iter().filter(|task| task.get_category() == category).collect();
+
+        if *category != Category::default() {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(format!("# {}", category.get_title()));
+        }
+
+        // top-level tasks are those without a parent in the same group
+        for task in tasks.iter().filter(|task| task.get_parent().is_none_or(|parent| tasks.iter().all(|item| item.get_id() != parent))) {
+            write_tree(&mut lines, task, &tasks, 0);
+        }
+    }
+
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+fn write_tree (lines: &mut Vec<String>, task: &Task, tasks: &[&Task], depth: usize) {
+    let checked = if matches!(task.get_status(), Status::Completed | Status::Archived) { 'x' } else { ' ' };
+    let tags: String = task.get_tags().iter().map(|tag| format!(" #{}", tag.get_title())).collect();
+
+    lines.push(format!("{}- [{}] {}{} <!-- id:{} -->", "  ".repeat(depth), checked, task.get_title(), tags, task.get_id()));
+
+    for child in tasks.iter().filter(|item| item.get_parent() == Some(task.get_id())) {
+        write_tree(lines, child, tasks, depth + 1);
+    }
+}
+
+/// Splits `- [x] rest` into whether it is checked and the rest.
+fn parse_item (line: &str) -> Option<(bool, &str)> {
+    let line = line.strip_prefix(['-', '*', '+'])?.trim_start();
+
+    let checked = match line.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None
+    };
+
+    Some((checked, &line[3..]))
+}
+
+fn parse_task (text: &str, checked: bool, category: Category, parent: Option<Uuid>) -> Result<Task, &'static str> {
+    let mut text = text.trim();
+    let mut id = None;
+
+    if let Some(start) = text.rfind("<!--") {
+        let comment = text[start..].strip_prefix("<!--").and_then(|comment| comment.strip_suffix("-->")).unwrap_or_default().trim();
+
+        if let Some(value) = comment.strip_prefix("id:") {
+            id = Some(Uuid::parse_str(value.trim()).map_err(|_| "Invalid task id")?);
+            text = text[..start].trim_end();
+        }
+    }
+
+    let mut title = vec![];
+    let mut tags: Vec<Tag> = vec![];
+
+    for word in text.split_whitespace() {
+        match word.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+            Some(tag) => {
+                let tag = Tag::new(tag)?;
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            None => title.push(word)
+        }
+    }
+
+    let mut builder = Task::new(&title.join(" "))?
+        .category(category)
+        .tags(&tags)
+        .status(if checked { Status::Completed } else { Status::Created });
+
+    if let Some(id) = id {
+        builder = builder.id(id);
+    }
+    if let Some(parent) = parent {
+        builder = builder.parent(parent);
+    }
+
+    Ok(builder.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse () {
+        let text = "\
+Some notes, not a task.
+
+- [ ] Inbox item
+
+## Work
+
+* [x] Write report #writing #docs
+  - [ ] Draft
+    - [X] Outline
+  - [ ] Review #docs
+- [ ] Ship
+- not a checkbox
+
+# Home
+\t+ [ ] Groceries
+";
+
+        let todolist = TodoList::init(Markdown::parse(text).unwrap());
+        let tasks = todolist.get_tasks();
+        let titles: Vec<&str> = tasks.iter().map(|task| task.get_title()).collect();
+
+        assert_eq!(titles, vec!["Inbox item", "Write report", "Draft", "Outline", "Review", "Ship", "Groceries"]);
+
+        assert_eq!(tasks[0].get_category(), &Category::default());
+        assert_eq!(tasks[1].get_category().get_title(), "Work");
+        assert_eq!(tasks[1].get_status(), &Status::Completed);
+        assert_eq!(tasks[1].get_tags(), &vec![Tag::new("writing").unwrap(), Tag::new("docs").unwrap()]);
+
+        assert_eq!(tasks[2].get_parent(), Some(tasks[1].get_id()));
+        assert_eq!(tasks[3].get_parent(), Some(tasks[2].get_id()));
+        assert_eq!(tasks[3].get_status(), &Status::Completed);
+        assert_eq!(tasks[4].get_parent(), Some(tasks[1].get_id()));
+        assert_eq!(tasks[5].get_parent(), None);
+        assert_eq!(tasks[6].get_category().get_title(), "Home");
+        assert_eq!(tasks[6].get_parent(), None);
+
+        assert_eq!(todolist.get_categories().len(), 3);
+        assert_eq!(todolist.get_tags().len(), 2);
+    }
+
+    #[test]
+    fn round_trip_keeps_ids () {
+        let text = "\
+- [ ] Inbox item
+
+# Work
+- [x] Write report #writing
+  - [ ] Draft
+- [ ] Ship
+";
+
+        let todolist = TodoList::init(Markdown::parse(text).unwrap());
+        let exported = export(&todolist);
+
+        assert_eq!(exported.lines().filter(|line| line.contains("<!-- id:")).count(), 4);
+        assert!(exported.contains(&format!("  - [ ] Draft <!-- id:{} -->\n", todolist.get_tasks()[2].get_id())));
+
+        let imported = TodoList::init(Markdown::parse(&exported).unwrap());
+        assert_eq!(export(&imported), exported);
+
+        for (task, original) in imported.get_tasks().iter().zip(todolist.get_tasks()) {
+            assert_eq!(task.get_id(), original.get_id());
+            assert_eq!(task.get_parent(), original.get_parent());
+        }
+    }
+
+    #[test]
+    fn invalid_input () {
+        assert!(Markdown::parse("- [ ] #only-tags").is_err());
+        assert!(Markdown::parse("- [ ] Task <!-- id:nope -->").is_err());
+
+        let todolist = TodoList::init(Markdown::parse("- [ ] <!-->").unwrap());
+        assert_eq!(todolist.get_tasks()[0].get_title(), "<!-->");
+
+        let id = Uuid::new_v4();
+        assert!(Markdown::parse(&format!("- [ ] A <!-- id:{} -->\n- [ ] B <!-- id:{} -->", id, id)).is_err());
+    }
+}
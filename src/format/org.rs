@@ -0,0 +1,572 @@
+//! Org-mode outlines:
+//!
+//! ```org
+//! * Work
+//! ** TODO [#A] Write report                                    :writing:
+//!    DEADLINE: <2024-05-10 Fri> SCHEDULED: <2024-05-08 Wed 09:00>
+//!    :PROPERTIES:
+//!    :ID:       7f0c...
+//!    :END:
+//!    The description.
+//! *** DONE Draft
+//! ```
+//!
+//! * A headline with a `TODO`, `STARTED`, `DONE` or `CANCELLED` keyword is a
+//!   task with the status Created, Progress, Completed or Archived.
+//! * The nearest headline above it without keyword is its category, a task
+//!   headline right above it is its parent.
+//! * `[#A]`, `[#B]` and `[#C]` are Red, Yellow and Green, no cookie is Common.
+//! * `:tags:` at the end of the headline are its tags.
+//! * `DEADLINE` and `SCHEDULED` set the deadline and scheduled start. A day
+//!   is all-day, a time range or a `<...>--<...>` range is a window.
+//! * The `ID`, `CREATED` and `UPDATED` properties keep the id and timestamps.
+//! * `- State "DONE"  from "TODO"  [...]` lines in the `LOGBOOK` drawer,
+//!   newest first, keep the status history.
+//! * Other text under the headline is the description.
+//!
+//! Org timestamps have no timezone, they are read and written in UTC and to
+//! the minute. Other keywords, drawers and repeaters are ignored. Writing
+//! then reading a list keeps the fields above, except for seconds and
+//! description lines that look like headlines or drawers. Reminders, the
+//! deadline history, `hidden_until` and the snooze count are not written,
+//! read tasks have none.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use uuid::Uuid;
+
+use crate::prelude::*;
+use crate::prelude::load::TodoListLoad;
+use crate::TodoList;
+
+/// Tasks read from an Org-mode file.
+pub struct Org {
+    tasks: Vec<Task>,
+    categories: Vec<Category>,
+    tags: Vec<Tag>
+}
+
+enum Outline {
+    Category(Category),
+    Task(Uuid)
+}
+
+/// A task headline with the lines under it, built when the next headline starts.
+struct Entry {
+    level: usize,
+    status: Status,
+    priority: Priority,
+    title: String,
+    tags: Vec<Tag>,
+    category: Category,
+    parent: Option<Uuid>,
+    body: Vec<String>
+}
+
+impl Org {
+    pub fn parse (text: &str) -> Result<Self, &'static str> {
+        let mut tasks: Vec<Task> = vec![];
+        let mut outline: Vec<(usize, Outline)> = vec![];
+        let mut entry: Option<Entry> = None;
+
+        for line in text.lines() {
+            let Some((level, headline)) = parse_stars(line) else {
+                if let Some(entry) = entry.as_mut() {
+                    entry.body.push(line.to_owned());
+                }
+                continue;
+            };
+
+            if let Some(entry) = entry.take() {
+                let level = entry.level;
+                let task = build_task(entry)?;
+
+                if tasks.iter().any(|item| item.get_id() == task.get_id()) {
+                    return Err("The task id is used twice");
+                }
+
+                outline.push((level, Outline::Task(*task.get_id())));
+                tasks.push(task);
+            }
+
+            while outline.last().is_some_and(|(outer, _)| *outer >= level) {
+                outline.pop();
+            }
+
+            let Some((status, rest)) = parse_keyword(headline) else {
+                outline.push((level, Outline::Category(Category::new(strip_tags(headline).0)?)));
+                continue;
+            };
+
+            let (priority, rest) = parse_cookie(rest);
+            let (title, tags) = strip_tags(rest);
+
+            entry = Some(Entry {
+                level,
+                status,
+                priority,
+                title: title.to_owned(),
+                tags: tags.into_iter().map(Tag::new).collect::<Result<_, _>>()?,
+                category: outline.iter().rev().find_map(|(_, item)| match item {
+                    Outline::Category(category) => Some(category.clone()),
+                    _ => None
+                }).unwrap_or_default(),
+                parent: match outline.last() {
+                    Some((_, Outline::Task(id))) => Some(*id),
+                    _ => None
+                },
+                body: vec![]
+            });
+        }
+
+        if let Some(entry) = entry {
+            let task = build_task(entry)?;
+
+            if tasks.iter().any(|item| item.get_id() == task.get_id()) {
+                return Err("The task id is used twice");
+            }
+
+            tasks.push(task);
+        }
+
+        let (categories, tags) = super::collect_registries(&tasks);
+
+        Ok(Self { tasks, categories, tags })
+    }
+}
+
+impl TodoListLoad for Org {
+    fn get_todolist_categories (&self) -> Vec<Category> {
+        self.categories.clone()
+    }
+
+    fn get_todolist_tasks (&self) -> Vec<Task> {
+        self.tasks.clone()
+    }
+
+    fn get_todolist_tags (&self) -> Vec<Tag> {
+        self.tags.clone()
+    }
+}
+
+/// The tasks of `todolist` as an Org outline, tasks of the default category
+/// at the top level and the others under one headline per category.
+pub fn export (todolist: &TodoList) -> String {
+    let mut categories: Vec<&Category> = vec![];
+
+    for task in todolist.get_tasks() {
+        if !categories.contains(&task.get_category()) {
+            categories.push(task.get_category());
+        }
+    }
+
+    categories.sort_by_key(|category| **category != Category::default());
+
+    let mut lines = vec![];
+
+    for category in categories {
+        let tasks: Vec<&Task> = todolist.get_tasks().iter().filter(|task| task.get_category() == category).collect();
+        let mut level = 1;
+
+        if *category != Category::default() {
+            lines.push(format!("* {}", category.get_title()));
+            level = 2;
+        }
+
+        for task in tasks.iter().filter(|task| task.get_parent().is_none_or(|parent| tasks.iter().all(|item| item.get_id() != parent))) {
+            write_tree(&mut lines, task, &tasks, level);
+        }
+    }
+
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+fn keyword (status: &Status) -> &'static str {
+    match status {
+        Status::Created => "TODO",
+        Status::Progress => "STARTED",
+        Status::Completed => "DONE",
+        Status::Archived => "CANCELLED"
+    }
+}
+
+fn write_tree (lines: &mut Vec<String>, task: &Task, tasks: &[&Task], level: usize) {
+    let mut headline = format!("{} {} ", "*".repeat(level), keyword(task.get_status()));
+
+    match task.get_priority() {
+        Priority::Red => headline.push_str("[#A] "),
+        Priority::Yellow => headline.push_str("[#B] "),
+        Priority::Green => headline.push_str("[#C] "),
+        Priority::Common => {}
+    }
+
+    headline.push_str(task.get_title());
+
+    if !task.get_tags().is_empty() {
+        let tags: Vec<&str> = task.get_tags().iter().map(Tag::get_title).collect();
+        headline.push_str(&format!(" :{}:", tags.join(":")));
+    }
+
+    lines.push(headline);
+
+    let indent = " ".repeat(level + 1);
+    let mut planning = vec![];
+
+    if let Some(deadline) = task.get_deadline() {
+        planning.push(format!("DEADLINE: {}", format_deadline(deadline)));
+    }
+    if let Some(scheduled) = task.get_scheduled() {
+        planning.push(format!("SCHEDULED: {}", format_deadline(scheduled)));
+    }
+    if !planning.is_empty() {
+        lines.push(format!("{}{}", indent, planning.join(" ")));
+    }
+
+    lines.push(format!("{}:PROPERTIES:", indent));
+    lines.push(format!("{}:ID:       {}", indent, task.get_id()));
+    lines.push(format!("{}:CREATED:  {}", indent, format_timestamp('[', task.get_created_at())));
+    lines.push(format!("{}:UPDATED:  {}", indent, format_timestamp('[', task.get_updated_at())));
+    lines.push(format!("{}:END:", indent));
+
+    if !task.get_status_history().is_empty() {
+        lines.push(format!("{}:LOGBOOK:", indent));
+
+        for change in task.get_status_history().iter().rev() {
+            let to = format!("\"{}\"", keyword(change.get_to()));
+            let from = format!("\"{}\"", keyword(change.get_from()));
+            lines.push(format!("{}- State {:<12} from {:<12} {}", indent, to, from, format_timestamp('[', change.get_changed_at())));
+        }
+
+        lines.push(format!("{}:END:", indent));
+    }
+
+    for line in task.get_description().lines() {
+        lines.push(if line.is_empty() { String::new() } else { format!("{}{}", indent, line) });
+    }
+
+    for child in tasks.iter().filter(|item| item.get_parent() == Some(task.get_id())) {
+        write_tree(lines, child, tasks, level + 1);
+    }
+}
+
+fn parse_stars (line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '*').count();
+    let rest = &line[level..];
+
+    (level > 0 && (rest.is_empty() || rest.starts_with(' '))).then(|| (level, rest.trim()))
+}
+
+fn parse_keyword (headline: &str) -> Option<(Status, &str)> {
+    let (keyword, rest) = headline.split_once(' ').unwrap_or((headline, ""));
+
+    let status = match keyword {
+        "TODO" => Status::Created,
+        "STARTED" => Status::Progress,
+        "DONE" => Status::Completed,
+        "CANCELLED" => Status::Archived,
+        _ => return None
+    };
+
+    Some((status, rest.trim_start()))
+}
+
+fn parse_cookie (headline: &str) -> (Priority, &str) {
+    let priority = match headline.get(..4) {
+        Some("[#A]") => Priority::Red,
+        Some("[#B]") => Priority::Yellow,
+        Some("[#C]") => Priority::Green,
+        _ => return (Priority::Common, headline)
+    };
+
+    (priority, headline[4..].trim_start())
+}
+
+/// Splits `Title :a:b:` into the title and its tags.
+fn strip_tags (headline: &str) -> (&str, Vec<&str>) {
+    let headline = headline.trim_end();
+
+    if let Some((title, tags)) = headline.rsplit_once([' ', '\t']) {
+        if tags.len() > 2 && tags.starts_with(':') && tags.ends_with(':') && !tags.contains("::") {
+            return (title.trim_end(), tags.trim_matches(':').split(':').collect());
+        }
+    }
+
+    (headline, vec![])
+}
+
+fn build_task (entry: Entry) -> Result<Task, &'static str> {
+    let mut builder = Task::new(&entry.title)?
+        .status(entry.status)
+        .priority(entry.priority)
+        .category(entry.category)
+        .tags(&entry.tags);
+
+    if let Some(parent) = entry.parent {
+        builder = builder.parent(parent);
+    }
+
+    let mut description: Vec<&str> = vec![];
+    let mut history = vec![];
+    let mut drawer: Option<String> = None;
+
+    for line in entry.body.iter() {
+        let trimmed = line.trim();
+
+        if let Some(name) = drawer.as_deref() {
+            if trimmed.eq_ignore_ascii_case(":END:") {
+                drawer = None;
+                continue;
+            }
+
+            if name == ":LOGBOOK:" {
+                history.extend(parse_state_change(trimmed));
+                continue;
+            }
+
+            let property = trimmed.strip_prefix(':').and_then(|rest| rest.split_once(':')).filter(|_| name == ":PROPERTIES:");
+
+            builder = match property.map(|(key, value)| (key.to_ascii_uppercase(), value.trim())) {
+                Some((key, value)) if key == "ID" => builder.id(Uuid::parse_str(value).map_err(|_| "Invalid task id")?),
+                Some((key, value)) if key == "CREATED" => builder.created_at(parse_timestamp(value)?.0),
+                Some((key, value)) if key == "UPDATED" => builder.updated_at(parse_timestamp(value)?.0),
+                _ => builder
+            };
+            continue;
+        }
+
+        if description.is_empty() && ["DEADLINE:", "SCHEDULED:", "CLOSED:"].iter().any(|keyword| trimmed.starts_with(keyword)) {
+            if let Some(deadline) = planning(trimmed, "DEADLINE:")? {
+                builder = builder.deadline(deadline);
+            }
+            if let Some(scheduled) = planning(trimmed, "SCHEDULED:")? {
+                builder = builder.scheduled(scheduled);
+            }
+            continue;
+        }
+
+        if trimmed.len() > 2 && trimmed.starts_with(':') && trimmed.ends_with(':') && !trimmed.contains(' ') {
+            drawer = Some(trimmed.to_ascii_uppercase());
+            continue;
+        }
+
+        description.push(line);
+    }
+
+    if !history.is_empty() {
+        history.reverse();
+        builder = builder.status_history(&history);
+    }
+
+    while description.last().is_some_and(|line| line.trim().is_empty()) {
+        description.pop();
+    }
+
+    let indent = description.iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let description: Vec<&str> = description.iter().map(|line| line.get(indent..).unwrap_or("")).collect();
+
+    if !description.is_empty() {
+        builder = builder.description(&description.join("\n"));
+    }
+
+    Ok(builder.build())
+}
+
+/// Reads a `- State "DONE"  from "TODO"  [2024-05-06 Mon 11:00]` line of a
+/// logbook. Other lines, like notes and clocks, and changes without a time
+/// are skipped.
+fn parse_state_change (line: &str) -> Option<StatusChange> {
+    let parts: Vec<&str> = line.strip_prefix("- State ")?.split('"').collect();
+
+    let (to, _) = parse_keyword(parts.get(1)?)?;
+    let (from, _) = parse_keyword(parts.get(3)?)?;
+
+    let timestamp = parts.get(4)?.trim();
+    let end = timestamp.find(']')?;
+
+    Some(StatusChange::new(parse_timestamp(&timestamp[..=end]).ok()?.0, from, to))
+}
+
+/// The timestamp after `keyword` in a planning line.
+fn planning (line: &str, keyword: &str) -> Result<Option<Deadline>, &'static str> {
+    let Some(start) = line.find(keyword) else {
+        return Ok(None);
+    };
+
+    let rest = line[start + keyword.len()..].trim_start();
+    let end = rest.find('>').ok_or("Invalid timestamp")?;
+    let (first, until) = parse_timestamp(&rest[..=end])?;
+
+    let deadline = match (rest[end + 1..].strip_prefix("--"), until) {
+        (Some(range), _) => {
+            let close = range.find('>').ok_or("Invalid timestamp")?;
+            Deadline::window(first, parse_timestamp(&range[..=close])?.0)?
+        }
+        (None, Some(until)) => Deadline::window(first, until)?,
+        (None, None) if is_all_day(&rest[..=end]) => Deadline::from(first.date_naive()),
+        (None, None) => Deadline::from(first)
+    };
+
+    Ok(Some(deadline))
+}
+
+fn is_all_day (timestamp: &str) -> bool {
+    !timestamp.contains(':')
+}
+
+/// Reads `<2024-05-10 Fri 09:00-12:00>` as its start and the end of the time range.
+fn parse_timestamp (timestamp: &str) -> Result<(DateTime<Utc>, Option<DateTime<Utc>>), &'static str> {
+    let inner = timestamp.trim()
+        .strip_prefix(['<', '['])
+        .and_then(|rest| rest.strip_suffix(['>', ']']))
+        .ok_or("Invalid timestamp")?;
+
+    let mut parts = inner.split_whitespace();
+    let date = parts.next().and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()).ok_or("Invalid timestamp")?;
+
+    let Some(times) = parts.find(|part| part.contains(':')) else {
+        return Ok((date.and_time(NaiveTime::MIN).and_utc(), None));
+    };
+
+    let time = |value: &str| NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| "Invalid timestamp");
+    let at = |time: NaiveTime| NaiveDateTime::new(date, time).and_utc();
+
+    match times.split_once('-') {
+        Some((start, end)) => Ok((at(time(start)?), Some(at(time(end)?)))),
+        None => Ok((at(time(times)?), None))
+    }
+}
+
+fn format_timestamp (open: char, at: &DateTime<Utc>) -> String {
+    let close = if open == '<' { '>' } else { ']' };
+    format!("{}{}{}", open, at.format("%Y-%m-%d %a %H:%M"), close)
+}
+
+fn format_deadline (deadline: &Deadline) -> String {
+    match deadline {
+        Deadline::Date(date) => format!("<{}>", date.format("%Y-%m-%d %a")),
+        Deadline::DateTime(at) => format_timestamp('<', at),
+        Deadline::Window { start, end } if start.date_naive() == end.date_naive() => {
+            format!("<{}-{}>", start.format("%Y-%m-%d %a %H:%M"), end.format("%H:%M"))
+        }
+        Deadline::Window { start, end } => format!("{}--{}", format_timestamp('<', start), format_timestamp('<', end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+
+    fn at (day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn parse () {
+        let text = "\
+#+TITLE: Tasks
+* TODO Inbox item
+* Work                                                         :office:
+** TODO [#A] Write report                                    :writing:docs:
+   DEADLINE: <2024-05-10 Fri> SCHEDULED: <2024-05-08 Wed 09:00>
+   :LOGBOOK:
+   - State \"DONE\" from \"TODO\"
+   :END:
+   First line.
+
+     Indented line.
+*** DONE Draft
+    CLOSED: [2024-05-07 Tue 12:00] DEADLINE: <2024-05-09 Thu 10:00-12:00>
+** Notes
+*** STARTED [#C] Review
+    SCHEDULED: <2024-05-08 Wed 09:00>--<2024-05-09 Thu 17:00>
+** CANCELLED Ship
+** WAITING Something else
+";
+
+        let todolist = TodoList::init(Org::parse(text).unwrap());
+        let tasks = todolist.get_tasks();
+        let titles: Vec<&str> = tasks.iter().map(|task| task.get_title()).collect();
+
+        assert_eq!(titles, vec!["Inbox item", "Write report", "Draft", "Review", "Ship"]);
+
+        assert_eq!(tasks[0].get_category(), &Category::default());
+
+        assert_eq!(tasks[1].get_category().get_title(), "Work");
+        assert_eq!(tasks[1].get_priority(), &Priority::Red);
+        assert_eq!(tasks[1].get_tags(), &vec![Tag::new("writing").unwrap(), Tag::new("docs").unwrap()]);
+        assert_eq!(tasks[1].get_deadline(), Some(&NaiveDate::from_ymd_opt(2024, 5, 10).unwrap().into()));
+        assert_eq!(tasks[1].get_scheduled(), Some(&at(8, 9).into()));
+        assert_eq!(tasks[1].get_description(), "First line.\n\n  Indented line.");
+
+        assert_eq!(tasks[2].get_status(), &Status::Completed);
+        assert_eq!(tasks[2].get_parent(), Some(tasks[1].get_id()));
+        assert_eq!(tasks[2].get_deadline(), Some(&Deadline::window(at(9, 10), at(9, 12)).unwrap()));
+
+        assert_eq!(tasks[3].get_category().get_title(), "Notes");
+        assert_eq!(tasks[3].get_status(), &Status::Progress);
+        assert_eq!(tasks[3].get_priority(), &Priority::Green);
+        assert_eq!(tasks[3].get_parent(), None);
+        assert_eq!(tasks[3].get_scheduled(), Some(&Deadline::window(at(8, 9), at(9, 17)).unwrap()));
+
+        assert_eq!(tasks[4].get_category().get_title(), "Work");
+        assert_eq!(tasks[4].get_status(), &Status::Archived);
+    }
+
+    #[test]
+    fn round_trip () {
+        let clock = Arc::new(FixedClock::new(at(6, 10)));
+        let mut todolist = TodoList::default().with_clock(clock.clone());
+
+        let report = todolist.new_task("Write report").unwrap()
+            .category(Category::new("Work").unwrap())
+            .tags(&[Tag::new("writing").unwrap()])
+            .priority(Priority::Yellow)
+            .description("Two\n\nparagraphs")
+            .deadline(NaiveDate::from_ymd_opt(2024, 5, 10).unwrap())
+            .scheduled(at(8, 9))
+            .build();
+        let draft = todolist.new_task("Draft").unwrap()
+            .category(Category::new("Work").unwrap())
+            .parent(*report.get_id())
+            .status(Status::Completed)
+            .deadline(Deadline::window(at(9, 22), at(10, 2)).unwrap())
+            .build();
+        let inbox = todolist.new_task("Inbox").unwrap().deadline(at(7, 12) + Duration::minutes(30)).build();
+
+        for task in [report, draft, inbox] {
+            todolist.add_task(task).unwrap();
+        }
+
+        clock.advance(Duration::hours(1));
+        let id = *todolist.get_tasks()[0].get_id();
+        todolist.get_task_mut(&id).unwrap().set_status(Status::Progress);
+
+        let org = export(&todolist);
+        assert!(org.starts_with("* TODO Inbox\n  DEADLINE: <2024-05-07 Tue 12:30>\n"));
+        assert!(org.contains("\n* Work\n** STARTED [#B] Write report :writing:\n   DEADLINE: <2024-05-10 Fri> SCHEDULED: <2024-05-08 Wed 09:00>\n"));
+        assert!(org.contains("   :LOGBOOK:\n   - State \"STARTED\"    from \"TODO\"       [2024-05-06 Mon 11:00]\n   :END:\n"));
+        assert!(org.contains("\n*** DONE Draft\n    DEADLINE: <2024-05-09 Thu 22:00>--<2024-05-10 Fri 02:00>\n"));
+
+        let imported = TodoList::init(Org::parse(&org).unwrap());
+        assert_eq!(export(&imported), org);
+
+        let mut expected: Vec<&Task> = todolist.get_tasks().iter().collect();
+        expected.rotate_right(1);
+        assert!(imported.get_tasks().iter().eq(expected.into_iter()));
+    }
+
+    #[test]
+    fn invalid_input () {
+        assert!(Org::parse("* TODO").is_err());
+        assert!(Org::parse("* TODO Task\n  DEADLINE: <tomorrow>").is_err());
+        assert!(Org::parse("* TODO Task\n  :PROPERTIES:\n  :ID: nope\n  :END:").is_err());
+    }
+}
@@ -0,0 +1,355 @@
+//! The JSON of Taskwarrior's `task export`, an array of tasks or one task per
+//! line as older versions write it.
+//!
+//! | Taskwarrior              | Task                                          |
+//! |--------------------------|-----------------------------------------------|
+//! | `uuid`                   | id                                            |
+//! | `description`            | title                                         |
+//! | `annotations`            | description, one line per annotation          |
+//! | `status`                 | `pending`, `waiting` and `recurring` are `Created`, or `Progress` with a `start`; `completed` is `Completed`, `deleted` is `Archived` |
+//! | `project`                | category                                      |
+//! | `tags`                   | tags                                          |
+//! | `priority` `H` `M` `L`   | Red, Yellow, Green, none is Common            |
+//! | `due`                    | deadline                                      |
+//! | `scheduled`              | scheduled start                               |
+//! | `wait`                   | hidden until                                  |
+//! | `entry`                  | created_at                                    |
+//! | `modified`               | updated_at                                    |
+//!
+//! Other attributes, like `end`, recurrence and user defined ones, are left
+//! out and listed by `Taskwarrior::get_unmapped`. `id` and `urgency` are
+//! computed by Taskwarrior and skipped silently.
+//!
+//! The export writes the same attributes. All-day deadlines are written as
+//! midnight UTC and windows as their end, so both read back as timed ones.
+//! Reminders and subtasks are not written.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::prelude::*;
+use crate::prelude::load::TodoListLoad;
+use crate::TodoList;
+
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// An attribute of an exported task that has no place in `Task`.
+pub struct Unmapped {
+    task: Uuid,
+    attribute: String,
+    value: String
+}
+
+impl Unmapped {
+    pub fn get_task (&self) -> &Uuid {
+        &self.task
+    }
+
+    pub fn get_attribute (&self) -> &str {
+        &self.attribute
+    }
+
+    /// The value as JSON.
+    pub fn get_value (&self) -> &str {
+        &self.value
+    }
+}
+
+impl std::fmt::Display for Unmapped {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} = {}", self.task, self.attribute, self.value)
+    }
+}
+
+/// Tasks read from a `task export`, with their original ids and timestamps.
+pub struct Taskwarrior {
+    tasks: Vec<Task>,
+    unmapped: Vec<Unmapped>,
+    categories: Vec<Category>,
+    tags: Vec<Tag>
+}
+
+impl Taskwarrior {
+    pub fn parse (text: &str) -> Result<Self, &'static str> {
+        let objects: Vec<Map<String, Value>> = if text.trim_start().starts_with('[') {
+            serde_json::from_str(text).map_err(|_| "Invalid JSON")?
+        } else {
+            text.lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| serde_json::from_str(line.trim().trim_end_matches(',')).map_err(|_| "Invalid JSON"))
+                .collect::<Result<_, _>>()?
+        };
+
+        let mut tasks: Vec<Task> = vec![];
+        let mut unmapped = vec![];
+
+        for object in objects {
+            let task = read_task(object, &mut unmapped)?;
+
+            if tasks.iter().any(|item| item.get_id() == task.get_id()) {
+                return Err("The task id is used twice");
+            }
+
+            tasks.push(task);
+        }
+
+        let (categories, tags) = super::collect_registries(&tasks);
+
+        Ok(Self { tasks, unmapped, categories, tags })
+    }
+
+    pub fn get_tasks (&self) -> &Vec<Task> {
+        &self.tasks
+    }
+
+    /// The attributes left out, in the order they were read.
+    pub fn get_unmapped (&self) -> &[Unmapped] {
+        &self.unmapped
+    }
+}
+
+impl TodoListLoad for Taskwarrior {
+    fn get_todolist_categories (&self) -> Vec<Category> {
+        self.categories.clone()
+    }
+
+    fn get_todolist_tasks (&self) -> Vec<Task> {
+        self.tasks.clone()
+    }
+
+    fn get_todolist_tags (&self) -> Vec<Tag> {
+        self.tags.clone()
+    }
+}
+
+/// The tasks of `todolist` as a JSON array `task import` reads.
+pub fn export (todolist: &TodoList) -> String {
+    let tasks: Vec<Value> = todolist.get_tasks().iter().map(write_task).collect();
+    serde_json::to_string_pretty(&tasks).unwrap() + "\n"
+}
+
+fn parse_time (value: &Value) -> Result<DateTime<Utc>, &'static str> {
+    value.as_str()
+        .and_then(|value| NaiveDateTime::parse_from_str(value, TIME_FORMAT).ok())
+        .map(|at| at.and_utc())
+        .ok_or("Invalid date")
+}
+
+fn format_time (at: &DateTime<Utc>) -> String {
+    at.format(TIME_FORMAT).to_string()
+}
+
+fn read_task (mut object: Map<String, Value>, unmapped: &mut Vec<Unmapped>) -> Result<Task, &'static str> {
+    let id = object.remove("uuid")
+        .and_then(|value| Uuid::parse_str(value.as_str()?).ok())
+        .ok_or("Invalid task id")?;
+    let title = object.remove("description").ok_or("The task has no description")?;
+
+    let mut builder = Task::new(title.as_str().ok_or("Invalid description")?)?.id(id);
+
+    let started = object.remove("start").is_some();
+    builder = builder.status(match object.remove("status").as_ref().and_then(Value::as_str) {
+        Some("pending" | "waiting" | "recurring") | None if started => Status::Progress,
+        Some("pending" | "waiting" | "recurring") | None => Status::Created,
+        Some("completed") => Status::Completed,
+        Some("deleted") => Status::Archived,
+        Some(_) => return Err("Unknown status")
+    });
+
+    if let Some(project) = object.remove("project") {
+        builder = builder.category(Category::new(project.as_str().ok_or("Invalid project")?)?);
+    }
+
+    if let Some(tags) = object.remove("tags") {
+        let tags = tags.as_array()
+            .ok_or("Invalid tags")?
+            .iter()
+            .map(|tag| Tag::new(tag.as_str().ok_or("Invalid tags")?))
+            .collect::<Result<Vec<_>, _>>()?;
+        builder = builder.tags(&tags);
+    }
+
+    if let Some(priority) = object.remove("priority") {
+        builder = builder.priority(match priority.as_str() {
+            Some("H") => Priority::Red,
+            Some("M") => Priority::Yellow,
+            Some("L") => Priority::Green,
+            _ => return Err("Unknown priority")
+        });
+    }
+
+    if let Some(annotations) = object.remove("annotations") {
+        let lines = annotations.as_array()
+            .ok_or("Invalid annotations")?
+            .iter()
+            .map(|annotation| annotation.get("description").and_then(Value::as_str).ok_or("Invalid annotations"))
+            .collect::<Result<Vec<_>, _>>()?;
+        builder = builder.description(&lines.join("\n"));
+    }
+
+    if let Some(due) = object.remove("due") {
+        builder = builder.deadline(parse_time(&due)?);
+    }
+    if let Some(scheduled) = object.remove("scheduled") {
+        builder = builder.scheduled(parse_time(&scheduled)?);
+    }
+    if let Some(wait) = object.remove("wait") {
+        builder = builder.hidden_until(parse_time(&wait)?);
+    }
+    if let Some(entry) = object.remove("entry") {
+        builder = builder.created_at(parse_time(&entry)?);
+    }
+    if let Some(modified) = object.remove("modified") {
+        builder = builder.updated_at(parse_time(&modified)?);
+    }
+
+    for (attribute, value) in object {
+        if attribute != "id" && attribute != "urgency" {
+            unmapped.push(Unmapped { task: id, attribute, value: value.to_string() });
+        }
+    }
+
+    Ok(builder.build())
+}
+
+fn write_task (task: &Task) -> Value {
+    let mut object = Map::new();
+
+    object.insert("uuid".to_owned(), json!(task.get_id()));
+    object.insert("description".to_owned(), json!(task.get_title()));
+    object.insert("status".to_owned(), json!(match task.get_status() {
+        Status::Created | Status::Progress => "pending",
+        Status::Completed => "completed",
+        Status::Archived => "deleted"
+    }));
+    object.insert("entry".to_owned(), json!(format_time(task.get_created_at())));
+    object.insert("modified".to_owned(), json!(format_time(task.get_updated_at())));
+
+    if task.get_status() == &Status::Progress {
+        object.insert("start".to_owned(), json!(format_time(task.get_updated_at())));
+    }
+
+    if task.get_category() != &Category::default() {
+        object.insert("project".to_owned(), json!(task.get_category().get_title()));
+    }
+
+    if !task.get_tags().is_empty() {
+        object.insert("tags".to_owned(), json!(task.get_tags().iter().map(Tag::get_title).collect::<Vec<_>>()));
+    }
+
+    match task.get_priority() {
+        Priority::Red => { object.insert("priority".to_owned(), json!("H")); },
+        Priority::Yellow => { object.insert("priority".to_owned(), json!("M")); },
+        Priority::Green => { object.insert("priority".to_owned(), json!("L")); },
+        Priority::Common => {}
+    }
+
+    if !task.get_description().is_empty() {
+        let annotations: Vec<Value> = task.get_description()
+            .lines()
+            .map(|line| json!({ "entry": format_time(task.get_updated_at()), "description": line }))
+            .collect();
+        object.insert("annotations".to_owned(), Value::Array(annotations));
+    }
+
+    if let Some(deadline) = task.get_deadline() {
+        let due = if deadline.is_all_day() { deadline.starts_at(&Utc) } else { deadline.due_at(&Utc) };
+        object.insert("due".to_owned(), json!(format_time(&due)));
+    }
+    if let Some(scheduled) = task.get_scheduled() {
+        object.insert("scheduled".to_owned(), json!(format_time(&scheduled.starts_at(&Utc))));
+    }
+    if let Some(until) = task.get_hidden_until() {
+        object.insert("wait".to_owned(), json!(format_time(until)));
+    }
+
+    Value::Object(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    const EXPORT: &str = r#"[
+{"id":1,"description":"Call mom","entry":"20240501T080000Z","modified":"20240502T090000Z","priority":"H","project":"Family","status":"pending","tags":["phone","home"],"uuid":"6b2e1c7e-54a0-4d3c-9a3e-2d1b7f2f0a11","due":"20240510T170000Z","urgency":9.1},
+{"id":2,"description":"Fix fence","entry":"20240501T080000Z","modified":"20240503T100000Z","start":"20240503T100000Z","status":"pending","uuid":"0f5d3a5c-1a7b-4f3e-8a43-5d6c2b1e9f22","annotations":[{"entry":"20240503T100000Z","description":"Buy nails"},{"entry":"20240503T100500Z","description":"Borrow a hammer"}],"estimate":"2h"},
+{"id":0,"description":"Pay rent","end":"20240506T120000Z","entry":"20240502T080000Z","modified":"20240506T120000Z","priority":"L","status":"completed","uuid":"c1a0b1f4-9d2e-4e5b-bf6a-7c8d9e0f1a33","wait":"20240504T000000Z"},
+{"id":0,"description":"Old idea","entry":"20240401T080000Z","modified":"20240402T080000Z","status":"deleted","uuid":"9e8d7c6b-5a4f-4e3d-8c2b-1a0f9e8d7c44"}
+]"#;
+
+    fn at (day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn parse () {
+        let import = Taskwarrior::parse(EXPORT).unwrap();
+        let todolist = TodoList::init(Taskwarrior::parse(EXPORT).unwrap());
+        let tasks = todolist.get_tasks();
+
+        assert_eq!(tasks.len(), 4);
+
+        assert_eq!(tasks[0].get_id(), &Uuid::parse_str("6b2e1c7e-54a0-4d3c-9a3e-2d1b7f2f0a11").unwrap());
+        assert_eq!(tasks[0].get_title(), "Call mom");
+        assert_eq!(tasks[0].get_status(), &Status::Created);
+        assert_eq!(tasks[0].get_priority(), &Priority::Red);
+        assert_eq!(tasks[0].get_category().get_title(), "Family");
+        assert_eq!(tasks[0].get_tags(), &vec![Tag::new("phone").unwrap(), Tag::new("home").unwrap()]);
+        assert_eq!(tasks[0].get_deadline(), Some(&at(10, 17).into()));
+        assert_eq!(tasks[0].get_created_at(), &at(1, 8));
+        assert_eq!(tasks[0].get_updated_at(), &at(2, 9));
+
+        assert_eq!(tasks[1].get_status(), &Status::Progress);
+        assert_eq!(tasks[1].get_description(), "Buy nails\nBorrow a hammer");
+        assert_eq!(tasks[1].get_category(), &Category::default());
+
+        assert_eq!(tasks[2].get_status(), &Status::Completed);
+        assert_eq!(tasks[2].get_priority(), &Priority::Green);
+        assert_eq!(tasks[2].get_hidden_until(), Some(&at(4, 0)));
+
+        assert_eq!(tasks[3].get_status(), &Status::Archived);
+
+        let unmapped: Vec<String> = import.get_unmapped().iter().map(|item| item.to_string()).collect();
+        assert_eq!(unmapped, vec![
+            "0f5d3a5c-1a7b-4f3e-8a43-5d6c2b1e9f22: estimate = \"2h\"",
+            "c1a0b1f4-9d2e-4e5b-bf6a-7c8d9e0f1a33: end = \"20240506T120000Z\""
+        ]);
+    }
+
+    #[test]
+    fn parse_lines () {
+        let text = "\
+{\"description\":\"One\",\"status\":\"pending\",\"uuid\":\"6b2e1c7e-54a0-4d3c-9a3e-2d1b7f2f0a11\",\"entry\":\"20240501T080000Z\"},
+{\"description\":\"Two\",\"status\":\"waiting\",\"uuid\":\"0f5d3a5c-1a7b-4f3e-8a43-5d6c2b1e9f22\",\"entry\":\"20240501T080000Z\"}
+";
+
+        let import = Taskwarrior::parse(text).unwrap();
+
+        assert_eq!(import.get_tasks().len(), 2);
+        assert_eq!(import.get_tasks()[1].get_updated_at(), &at(1, 8));
+        assert!(import.get_unmapped().is_empty());
+    }
+
+    #[test]
+    fn round_trip () {
+        let todolist = TodoList::init(Taskwarrior::parse(EXPORT).unwrap());
+        let imported = TodoList::init(Taskwarrior::parse(&export(&todolist)).unwrap());
+
+        assert!(imported.get_tasks().iter().eq(todolist.get_tasks().iter()));
+    }
+
+    #[test]
+    fn invalid_input () {
+        let id = "6b2e1c7e-54a0-4d3c-9a3e-2d1b7f2f0a11";
+
+        assert!(Taskwarrior::parse("[{").is_err());
+        assert!(Taskwarrior::parse(r#"[{"description":"No id"}]"#).is_err());
+        assert!(Taskwarrior::parse(&format!(r#"[{{"uuid":"{}"}}]"#, id)).is_err());
+        assert!(Taskwarrior::parse(&format!(r#"[{{"uuid":"{}","description":"A","status":"lost"}}]"#, id)).is_err());
+        assert!(Taskwarrior::parse(&format!(r#"[{{"uuid":"{}","description":"A","due":"2024-05-10"}}]"#, id)).is_err());
+        assert!(Taskwarrior::parse(&format!(r#"[{{"uuid":"{0}","description":"A"}},{{"uuid":"{0}","description":"B"}}]"#, id)).is_err());
+    }
+}
@@ -0,0 +1,338 @@
+//! Todoist CSV templates (project menu, Export as a template, CSV), with the
+//! columns `TYPE,CONTENT,DESCRIPTION,PRIORITY,INDENT,...,DATE,...`.
+//!
+//! * A `section` row is the category of the tasks below it.
+//! * A `task` row is a task, `@labels` in its content are tags.
+//! * `INDENT` above 1 makes it a subtask of the task above with one less.
+//! * `PRIORITY` 1 to 4 (p1 to p4) is Red, Yellow, Green and Common.
+//! * `DATE` as `2024-05-10` or `2024-05-10 17:00` is the deadline, in UTC.
+//! * A `note` row is added to the description of the task above.
+//!
+//! Templates have no ids nor times. A task gets the same id on every import,
+//! from its section, parent and content, and keeps the times of the task of
+//! an earlier import while it doesn't change. Other tasks are created and
+//! updated at the time of the import. Rows that fail are left out and
+//! reported by `get_errors`, as are dates in words (`every monday`), the
+//! task is then kept without deadline. Authors, assignees and durations are
+//! not read.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use uuid::Uuid;
+
+use crate::prelude::*;
+use crate::prelude::load::TodoListLoad;
+use crate::TodoList;
+
+use super::csv::{read_rows, RowError};
+use super::{stable_id, ImportPreview};
+
+/// The task fields read from the export, the others are kept on import.
+const FIELDS: &[TaskField] = &[
+    TaskField::Title, TaskField::Description, TaskField::Priority, TaskField::Category, TaskField::Tags,
+    TaskField::Parent, TaskField::Deadline, TaskField::CreatedAt, TaskField::UpdatedAt
+];
+
+/// A task row with the notes below it.
+struct Item {
+    line: usize,
+    id: Uuid,
+    title: String,
+    description: Vec<String>,
+    priority: Priority,
+    category: Category,
+    tags: Vec<Tag>,
+    parent: Option<Uuid>,
+    deadline: Option<Deadline>
+}
+
+/// Tasks read from a Todoist template, to be previewed against a list before
+/// they are applied to it.
+pub struct Todoist {
+    tasks: Vec<Task>,
+    errors: Vec<RowError>,
+    categories: Vec<Category>,
+    tags: Vec<Tag>
+}
+
+impl Todoist {
+    /// Reads `text` for importing into `todolist`, where the tasks of an
+    /// earlier import are found. Fails only when `text` is not a template.
+    pub fn parse (text: &str, todolist: &TodoList) -> Result<Self, &'static str> {
+        let mut rows = read_rows(text)?.into_iter();
+        let (_, header) = rows.next().ok_or("The CSV is not a Todoist template")?;
+        let column = |name: &str| header.iter().position(|cell| cell.trim().eq_ignore_ascii_case(name));
+
+        let (Some(kind), Some(content)) = (column("TYPE"), column("CONTENT")) else {
+            return Err("The CSV is not a Todoist template");
+        };
+        let (description, priority, indent, date) = (column("DESCRIPTION"), column("PRIORITY"), column("INDENT"), column("DATE"));
+
+        let mut items: Vec<Item> = vec![];
+        let mut errors = vec![];
+        let mut category = Category::default();
+        // indent and id of the tasks the next one may be a subtask of
+        let mut parents: Vec<(usize, Uuid)> = vec![];
+
+        for (line, row) in rows {
+            let cell = |index: Option<usize>| index.and_then(|index| row.get(index)).map(|cell| cell.trim()).unwrap_or_default();
+
+            match cell(Some(kind)).to_ascii_lowercase().as_str() {
+                "" => {}
+                "section" => {
+                    parents.clear();
+                    match Category::new(cell(Some(content))) {
+                        Ok(section) => category = section,
+                        Err(message) => errors.push(RowError::new(line, message))
+                    }
+                }
+                "note" => match items.last_mut() {
+                    Some(item) => item.description.push(cell(Some(content)).to_owned()),
+                    None => errors.push(RowError::new(line, "The note has no task"))
+                },
+                "task" => {
+                    let level = cell(indent).parse::<usize>().unwrap_or(1);
+
+                    while parents.last().is_some_and(|(outer, _)| *outer >= level) {
+                        parents.pop();
+                    }
+
+                    match read_item(line, cell(Some(content)), cell(description), cell(priority), &category, parents.last().map(|(_, id)| *id)) {
+                        Ok(item) if items.iter().any(|other| other.id == item.id) => errors.push(RowError::new(line, "The task is listed twice")),
+                        Ok(mut item) => {
+                            match parse_date(cell(date)) {
+                                Ok(deadline) => item.deadline = deadline,
+                                Err(message) => errors.push(RowError::new(line, message))
+                            }
+
+                            parents.push((level, item.id));
+                            items.push(item);
+                        }
+                        Err(message) => errors.push(RowError::new(line, message))
+                    }
+                }
+                _ => errors.push(RowError::new(line, "Unknown row type"))
+            }
+        }
+
+        let mut tasks = vec![];
+
+        for item in items {
+            match build_task(&item, todolist) {
+                Ok(task) => tasks.push(task),
+                Err(message) => errors.push(RowError::new(item.line, message))
+            }
+        }
+
+        errors.sort_by_key(RowError::get_line);
+
+        let (categories, tags) = super::collect_registries(&tasks);
+
+        Ok(Self { tasks, errors, categories, tags })
+    }
+
+    pub fn get_tasks (&self) -> &Vec<Task> {
+        &self.tasks
+    }
+
+    pub fn get_errors (&self) -> &[RowError] {
+        &self.errors
+    }
+
+    /// What `apply` would change in `todolist`, without changing it.
+    pub fn dry_run<'a> (&'a self, todolist: &TodoList) -> ImportPreview<'a> {
+        ImportPreview::new(&self.tasks, FIELDS, todolist)
+    }
+
+    /// Adds the read tasks to `todolist`, updating the ones of an earlier import.
+    pub fn apply (self, todolist: &mut TodoList) -> usize {
+        super::apply(self.tasks, FIELDS, todolist)
+    }
+}
+
+impl TodoListLoad for Todoist {
+    fn get_todolist_categories (&self) -> Vec<Category> {
+        self.categories.clone()
+    }
+
+    fn get_todolist_tasks (&self) -> Vec<Task> {
+        self.tasks.clone()
+    }
+
+    fn get_todolist_tags (&self) -> Vec<Tag> {
+        self.tags.clone()
+    }
+}
+
+fn read_item (line: usize, content: &str, description: &str, priority: &str, category: &Category, parent: Option<Uuid>) -> Result<Item, &'static str> {
+    let mut title = vec![];
+    let mut tags: Vec<Tag> = vec![];
+
+    for word in content.split_whitespace() {
+        match word.strip_prefix('@').filter(|label| !label.is_empty()) {
+            Some(label) => {
+                let tag = Tag::new(label)?;
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+            None => title.push(word)
+        }
+    }
+
+    let title = title.join(" ");
+    let scope = match parent {
+        Some(parent) => parent.to_string(),
+        None => category.get_title().to_owned()
+    };
+
+    Ok(Item {
+        line,
+        id: stable_id(&format!("todoist:{}/{}", scope, title)),
+        title,
+        description: if description.is_empty() { vec![] } else { vec![description.to_owned()] },
+        priority: match priority {
+            "1" => Priority::Red,
+            "2" => Priority::Yellow,
+            "3" => Priority::Green,
+            "" | "4" => Priority::Common,
+            _ => return Err("Unknown priority")
+        },
+        category: category.clone(),
+        tags,
+        parent,
+        deadline: None
+    })
+}
+
+fn parse_date (value: &str) -> Result<Option<Deadline>, &'static str> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(Some(date.into()));
+    }
+
+    ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M:%S"].iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .map(|at| Some(at.and_utc().into()))
+        .ok_or("Only dates as YYYY-MM-DD are read, the date is left out")
+}
+
+/// Builds the task of `item` with the times of the task with its id in
+/// `todolist` when nothing else changed.
+fn build_task (item: &Item, todolist: &TodoList) -> Result<Task, &'static str> {
+    let build = |created_at: DateTime<Utc>, updated_at: DateTime<Utc>| -> Result<Task, &'static str> {
+        let mut builder = Task::new(&item.title)?
+            .id(item.id)
+            .description(&item.description.join("\n"))
+            .priority(item.priority)
+            .category(item.category.clone())
+            .tags(&item.tags)
+            .created_at(created_at)
+            .updated_at(updated_at);
+
+        if let Some(parent) = item.parent {
+            builder = builder.parent(parent);
+        }
+        if let Some(deadline) = item.deadline {
+            builder = builder.deadline(deadline);
+        }
+
+        Ok(builder.build())
+    };
+
+    let Some(existing) = todolist.get_task(&item.id) else {
+        return build(todolist.now(), todolist.now());
+    };
+
+    let task = build(*existing.get_created_at(), *existing.get_updated_at())?;
+
+    if task == *existing {
+        Ok(task)
+    } else {
+        build(*existing.get_created_at(), todolist.now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::{Duration, TimeZone};
+
+    use super::*;
+
+    const TEMPLATE: &str = "\
+TYPE,CONTENT,DESCRIPTION,PRIORITY,INDENT,AUTHOR,RESPONSIBLE,DATE,DATE_LANG,TIMEZONE
+task,Call mom @phone,,1,1,Sam (1),,2024-05-10,en,Europe/Berlin
+note,Ask about the trip,,,,,,,,
+,,,,,,,,,
+section,Garden,,,,,,,,
+task,Fix fence,Back side,4,1,Sam (1),,2024-05-09 17:00,en,Europe/Berlin
+task,Buy nails @shop,,3,2,Sam (1),,,en,Europe/Berlin
+task,Mow lawn,,2,1,Sam (1),,every monday,en,Europe/Berlin
+meeting,Standup,,,,,,,,
+task,@nolabel,,4,1,,,,,
+";
+
+    fn now () -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 6, 10, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn parse () {
+        let todolist = TodoList::default().with_clock(Arc::new(FixedClock::new(now())));
+        let import = Todoist::parse(TEMPLATE, &todolist).unwrap();
+        let tasks = import.get_tasks();
+        let titles: Vec<&str> = tasks.iter().map(|task| task.get_title()).collect();
+
+        assert_eq!(titles, vec!["Call mom", "Fix fence", "Buy nails", "Mow lawn"]);
+
+        assert_eq!(tasks[0].get_priority(), &Priority::Red);
+        assert_eq!(tasks[0].get_tags(), &vec![Tag::new("phone").unwrap()]);
+        assert_eq!(tasks[0].get_description(), "Ask about the trip");
+        assert_eq!(tasks[0].get_deadline(), Some(&NaiveDate::from_ymd_opt(2024, 5, 10).unwrap().into()));
+        assert_eq!(tasks[0].get_category(), &Category::default());
+        assert_eq!(tasks[0].get_created_at(), &now());
+
+        assert_eq!(tasks[1].get_category().get_title(), "Garden");
+        assert_eq!(tasks[1].get_description(), "Back side");
+        assert_eq!(tasks[1].get_priority(), &Priority::Common);
+        assert_eq!(tasks[1].get_deadline(), Some(&Utc.with_ymd_and_hms(2024, 5, 9, 17, 0, 0).unwrap().into()));
+
+        assert_eq!(tasks[2].get_parent(), Some(tasks[1].get_id()));
+        assert_eq!(tasks[2].get_priority(), &Priority::Green);
+        assert_eq!(tasks[3].get_parent(), None);
+        assert_eq!(tasks[3].get_deadline(), None);
+
+        let errors: Vec<usize> = import.get_errors().iter().map(RowError::get_line).collect();
+        assert_eq!(errors, vec![8, 9, 10]);
+
+        assert!(Todoist::parse("title,due\nA,B", &todolist).is_err());
+    }
+
+    #[test]
+    fn preview_against_earlier_import () {
+        let clock = Arc::new(FixedClock::new(now()));
+        let mut todolist = TodoList::default().with_clock(clock.clone());
+
+        Todoist::parse(TEMPLATE, &todolist).unwrap().apply(&mut todolist);
+        assert_eq!(todolist.get_tasks().len(), 4);
+
+        clock.advance(Duration::days(1));
+
+        let newer = TEMPLATE.replace("Fix fence,Back side,4", "Fix fence,Back side,1") + "task,Rake leaves,,4,1,,,,,\n";
+        let import = Todoist::parse(&newer, &todolist).unwrap();
+        let preview = import.dry_run(&todolist);
+
+        assert_eq!(preview.get_created().iter().map(|task| task.get_title()).collect::<Vec<_>>(), vec!["Rake leaves"]);
+        assert_eq!(preview.get_updated().iter().map(|task| task.get_title()).collect::<Vec<_>>(), vec!["Fix fence"]);
+        assert_eq!(preview.get_updated()[0].get_created_at(), &now());
+        assert_eq!(preview.get_updated()[0].get_updated_at(), &clock.now());
+        assert_eq!(preview.get_unchanged().len(), 3);
+
+        assert_eq!(import.apply(&mut todolist), 2);
+        assert!(Todoist::parse(&newer, &todolist).unwrap().dry_run(&todolist).is_empty());
+    }
+}
@@ -0,0 +1,291 @@
+//! The [todo.txt](https://github.com/todotxt/todo.txt) format, one task per line:
+//! `x (A) 2024-05-06 2024-05-01 Call mom +Family @phone due:2024-05-10`.
+//!
+//! | todo.txt                    | Task                                       |
+//! |-----------------------------|--------------------------------------------|
+//! | `x`                         | `Completed`, otherwise `Created`           |
+//! | `(A)` `(B)` `(C)` `(D)`     | Red, Yellow, Green, Common                 |
+//! | first `+project`            | category                                   |
+//! | `@context`, `#hashtag`      | tags                                       |
+//! | `due:YYYY-MM-DD`            | all-day deadline                           |
+//! | `t:YYYY-MM-DD`              | all-day scheduled start                    |
+//! | creation date               | created_at                                 |
+//! | completion date             | updated_at                                 |
+//!
+//! The mapping is lossy both ways:
+//!
+//! * Priorities `(E)` to `(Z)` read as Common, and Common is written without
+//!   a priority. Completed tasks keep theirs as `pri:A`.
+//! * Further `+project`s and unknown `key:value` pairs stay in the title.
+//! * Tags are written as `@context`, spaces in a category become `_`.
+//! * `Progress` is written as an open task and `Archived` as a completed one.
+//! * Timed deadlines and windows are written as the day they are due in UTC.
+//! * Descriptions, reminders and ids are not written, read tasks get new ids.
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::prelude::*;
+use crate::prelude::load::TodoListLoad;
+use crate::TodoList;
+
+use super::csv::RowError;
+
+/// Tasks read from a todo.txt file. Lines that fail are reported by
+/// `get_errors` and left out.
+pub struct TodoTxt {
+    tasks: Vec<Task>,
+    errors: Vec<RowError>,
+    categories: Vec<Category>,
+    tags: Vec<Tag>
+}
+
+impl TodoTxt {
+    /// Reads one task per line, skipping empty lines.
+    pub fn parse (text: &str) -> Self {
+        let mut tasks = vec![];
+        let mut errors = vec![];
+
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match parse_line(line) {
+                Ok(task) => tasks.push(task),
+                Err(message) => errors.push(RowError::new(index + 1, message))
+            }
+        }
+
+        let (categories, tags) = super::collect_registries(&tasks);
+
+        Self { tasks, errors, categories, tags }
+    }
+
+    pub fn get_errors (&self) -> &[RowError] {
+        &self.errors
+    }
+}
+
+impl TodoListLoad for TodoTxt {
+    fn get_todolist_categories (&self) -> Vec<Category> {
+        self.categories.clone()
+    }
+
+    fn get_todolist_tasks (&self) -> Vec<Task> {
+        self.tasks.clone()
+    }
+
+    fn get_todolist_tags (&self) -> Vec<Tag> {
+        self.tags.clone()
+    }
+}
+
+/// The tasks of `todolist` as a todo.txt file.
+pub fn export (todolist: &TodoList) -> String {
+    todolist.get_tasks().iter().map(|task| format_line(task) + "\n").collect()
+}
+
+fn parse_date (word: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()
+}
+
+fn midnight (date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap().and_utc()
+}
+
+fn parse_priority (letter: &str) -> Option<Priority> {
+    match letter {
+        "A" => Some(Priority::Red),
+        "B" => Some(Priority::Yellow),
+        "C" => Some(Priority::Green),
+        _ if letter.len() == 1 && letter.chars().all(|c| c.is_ascii_uppercase()) => Some(Priority::Common),
+        _ => None
+    }
+}
+
+fn priority_letter (priority: &Priority) -> Option<char> {
+    match priority {
+        Priority::Red => Some('A'),
+        Priority::Yellow => Some('B'),
+        Priority::Green => Some('C'),
+        Priority::Common => None
+    }
+}
+
+fn parse_line (line: &str) -> Result<Task, &'static str> {
+    let mut words = line.split_whitespace().peekable();
+
+    let completed = words.next_if_eq(&"x").is_some();
+    let completed_at = if completed { words.next_if(|word| parse_date(word).is_some()).and_then(parse_date) } else { None };
+
+    let mut priority = words.peek()
+        .and_then(|word| word.strip_prefix('(')?.strip_suffix(')'))
+        .and_then(parse_priority);
+    if priority.is_some() {
+        words.next();
+    }
+
+    let created_at = words.next_if(|word| parse_date(word).is_some()).and_then(parse_date);
+
+    let mut title = vec![];
+    let mut category = None;
+    let mut tags: Vec<Tag> = vec![];
+    let mut due = None;
+    let mut threshold = None;
+
+    for word in words {
+        if let Some(project) = word.strip_prefix('+').filter(|project| !project.is_empty() && category.is_none()) {
+            category = Some(Category::new(project)?);
+        } else if let Some(tag) = word.strip_prefix(['@', '#']).filter(|tag| !tag.is_empty()) {
+            let tag = Tag::new(tag)?;
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        } else if let Some(date) = word.strip_prefix("due:").and_then(parse_date) {
+            due = Some(date);
+        } else if let Some(date) = word.strip_prefix("t:").and_then(parse_date) {
+            threshold = Some(date);
+        } else if let Some(letter) = word.strip_prefix("pri:").filter(|_| completed) {
+            priority = parse_priority(letter);
+        } else {
+            title.push(word);
+        }
+    }
+
+    let mut builder = Task::new(&title.join(" "))?
+        .status(if completed { Status::Completed } else { Status::Created })
+        .priority(priority.unwrap_or_default())
+        .tags(&tags);
+
+    if let Some(category) = category {
+        builder = builder.category(category);
+    }
+    if let Some(date) = due {
+        builder = builder.deadline(date);
+    }
+    if let Some(date) = threshold {
+        builder = builder.scheduled(date);
+    }
+    if let Some(date) = created_at {
+        builder = builder.created_at(midnight(date));
+    }
+    if let Some(date) = completed_at {
+        builder = builder.updated_at(midnight(date));
+    }
+
+    Ok(builder.build())
+}
+
+fn format_line (task: &Task) -> String {
+    let mut words = vec![];
+    let completed = matches!(task.get_status(), Status::Completed | Status::Archived);
+
+    if completed {
+        words.push("x".to_owned());
+        words.push(task.get_updated_at().format("%Y-%m-%d").to_string());
+    } else if let Some(letter) = priority_letter(task.get_priority()) {
+        words.push(format!("({})", letter));
+    }
+
+    words.push(task.get_created_at().format("%Y-%m-%d").to_string());
+    words.push(task.get_title().to_owned());
+
+    if task.get_category() != &Category::default() {
+        words.push(format!("+{}", task.get_category().get_title().replace(' ', "_")));
+    }
+
+    words.extend(task.get_tags().iter().map(|tag| format!("@{}", tag.get_title())));
+
+    if let Some(deadline) = task.get_deadline() {
+        words.push(format!("due:{}", deadline.date_in(&Utc).format("%Y-%m-%d")));
+    }
+
+    if let Some(scheduled) = task.get_scheduled() {
+        words.push(format!("t:{}", scheduled.date_in(&Utc).format("%Y-%m-%d")));
+    }
+
+    if let Some(letter) = priority_letter(task.get_priority()).filter(|_| completed) {
+        words.push(format!("pri:{}", letter));
+    }
+
+    words.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn date (day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    #[test]
+    fn parse () {
+        let text = "\
+            (A) 2024-05-01 Call mom +Family @phone due:2024-05-10\n\
+            \n\
+            x 2024-05-06 2024-05-02 Pay rent +Home #bills pri:B\n\
+            (E) Read +book +later see http://example.com t:2024-05-08\n";
+
+        let todolist = TodoList::init(TodoTxt::parse(text));
+        let tasks = todolist.get_tasks();
+
+        assert_eq!(tasks.len(), 3);
+
+        assert_eq!(tasks[0].get_title(), "Call mom");
+        assert_eq!(tasks[0].get_priority(), &Priority::Red);
+        assert_eq!(tasks[0].get_category().get_title(), "Family");
+        assert_eq!(tasks[0].get_tags(), &vec![Tag::new("phone").unwrap()]);
+        assert_eq!(tasks[0].get_deadline(), Some(&date(10).into()));
+        assert_eq!(tasks[0].get_created_at(), &midnight(date(1)));
+        assert_eq!(tasks[0].get_status(), &Status::Created);
+
+        assert_eq!(tasks[1].get_title(), "Pay rent");
+        assert_eq!(tasks[1].get_status(), &Status::Completed);
+        assert_eq!(tasks[1].get_priority(), &Priority::Yellow);
+        assert_eq!(tasks[1].get_updated_at(), &midnight(date(6)));
+        assert_eq!(tasks[1].get_created_at(), &midnight(date(2)));
+
+        assert_eq!(tasks[2].get_title(), "Read +later see http://example.com");
+        assert_eq!(tasks[2].get_priority(), &Priority::Common);
+        assert_eq!(tasks[2].get_scheduled(), Some(&date(8).into()));
+
+        assert_eq!(todolist.get_categories().len(), 3);
+        assert_eq!(todolist.get_tags().len(), 2);
+
+        assert!(TodoTxt::parse(text).get_errors().is_empty());
+
+        let todotxt = TodoTxt::parse("Call mom\n\nx 2024-05-06 +Home\nPay rent\n");
+        assert_eq!(todotxt.get_errors(), &[RowError::new(3, "Title is empty.")]);
+        assert_eq!(TodoList::init(todotxt).get_tasks().len(), 2);
+    }
+
+    #[test]
+    fn round_trip () {
+        let text = "\
+            (A) 2024-05-01 Call mom +Family @phone @home due:2024-05-10\n\
+            x 2024-05-06 2024-05-02 Pay rent +Home @bills pri:B\n\
+            2024-05-03 Read t:2024-05-08\n";
+
+        assert_eq!(export(&TodoList::init(TodoTxt::parse(text))), text);
+    }
+
+    #[test]
+    fn lossy_export () {
+        let now = Utc.with_ymd_and_hms(2024, 5, 6, 22, 0, 0).unwrap();
+        let mut todolist = TodoList::default();
+
+        let task = Task::new("Sync").unwrap()
+            .category(Category::new("Side project").unwrap())
+            .status(Status::Progress)
+            .deadline(now + chrono::Duration::hours(3))
+            .description("Not written")
+            .created_at(now)
+            .build();
+        todolist.add_task(task).unwrap();
+
+        assert_eq!(export(&todolist), "2024-05-06 Sync +Side_project due:2024-05-07\n");
+    }
+}
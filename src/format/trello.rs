@@ -0,0 +1,308 @@
+//! Trello board exports (Menu, Print and export, Export as JSON).
+//!
+//! * A card is a task: its name is the title, its description the
+//!   description, `due` the deadline and `start` the scheduled start.
+//! * Its list is its category, or its status when the list is mapped with
+//!   `TrelloMapping::status`, those cards keep the default category.
+//! * Labels are tags, a label without name is tagged with its color.
+//! * An archived card or a card in an archived list is `Archived`, a card
+//!   with its due date marked complete is `Completed`.
+//! * Checklist items are subtasks of their card, `Completed` when checked.
+//!
+//! Tasks get the same id on every import, read from the card, so a newer
+//! export of the board updates the tasks of the last one. Cards are created
+//! at the time their id was made and updated at their last activity.
+//! Members, comments, attachments and custom fields are not read.
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::prelude::*;
+use crate::prelude::load::TodoListLoad;
+use crate::TodoList;
+
+use super::{stable_id, ImportPreview};
+
+/// The task fields read from the export, the others are kept on import.
+const FIELDS: &[TaskField] = &[
+    TaskField::Title, TaskField::Description, TaskField::Status, TaskField::Category, TaskField::Tags,
+    TaskField::Parent, TaskField::Deadline, TaskField::Scheduled, TaskField::CreatedAt, TaskField::UpdatedAt
+];
+
+#[derive(Deserialize)]
+struct Board {
+    #[serde(default)]
+    lists: Vec<List>,
+    #[serde(default)]
+    cards: Vec<Card>,
+    #[serde(default)]
+    checklists: Vec<Checklist>
+}
+
+#[derive(Deserialize)]
+struct List {
+    id: String,
+    name: String,
+    #[serde(default)]
+    closed: bool
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Card {
+    id: String,
+    name: String,
+    #[serde(default)]
+    desc: String,
+    id_list: String,
+    #[serde(default)]
+    closed: bool,
+    due: Option<DateTime<Utc>>,
+    start: Option<DateTime<Utc>>,
+    #[serde(default)]
+    due_complete: bool,
+    #[serde(default)]
+    labels: Vec<Label>,
+    date_last_activity: Option<DateTime<Utc>>
+}
+
+#[derive(Deserialize)]
+struct Label {
+    #[serde(default)]
+    name: String,
+    color: Option<String>
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Checklist {
+    id_card: String,
+    #[serde(default)]
+    check_items: Vec<CheckItem>
+}
+
+#[derive(Deserialize)]
+struct CheckItem {
+    id: String,
+    name: String,
+    state: String
+}
+
+/// Which Trello lists are statuses rather than categories.
+#[derive(Default)]
+pub struct TrelloMapping {
+    statuses: Vec<(String, Status)>
+}
+
+impl TrelloMapping {
+    pub fn new () -> Self {
+        Self::default()
+    }
+
+    /// Gives the cards of the list named `list` the status `status`.
+    pub fn status (mut self, list: &str, status: Status) -> Self {
+        self.statuses.push((list.trim().to_owned(), status));
+        self
+    }
+}
+
+/// Tasks read from a Trello board, to be previewed against a list before
+/// they are applied to it.
+pub struct Trello {
+    tasks: Vec<Task>,
+    categories: Vec<Category>,
+    tags: Vec<Tag>
+}
+
+impl Trello {
+    pub fn parse (text: &str, mapping: &TrelloMapping) -> Result<Self, &'static str> {
+        let board: Board = serde_json::from_str(text).map_err(|_| "Invalid JSON")?;
+        let mut tasks = vec![];
+
+        for card in board.cards.iter() {
+            let list = board.lists.iter().find(|list| list.id == card.id_list).ok_or("The card is in an unknown list")?;
+            let listed_status = mapping.statuses.iter().find(|(name, _)| name.eq_ignore_ascii_case(list.name.trim())).map(|(_, status)| *status);
+
+            let status = if card.closed || list.closed {
+                Status::Archived
+            } else if card.due_complete {
+                Status::Completed
+            } else {
+                listed_status.unwrap_or_default()
+            };
+
+            let category = match listed_status {
+                Some(_) => Category::default(),
+                None => Category::new(&list.name)?
+            };
+
+            let tags = card.labels.iter()
+                .filter_map(|label| Some(if label.name.trim().is_empty() { label.color.as_deref()? } else { &label.name }))
+                .map(Tag::new)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let id = stable_id(&format!("https://trello.com/c/{}", card.id));
+            let updated_at = card.date_last_activity.or_else(|| created_at(&card.id)).unwrap_or_default();
+
+            let mut builder = Task::new(&card.name)?
+                .id(id)
+                .description(&card.desc)
+                .status(status)
+                .category(category.clone())
+                .tags(&tags)
+                .created_at(created_at(&card.id).unwrap_or(updated_at))
+                .updated_at(updated_at);
+
+            if let Some(due) = card.due {
+                builder = builder.deadline(due);
+            }
+            if let Some(start) = card.start {
+                builder = builder.scheduled(start);
+            }
+
+            tasks.push(builder.build());
+
+            for item in board.checklists.iter().filter(|checklist| checklist.id_card == card.id).flat_map(|checklist| checklist.check_items.iter()) {
+                let subtask = Task::new(&item.name)?
+                    .id(stable_id(&format!("https://trello.com/c/{}#{}", card.id, item.id)))
+                    .parent(id)
+                    .category(category.clone())
+                    .status(if item.state == "complete" { Status::Completed } else { Status::Created })
+                    .created_at(created_at(&item.id).unwrap_or(updated_at))
+                    .updated_at(updated_at)
+                    .build();
+
+                tasks.push(subtask);
+            }
+        }
+
+        let (categories, tags) = super::collect_registries(&tasks);
+
+        Ok(Self { tasks, categories, tags })
+    }
+
+    pub fn get_tasks (&self) -> &Vec<Task> {
+        &self.tasks
+    }
+
+    /// What `apply` would change in `todolist`, without changing it.
+    pub fn dry_run<'a> (&'a self, todolist: &TodoList) -> ImportPreview<'a> {
+        ImportPreview::new(&self.tasks, FIELDS, todolist)
+    }
+
+    /// Adds the read tasks to `todolist`, updating the ones of an earlier import.
+    pub fn apply (self, todolist: &mut TodoList) -> usize {
+        super::apply(self.tasks, FIELDS, todolist)
+    }
+}
+
+impl TodoListLoad for Trello {
+    fn get_todolist_categories (&self) -> Vec<Category> {
+        self.categories.clone()
+    }
+
+    fn get_todolist_tasks (&self) -> Vec<Task> {
+        self.tasks.clone()
+    }
+
+    fn get_todolist_tags (&self) -> Vec<Tag> {
+        self.tags.clone()
+    }
+}
+
+/// Trello ids start with the second they were made, in hex.
+fn created_at (id: &str) -> Option<DateTime<Utc>> {
+    let seconds = i64::from_str_radix(id.get(..8)?, 16).ok()?;
+    DateTime::from_timestamp(seconds, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    const BOARD: &str = r#"{
+        "name": "Home",
+        "lists": [
+            {"id": "6630a0000000000000000001", "name": "To do", "closed": false},
+            {"id": "6630a0000000000000000002", "name": "Done", "closed": false},
+            {"id": "6630a0000000000000000003", "name": "Garden", "closed": false},
+            {"id": "6630a0000000000000000004", "name": "Old", "closed": true}
+        ],
+        "cards": [
+            {"id": "6630a1000000000000000011", "name": "Fix fence", "desc": "Back side", "idList": "6630a0000000000000000001",
+             "closed": false, "due": "2024-05-10T17:00:00.000Z", "dueComplete": false, "dateLastActivity": "2024-05-03T10:00:00.000Z",
+             "labels": [{"name": "outside", "color": "green"}, {"name": "", "color": "red"}]},
+            {"id": "6630a1000000000000000012", "name": "Pay rent", "idList": "6630a0000000000000000002", "closed": false,
+             "dateLastActivity": "2024-05-04T10:00:00.000Z", "labels": []},
+            {"id": "6630a1000000000000000013", "name": "Plant tomatoes", "idList": "6630a0000000000000000003", "closed": false,
+             "start": "2024-05-08T09:00:00.000Z", "due": "2024-05-09T17:00:00.000Z", "dueComplete": true,
+             "dateLastActivity": "2024-05-05T10:00:00.000Z", "labels": []},
+            {"id": "6630a1000000000000000014", "name": "Paint shed", "idList": "6630a0000000000000000004", "closed": false,
+             "dateLastActivity": "2024-05-05T10:00:00.000Z", "labels": []}
+        ],
+        "checklists": [
+            {"id": "6630a2000000000000000021", "idCard": "6630a1000000000000000011", "checkItems": [
+                {"id": "6630a3000000000000000031", "name": "Buy nails", "state": "complete"},
+                {"id": "6630a3000000000000000032", "name": "Borrow a hammer", "state": "incomplete"}
+            ]}
+        ]
+    }"#;
+
+    fn mapping () -> TrelloMapping {
+        TrelloMapping::new().status("To do", Status::Created).status("done", Status::Completed)
+    }
+
+    #[test]
+    fn parse () {
+        let todolist = TodoList::init(Trello::parse(BOARD, &mapping()).unwrap());
+        let tasks = todolist.get_tasks();
+        let titles: Vec<&str> = tasks.iter().map(|task| task.get_title()).collect();
+
+        assert_eq!(titles, vec!["Fix fence", "Buy nails", "Borrow a hammer", "Pay rent", "Plant tomatoes", "Paint shed"]);
+
+        assert_eq!(tasks[0].get_status(), &Status::Created);
+        assert_eq!(tasks[0].get_category(), &Category::default());
+        assert_eq!(tasks[0].get_description(), "Back side");
+        assert_eq!(tasks[0].get_tags(), &vec![Tag::new("outside").unwrap(), Tag::new("red").unwrap()]);
+        assert_eq!(tasks[0].get_deadline(), Some(&Utc.with_ymd_and_hms(2024, 5, 10, 17, 0, 0).unwrap().into()));
+        assert_eq!(tasks[0].get_created_at(), &DateTime::from_timestamp(0x6630a100, 0).unwrap());
+        assert_eq!(tasks[0].get_updated_at(), &Utc.with_ymd_and_hms(2024, 5, 3, 10, 0, 0).unwrap());
+
+        assert_eq!(tasks[1].get_parent(), Some(tasks[0].get_id()));
+        assert_eq!(tasks[1].get_status(), &Status::Completed);
+        assert_eq!(tasks[2].get_status(), &Status::Created);
+
+        assert_eq!(tasks[3].get_status(), &Status::Completed);
+        assert_eq!(tasks[4].get_category().get_title(), "Garden");
+        assert_eq!(tasks[4].get_status(), &Status::Completed);
+        assert_eq!(tasks[4].get_scheduled(), Some(&Utc.with_ymd_and_hms(2024, 5, 8, 9, 0, 0).unwrap().into()));
+        assert_eq!(tasks[5].get_status(), &Status::Archived);
+    }
+
+    #[test]
+    fn preview_against_earlier_import () {
+        let mut todolist = TodoList::init(Trello::parse(BOARD, &mapping()).unwrap());
+
+        let newer = BOARD
+            .replace("\"name\": \"Pay rent\"", "\"name\": \"Pay the rent\"")
+            .replace("\"cards\": [", r#""cards": [{"id": "6630a1000000000000000015", "name": "Mow lawn", "idList": "6630a0000000000000000003"},"#);
+        let import = Trello::parse(&newer, &mapping()).unwrap();
+        let preview = import.dry_run(&todolist);
+
+        assert_eq!(preview.get_created().iter().map(|task| task.get_title()).collect::<Vec<_>>(), vec!["Mow lawn"]);
+        assert_eq!(preview.get_updated().iter().map(|task| task.get_title()).collect::<Vec<_>>(), vec!["Pay the rent"]);
+        assert_eq!(preview.get_unchanged().len(), 5);
+
+        assert_eq!(import.apply(&mut todolist), 2);
+        assert_eq!(todolist.get_tasks().len(), 7);
+        assert!(Trello::parse(&newer, &mapping()).unwrap().dry_run(&todolist).is_empty());
+    }
+
+    #[test]
+    fn invalid_input () {
+        assert!(Trello::parse("{", &TrelloMapping::new()).is_err());
+        assert!(Trello::parse(r#"{"cards": [{"id": "1", "name": "Lost", "idList": "2"}]}"#, &TrelloMapping::new()).is_err());
+    }
+}
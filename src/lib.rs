@@ -1,8 +1,32 @@
 pub mod prelude;
+pub mod clock;
+pub mod calendar;
+pub mod format;
+// There is no C host in the browser, and its symbols would clash with the JS exports.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub mod ffi;
+// The browser has no file system to keep the list in.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub mod store;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 mod task;
+mod agenda;
+mod reminder;
+mod drift;
+mod merge;
+mod replica;
 
 use prelude::*;
 use prelude::load::TodoListLoad;
+use uuid::Uuid;
+use serde::{Serialize, Deserialize};
+use std::sync::Arc;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use calendar::WorkingCalendar;
+use clock::{Clock, ClockHandle, IdGenerator, IdsHandle};
 
 pub enum By<'a> {
     Category(&'a Category),
@@ -11,57 +35,183 @@ pub enum By<'a> {
     Status(Status)
 }
 
+#[derive(Default, Serialize, Deserialize)]
+#[serde(try_from = "TodoListFields")]
 pub struct TodoList {
+    tasks: Vec<Task>,
+    categories: Vec<Category>,
+    tags: Vec<Tag>,
+
+    #[serde(skip)]
+    clock: ClockHandle,
+    #[serde(skip)]
+    ids: IdsHandle,
+    #[serde(skip)]
+    calendar: WorkingCalendar
+}
+
+/// A list as serialized, refused when it holds a task, category or tag twice.
+#[derive(Deserialize)]
+struct TodoListFields {
     tasks: Vec<Task>,
     categories: Vec<Category>,
     tags: Vec<Tag>
 }
 
+impl TryFrom<TodoListFields> for TodoList {
+    type Error = &'static str;
+
+    fn try_from (fields: TodoListFields) -> Result<Self, Self::Error> {
+        let mut todolist = TodoList::default();
+        let mut ids = std::collections::HashSet::new();
+
+        for task in fields.tasks {
+            if !ids.insert(*task.get_id()) {
+                return Err("The task is already exists");
+            }
+            todolist.tasks.push(task);
+        }
+        for category in fields.categories {
+            if todolist.categories.contains(&category) {
+                return Err("The category with same title is already exists");
+            }
+            todolist.categories.push(category);
+        }
+        for tag in fields.tags {
+            if todolist.tags.contains(&tag) {
+                return Err("The tag is already exists");
+            }
+            todolist.tags.push(tag);
+        }
+
+        Ok(todolist)
+    }
+}
+
 impl TodoList {
     pub fn init (loader: impl TodoListLoad) -> Self {
         Self {
             tasks: loader.get_todolist_tasks(),
             categories: loader.get_todolist_categories(),
-            tags: loader.get_todolist_tags()
+            tags: loader.get_todolist_tags(),
+            ..Default::default()
         }
     }
 
+    /// Uses `clock` for the list and every task in it, including tasks added later.
+    pub fn with_clock (mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = ClockHandle(clock);
+
+        for task in self.tasks.iter_mut() {
+            task.set_clock(self.clock.clone());
+        }
+
+        self
+    }
+
+    /// Uses `ids` for the tasks created with `new_task`.
+    pub fn with_id_generator (mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.ids = IdsHandle(ids);
+        self
+    }
+
+    /// Uses `calendar` for working-day math, see `quick_add` and `escalate_due_within`.
+    pub fn with_calendar (mut self, calendar: WorkingCalendar) -> Self {
+        self.calendar = calendar;
+        self
+    }
+
+    pub fn now (&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    pub fn get_calendar (&self) -> &WorkingCalendar {
+        &self.calendar
+    }
+
+    /// Starts a task using the clock and id generator of the list.
+    pub fn new_task (&self, title: &str) -> Result<TaskBuilder, &'static str> {
+        Ok(Task::new(title)?.clock(self.clock.0.clone()).id_generator(self.ids.0.clone()))
+    }
+
+    /// Parses a quick-add line (see `Task::quick_add`) against the current
+    /// time in `tz` and the calendar of the list, using its clock and id generator.
+    pub fn quick_add<Tz: TimeZone> (&self, line: &str, tz: &Tz) -> Result<QuickAdd, &'static str> {
+        let quick_add = Task::quick_add_with(line, &self.now().with_timezone(tz), &self.calendar)?;
+
+        Ok(quick_add.map_builder(|builder| builder.clock(self.clock.0.clone()).id_generator(self.ids.0.clone())))
+    }
+
     pub fn get_tasks (&self) -> &Vec<Task> {
         &self.tasks
     }
 
-    pub fn add_task(&mut self, task: Task) -> Result<(), &str> {
-        if ! self.tasks.contains(&task) {
-            return Err("THe task is not exists");
+    pub fn get_task (&self, id: &Uuid) -> Option<&Task> {
+        self.tasks.iter().find(|task| task.get_id() == id)
+    }
+
+    /// Tasks whose parent is `id`, in list order.
+    pub fn get_subtasks (&self, id: &Uuid) -> Vec<&Task> {
+        self.tasks.iter().filter(|task| task.get_parent() == Some(id)).collect()
+    }
+
+    pub fn get_task_mut (&mut self, id: &Uuid) -> Option<&mut Task> {
+        self.tasks.iter_mut().find(|task| task.get_id() == id)
+    }
+
+    /// Adds `task` at the end, with the clock of the list unless it was built
+    /// with its own, see `TaskBuilder::clock`.
+    pub fn add_task(&mut self, mut task: Task) -> Result<(), &str> {
+        if self.tasks.iter().any(|item| item.get_id() == task.get_id()) {
+            return Err("The task is already exists");
         }
 
+        task.adopt_clock(&self.clock);
+
         self.tasks.push(task);
 
         Ok(())
     }
 
-    pub fn remove_task (&mut self, task: Task) -> Result<(), &str> {
-        if ! self.tasks.contains(&task) {
-            return Err("THe task is not exists");
-        }
+    /// Puts `task` in place of the listed task with the same id and returns
+    /// the old one. The clock is taken as by `add_task`.
+    pub fn replace_task (&mut self, mut task: Task) -> Result<Task, &str> {
+        let Some(index) = self.tasks.iter().position(|item| item.get_id() == task.get_id()) else {
+            return Err("The task is not exists");
+        };
 
-        self.tasks.retain(|item| item.get_title() != item.get_title());
+        task.adopt_clock(&self.clock);
+
+        Ok(std::mem::replace(&mut self.tasks[index], task))
+    }
+
+    pub fn remove_task (&mut self, task: Task) -> Result<(), &str> {
+        self.remove_task_by_id(task.get_id())?;
 
         Ok(())
     }
 
+    pub fn remove_task_by_id (&mut self, id: &Uuid) -> Result<Task, &str> {
+        match self.tasks.iter().position(|item| item.get_id() == id) {
+            Some(index) => Ok(self.tasks.remove(index)),
+            None => Err("The task is not exists")
+        }
+    }
+
     pub fn get_categories (&self) -> &Vec<Category> {
         &self.categories
     }
 
     pub fn new_category (&mut self, title: &str) -> Result<(), &str> {
+        let category = Category::new(title)?;
+
         for item in self.categories.iter() {
-            if item.get_title() == title {
+            if item.get_title() == category.get_title() {
                 return Err("The category with same title is already exists");
             }
         }
 
-        self.categories.push(Category::new(title)?);
+        self.categories.push(category);
 
         Ok(())
     }
@@ -91,6 +241,70 @@ impl TodoList {
         }
     }
 
+    /// Tasks due on the current calendar day in `tz`.
+    pub fn get_tasks_due_today<Tz: TimeZone> (&self, tz: &Tz) -> Vec<&Task> {
+        self.tasks.iter().filter(|task| task.is_due_today(tz)).collect()
+    }
+
+    /// Tasks due in the current ISO week in `tz`.
+    pub fn get_tasks_due_this_week<Tz: TimeZone> (&self, tz: &Tz) -> Vec<&Task> {
+        self.tasks.iter().filter(|task| task.is_due_this_week(tz)).collect()
+    }
+
+    /// Open tasks whose deadline has passed, all-day deadlines end with their day in `tz`.
+    pub fn get_overdue_tasks<Tz: TimeZone> (&self, tz: &Tz) -> Vec<&Task> {
+        self.tasks.iter().filter(|task| task.is_overdue(tz)).collect()
+    }
+
+    /// Tasks with a deadline on a day from `from` to `to` (inclusive) in `tz`.
+    pub fn get_tasks_due_between<Tz: TimeZone> (&self, tz: &Tz, from: NaiveDate, to: NaiveDate) -> Vec<&Task> {
+        self.tasks.iter()
+            .filter(|task| task.get_deadline().is_some_and(|deadline| (from..=to).contains(&deadline.date_in(tz))))
+            .collect()
+    }
+
+    /// Tasks with a deadline, soonest first, then by priority. Tasks
+    /// without deadline are left out.
+    pub fn get_tasks_sorted_by_deadline<Tz: TimeZone> (&self, tz: &Tz) -> Vec<&Task> {
+        let mut tasks: Vec<&Task> = self.tasks.iter().filter(|task| task.has_deadline()).collect();
+        agenda::sort_by_deadline(&mut tasks, tz);
+
+        tasks
+    }
+
+    /// Raises open tasks due within `days` working days from today in `tz`,
+    /// or overdue, to at least `priority`. Returns the ids of the raised tasks.
+    pub fn escalate_due_within<Tz: TimeZone> (&mut self, tz: &Tz, days: u32, priority: Priority) -> Vec<Uuid> {
+        let limit = self.calendar.add_working_days(self.now().with_timezone(tz).date_naive(), days).unwrap_or(NaiveDate::MAX);
+        let mut raised = vec![];
+
+        for task in self.tasks.iter_mut() {
+            let due = task.get_deadline().is_some_and(|deadline| deadline.date_in(tz) <= limit);
+
+            if due && matches!(task.get_status(), Status::Created | Status::Progress) && *task.get_priority() < priority {
+                task.set_priority(priority);
+                raised.push(*task.get_id());
+            }
+        }
+
+        raised
+    }
+
+    /// Open tasks that are not deferred and whose scheduled start has come.
+    pub fn get_available_tasks<Tz: TimeZone> (&self, tz: &Tz) -> Vec<&Task> {
+        self.tasks.iter().filter(|task| task.is_available(tz)).collect()
+    }
+
+    /// Tasks scheduled to start on the current calendar day in `tz`.
+    pub fn get_tasks_starting_today<Tz: TimeZone> (&self, tz: &Tz) -> Vec<&Task> {
+        self.tasks.iter().filter(|task| task.is_starting_today(tz)).collect()
+    }
+
+    /// Tasks hidden until a later time.
+    pub fn get_deferred_tasks (&self) -> Vec<&Task> {
+        self.tasks.iter().filter(|task| task.is_deferred()).collect()
+    }
+
     fn get_tasks_by_category (&self, category: &Category) -> Vec<&Task> {
         self.tasks.iter().filter(|task| task.get_category().get_title() == category.get_title()).collect()
     }
@@ -111,6 +325,8 @@ impl TodoList {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::{Duration, TimeZone};
+    use clock::{FixedClock, SequentialIds};
 
     struct TestTodoListLoader;
     impl TodoListLoad for TestTodoListLoader {
@@ -133,9 +349,206 @@ mod tests {
         assert!(todolist.get_tasks().is_empty());
     }
 
+    #[test]
+    fn deserialize_refuses_duplicates () {
+        let mut todolist = TodoList::default();
+        todolist.add_task(Task::new("First").unwrap().build()).unwrap();
+        todolist.new_tag("rust").unwrap();
+
+        let mut json = serde_json::to_value(&todolist).unwrap();
+        assert!(serde_json::from_value::<TodoList>(json.clone()).is_ok());
+
+        let task = json["tasks"][0].clone();
+        json["tasks"].as_array_mut().unwrap().push(task);
+        assert!(serde_json::from_value::<TodoList>(json.clone()).is_err());
+
+        json["tasks"].as_array_mut().unwrap().pop();
+        let tag = json["tags"][0].clone();
+        json["tags"].as_array_mut().unwrap().push(tag);
+        assert!(serde_json::from_value::<TodoList>(json).is_err());
+    }
+
+    #[test]
+    fn new_category_refuses_trimmed_duplicate () {
+        let mut todolist = TodoList::default();
+        todolist.new_category("Work").unwrap();
+
+        assert!(todolist.new_category("Work ").is_err());
+        assert!(todolist.new_category(" Work").is_err());
+        assert_eq!(todolist.get_categories().len(), 1);
+
+        let json = serde_json::to_value(&todolist).unwrap();
+        assert!(serde_json::from_value::<TodoList>(json).is_ok());
+    }
+
     #[test]
     fn empty_categories_after_init () {
         let todolist = TodoList::init(TestTodoListLoader);
         assert!(todolist.get_categories().is_empty());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn injected_clock_and_ids () {
+        let clock = Arc::new(FixedClock::new(Utc.with_ymd_and_hms(2024, 5, 6, 10, 0, 0).unwrap()));
+        let mut todolist = TodoList::default()
+            .with_clock(clock.clone())
+            .with_id_generator(Arc::new(SequentialIds::new()));
+
+        todolist.add_task(todolist.new_task("First").unwrap().build()).unwrap();
+        todolist.add_task(Task::new("Second").unwrap().build()).unwrap();
+
+        let id = Uuid::from_u128(1);
+        assert_eq!(todolist.get_task(&id).unwrap().get_created_at(), &todolist.now());
+
+        clock.advance(Duration::hours(1));
+        for task in todolist.tasks.iter_mut() {
+            task.set_description("changed");
+            assert_eq!(task.get_updated_at(), &clock.now());
+        }
+    }
+
+    #[test]
+    fn task_keeps_its_own_clock () {
+        let list_clock = Arc::new(FixedClock::new(Utc.with_ymd_and_hms(2024, 5, 6, 10, 0, 0).unwrap()));
+        let task_clock = Arc::new(FixedClock::new(Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).unwrap()));
+        let mut todolist = TodoList::default().with_clock(list_clock.clone());
+
+        let task = Task::new("Own").unwrap().clock(task_clock.clone()).build();
+        let id = *task.get_id();
+        todolist.add_task(task).unwrap();
+        todolist.get_task_mut(&id).unwrap().set_description("changed");
+        assert_eq!(todolist.get_task(&id).unwrap().get_updated_at(), &task_clock.now());
+
+        let task = todolist.get_task(&id).unwrap().clone();
+        todolist.replace_task(task).unwrap();
+        todolist.get_task_mut(&id).unwrap().set_description("again");
+        assert_eq!(todolist.get_task(&id).unwrap().get_updated_at(), &task_clock.now());
+    }
+
+    #[test]
+    fn day_queries_use_timezone () {
+        let clock = Arc::new(FixedClock::new(Utc.with_ymd_and_hms(2024, 5, 6, 20, 0, 0).unwrap()));
+        let mut todolist = TodoList::default().with_clock(clock);
+
+        for (title, hour) in [("Late", 18), ("Tonight", 23), ("Tomorrow", 44)] {
+            let deadline = Utc.with_ymd_and_hms(2024, 5, 6, 0, 0, 0).unwrap() + Duration::hours(hour);
+            todolist.add_task(todolist.new_task(title).unwrap().deadline(deadline).build()).unwrap();
+        }
+
+        let titles = |tasks: Vec<&Task>| tasks.iter().map(|task| task.get_title().to_owned()).collect::<Vec<_>>();
+        let east = chrono::FixedOffset::east_opt(2 * 3600).unwrap();
+
+        assert_eq!(titles(todolist.get_tasks_due_today(&Utc)), vec!["Late", "Tonight"]);
+        assert_eq!(titles(todolist.get_tasks_due_today(&east)), vec!["Late"]);
+        assert_eq!(titles(todolist.get_tasks_due_this_week(&east)), vec!["Late", "Tonight", "Tomorrow"]);
+        assert_eq!(titles(todolist.get_overdue_tasks(&Utc)), vec!["Late"]);
+    }
+
+    #[test]
+    fn all_day_and_timed_deadlines () {
+        let clock = Arc::new(FixedClock::new(Utc.with_ymd_and_hms(2024, 5, 6, 20, 0, 0).unwrap()));
+        let mut todolist = TodoList::default().with_clock(clock);
+        let date = |day| NaiveDate::from_ymd_opt(2024, 5, day).unwrap();
+
+        todolist.add_task(todolist.new_task("Friday").unwrap().deadline(date(10)).build()).unwrap();
+        todolist.add_task(todolist.new_task("Thursday noon").unwrap().deadline(Utc.with_ymd_and_hms(2024, 5, 9, 12, 0, 0).unwrap()).build()).unwrap();
+        todolist.add_task(todolist.new_task("Whenever").unwrap().build()).unwrap();
+        todolist.add_task(todolist.new_task("Thursday").unwrap().deadline(date(9)).build()).unwrap();
+
+        let titles = |tasks: Vec<&Task>| tasks.iter().map(|task| task.get_title().to_owned()).collect::<Vec<_>>();
+
+        assert_eq!(titles(todolist.get_tasks_sorted_by_deadline(&Utc)), vec!["Thursday noon", "Thursday", "Friday"]);
+        assert_eq!(titles(todolist.get_tasks_due_between(&Utc, date(9), date(9))), vec!["Thursday noon", "Thursday"]);
+        assert_eq!(titles(todolist.get_tasks_due_between(&Utc, date(10), date(20))), vec!["Friday"]);
+    }
+
+    #[test]
+    fn availability_queries () {
+        let now = Utc.with_ymd_and_hms(2024, 5, 6, 10, 0, 0).unwrap();
+        let mut todolist = TodoList::default().with_clock(Arc::new(FixedClock::new(now)));
+
+        todolist.add_task(todolist.new_task("Now").unwrap().build()).unwrap();
+        todolist.add_task(todolist.new_task("Later today").unwrap().scheduled(now + Duration::hours(3)).build()).unwrap();
+        todolist.add_task(todolist.new_task("Started").unwrap().scheduled(now.date_naive()).build()).unwrap();
+        todolist.add_task(todolist.new_task("Hidden").unwrap().hidden_until(now + Duration::days(1)).build()).unwrap();
+
+        let titles = |tasks: Vec<&Task>| tasks.iter().map(|task| task.get_title().to_owned()).collect::<Vec<_>>();
+
+        assert_eq!(titles(todolist.get_available_tasks(&Utc)), vec!["Now", "Started"]);
+        assert_eq!(titles(todolist.get_tasks_starting_today(&Utc)), vec!["Later today", "Started"]);
+        assert_eq!(titles(todolist.get_deferred_tasks()), vec!["Hidden"]);
+    }
+
+    #[test]
+    fn add_and_remove_task () {
+        let mut todolist = TodoList::default();
+        let task = Task::new("First").unwrap().build();
+        let id = *task.get_id();
+
+        todolist.add_task(task).unwrap();
+        assert_eq!(todolist.get_task(&id).unwrap().get_title(), "First");
+
+        todolist.add_task(Task::new("Second").unwrap().build()).unwrap();
+
+        let task = Task::new("First").unwrap().build();
+        assert!(todolist.remove_task(task).is_err());
+        assert_eq!(todolist.get_tasks().len(), 2);
+
+        let mut task = todolist.get_task(&id).unwrap().clone();
+        task.set_title("Renamed").unwrap();
+        assert_eq!(todolist.replace_task(task).unwrap().get_title(), "First");
+        assert!(todolist.replace_task(Task::new("Third").unwrap().build()).is_err());
+
+        let removed = todolist.remove_task_by_id(&id).unwrap();
+        assert_eq!(removed.get_title(), "Renamed");
+        assert_eq!(todolist.get_tasks().len(), 1);
+    }
+
+    #[test]
+    fn subtasks () {
+        let mut todolist = TodoList::default();
+        let mut parent = Task::new("Parent").unwrap().build();
+        let id = *parent.get_id();
+
+        assert!(parent.set_parent(Some(id)).is_err());
+
+        let own = Task::new("Own parent").unwrap().parent(id).id(id).build();
+        assert_eq!(own.get_parent(), None);
+        assert!(serde_json::from_value::<Task>(serde_json::to_value(&own).unwrap()).is_ok());
+
+        todolist.add_task(parent).unwrap();
+        todolist.add_task(Task::new("First").unwrap().parent(id).build()).unwrap();
+        todolist.add_task(Task::new("Other").unwrap().build()).unwrap();
+        todolist.add_task(Task::new("Second").unwrap().parent(id).build()).unwrap();
+
+        let titles: Vec<&str> = todolist.get_subtasks(&id).iter().map(|task| task.get_title()).collect();
+        assert_eq!(titles, vec!["First", "Second"]);
+    }
+
+    #[test]
+    fn working_day_calendar () {
+        // Friday
+        let now = Utc.with_ymd_and_hms(2024, 5, 10, 10, 0, 0).unwrap();
+        let holiday = NaiveDate::from_ymd_opt(2024, 5, 14).unwrap();
+        let mut todolist = TodoList::default()
+            .with_clock(Arc::new(FixedClock::new(now)))
+            .with_id_generator(Arc::new(SequentialIds::new()))
+            .with_calendar(WorkingCalendar::new().holiday(holiday));
+
+        let task = todolist.quick_add("Ship in 2 working days !green", &Utc).unwrap().build();
+        assert_eq!(task.get_id().as_u128(), 1);
+        assert_eq!(task.get_deadline(), Some(&NaiveDate::from_ymd_opt(2024, 5, 15).unwrap().into()));
+        todolist.add_task(task).unwrap();
+
+        todolist.add_task(todolist.new_task("Late").unwrap().deadline(now - Duration::days(1)).build()).unwrap();
+        todolist.add_task(todolist.new_task("Monday").unwrap().deadline(now + Duration::days(3)).priority(Priority::Red).build()).unwrap();
+        todolist.add_task(todolist.new_task("Done").unwrap().deadline(now).status(Status::Completed).build()).unwrap();
+
+        let raised = todolist.escalate_due_within(&Utc, 1, Priority::Yellow);
+        assert_eq!(raised.iter().map(|id| id.as_u128()).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(todolist.get_task(&raised[0]).unwrap().get_priority(), &Priority::Yellow);
+
+        let raised = todolist.escalate_due_within(&Utc, 2, Priority::Yellow);
+        assert_eq!(raised.iter().map(|id| id.as_u128()).collect::<Vec<_>>(), vec![1]);
+    }
+}
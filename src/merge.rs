@@ -0,0 +1,494 @@
+//! Three-way merge of two copies of a list that diverged from a common
+//! ancestor, as `TodoList::merge`.
+//!
+//! * Tasks are matched by id. A task added on one side is kept, a task
+//!   removed on one side is removed unless the other side changed it.
+//! * A task changed on both sides is merged field by field: a field changed
+//!   on one side takes that change, a field changed on both sides to
+//!   different values is a conflict, resolved by a `MergeStrategy`.
+//! * The tags of a task are merged as a set, so tags added on both sides
+//!   are kept and never conflict. `updated_at` takes the later of both.
+//! * The status and deadline histories keep the changes of both sides,
+//!   oldest first. The status is the one of the last change in the merged
+//!   history, a task that never changed status merges it like other fields.
+//! * The categories and tags of the lists are the union of both.
+//! * A task whose merged fields don't make a valid task keeps its local
+//!   copy and is reported by `Merge::get_errors`.
+
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use crate::prelude::*;
+use crate::TodoList;
+
+/// The copy of the list a value comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeSide {
+    Local,
+    Remote
+}
+
+/// A field of a task changed to different values on both sides.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeConflict {
+    task: Uuid,
+    field: String,
+    base: Option<Value>,
+    local: Value,
+    remote: Value,
+    local_updated_at: DateTime<Utc>,
+    remote_updated_at: DateTime<Utc>,
+    resolution: Option<MergeSide>
+}
+
+impl MergeConflict {
+    pub fn get_task (&self) -> &Uuid {
+        &self.task
+    }
+
+    /// The name of the field as serialized, e.g. `description`.
+    pub fn get_field (&self) -> &str {
+        &self.field
+    }
+
+    /// The value in the common ancestor, none when both sides added the task.
+    pub fn get_base (&self) -> Option<&Value> {
+        self.base.as_ref()
+    }
+
+    pub fn get_local (&self) -> &Value {
+        &self.local
+    }
+
+    pub fn get_remote (&self) -> &Value {
+        &self.remote
+    }
+
+    pub fn get_local_updated_at (&self) -> &DateTime<Utc> {
+        &self.local_updated_at
+    }
+
+    pub fn get_remote_updated_at (&self) -> &DateTime<Utc> {
+        &self.remote_updated_at
+    }
+
+    /// The side whose value the merged task has, none while unresolved.
+    pub fn get_resolution (&self) -> Option<MergeSide> {
+        self.resolution
+    }
+}
+
+/// A task whose fields don't make a valid task.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskError {
+    task: Uuid,
+    message: &'static str
+}
+
+impl TaskError {
+    pub(crate) fn new (task: Uuid, message: &'static str) -> Self {
+        Self { task, message }
+    }
+
+    pub fn get_task (&self) -> &Uuid {
+        &self.task
+    }
+
+    pub fn get_message (&self) -> &'static str {
+        self.message
+    }
+}
+
+impl std::fmt::Display for TaskError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "task {}: {}", self.task, self.message)
+    }
+}
+
+/// Picks the side of a conflict the merged task takes.
+pub trait MergeStrategy {
+    /// None leaves the conflict to `Merge::resolve`.
+    fn resolve (&self, conflict: &MergeConflict) -> Option<MergeSide>;
+}
+
+/// The side whose task was updated last wins, the local one on a tie.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NewestWins;
+
+impl MergeStrategy for NewestWins {
+    fn resolve (&self, conflict: &MergeConflict) -> Option<MergeSide> {
+        match conflict.remote_updated_at > conflict.local_updated_at {
+            true => Some(MergeSide::Remote),
+            false => Some(MergeSide::Local)
+        }
+    }
+}
+
+/// The local side always wins.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PreferLocal;
+
+impl MergeStrategy for PreferLocal {
+    fn resolve (&self, _conflict: &MergeConflict) -> Option<MergeSide> {
+        Some(MergeSide::Local)
+    }
+}
+
+/// Every conflict is left to `Merge::resolve`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ResolveManually;
+
+impl MergeStrategy for ResolveManually {
+    fn resolve (&self, _conflict: &MergeConflict) -> Option<MergeSide> {
+        None
+    }
+}
+
+/// A merged list with the conflicts met on the way. Until a conflict is
+/// resolved, its field keeps the local value.
+pub struct Merge {
+    todolist: TodoList,
+    conflicts: Vec<MergeConflict>,
+    errors: Vec<TaskError>
+}
+
+impl Merge {
+    pub fn get_todolist (&self) -> &TodoList {
+        &self.todolist
+    }
+
+    pub fn into_todolist (self) -> TodoList {
+        self.todolist
+    }
+
+    /// All conflicts, resolved or not, in list order.
+    pub fn get_conflicts (&self) -> &[MergeConflict] {
+        &self.conflicts
+    }
+
+    pub fn get_unresolved_conflicts (&self) -> Vec<&MergeConflict> {
+        self.conflicts.iter().filter(|conflict| conflict.resolution.is_none()).collect()
+    }
+
+    /// The tasks that failed to merge and kept their local copy.
+    pub fn get_errors (&self) -> &[TaskError] {
+        &self.errors
+    }
+
+    pub fn is_resolved (&self) -> bool {
+        self.conflicts.iter().all(|conflict| conflict.resolution.is_some())
+    }
+
+    /// Gives the field of the conflict at `index` in `get_conflicts` the
+    /// value of `side`, also when it was resolved before.
+    pub fn resolve (&mut self, index: usize, side: MergeSide) -> Result<(), &'static str> {
+        let conflict = self.conflicts.get_mut(index).ok_or("The conflict is not exists")?;
+        let task = self.todolist.get_task(&conflict.task).ok_or("The task is not exists")?;
+
+        let value = match side {
+            MergeSide::Local => conflict.local.clone(),
+            MergeSide::Remote => conflict.remote.clone()
+        };
+
+        let mut fields = to_fields(task);
+        fields.insert(conflict.field.clone(), value);
+        self.todolist.replace_task(from_fields(fields)?).map_err(|_| "The task is not exists")?;
+
+        conflict.resolution = Some(side);
+        Ok(())
+    }
+}
+
+impl TodoList {
+    /// Merges this list and `remote`, both changed from `base`, see the
+    /// `merge` module. The merged list uses the clock of this one.
+    pub fn merge (&self, base: &TodoList, remote: &TodoList, strategy: &impl MergeStrategy) -> Merge {
+        let mut tasks = vec![];
+        let mut conflicts = vec![];
+        let mut errors = vec![];
+
+        for local in self.tasks.iter() {
+            let original = base.get_task(local.get_id());
+
+            match remote.get_task(local.get_id()) {
+                Some(remote) => match merge_task(original, local, remote, strategy, &mut conflicts) {
+                    Ok(task) => tasks.push(task),
+                    Err(message) => {
+                        errors.push(TaskError::new(*local.get_id(), message));
+                        tasks.push(local.clone());
+                    }
+                },
+                // removed remotely, kept when changed here
+                None if original == Some(local) => {}
+                None => tasks.push(local.clone())
+            }
+        }
+
+        for remote in remote.tasks.iter() {
+            if self.get_task(remote.get_id()).is_none() && base.get_task(remote.get_id()) != Some(remote) {
+                tasks.push(remote.clone());
+            }
+        }
+
+        for task in tasks.iter_mut() {
+            task.set_clock(self.clock.clone());
+        }
+
+        let mut categories = self.categories.clone();
+        categories.extend(remote.categories.iter().filter(|category| !self.categories.contains(category)).cloned());
+
+        let mut tags = self.tags.clone();
+        tags.extend(remote.tags.iter().filter(|tag| !self.tags.contains(tag)).cloned());
+
+        let todolist = TodoList {
+            tasks,
+            categories,
+            tags,
+            clock: self.clock.clone(),
+            ids: self.ids.clone(),
+            calendar: self.calendar.clone()
+        };
+
+        Merge { todolist, conflicts, errors }
+    }
+}
+
+fn merge_task (base: Option<&Task>, local: &Task, remote: &Task, strategy: &impl MergeStrategy, conflicts: &mut Vec<MergeConflict>) -> Result<Task, &'static str> {
+    if base == Some(remote) || local == remote {
+        return Ok(local.clone());
+    }
+    if base == Some(local) {
+        return Ok(remote.clone());
+    }
+
+    let base_fields = base.map(to_fields);
+    let local_fields = to_fields(local);
+    let remote_fields = to_fields(remote);
+    let mut merged = Map::new();
+
+    let status_history = merge_history(local.get_status_history(), remote.get_status_history(), StatusChange::get_changed_at);
+    let deadline_changes = merge_history(local.get_deadline_changes(), remote.get_deadline_changes(), DeadlineChange::get_changed_at);
+    let status = status_history.last().map(|change| serde_json::to_value(change.get_to()).unwrap_or_default());
+
+    for (field, local_value) in local_fields.iter() {
+        let remote_value = remote_fields.get(field).unwrap_or(&Value::Null);
+        let base_value = base_fields.as_ref().and_then(|fields| fields.get(field));
+
+        let value = match field.as_str() {
+            "updated_at" => serde_json::to_value(local.get_updated_at().max(remote.get_updated_at())).unwrap_or_default(),
+            "tags" => merge_tags(base, local, remote),
+            "status_changes" => serde_json::to_value(&status_history).unwrap_or_default(),
+            "deadline_changes" => serde_json::to_value(&deadline_changes).unwrap_or_default(),
+            "status" if status.is_some() => status.clone().unwrap_or_default(),
+            _ if local_value == remote_value || base_value == Some(remote_value) => local_value.clone(),
+            _ if base_value == Some(local_value) => remote_value.clone(),
+            _ => {
+                let mut conflict = MergeConflict {
+                    task: *local.get_id(),
+                    field: field.clone(),
+                    base: base_value.cloned(),
+                    local: local_value.clone(),
+                    remote: remote_value.clone(),
+                    local_updated_at: *local.get_updated_at(),
+                    remote_updated_at: *remote.get_updated_at(),
+                    resolution: None
+                };
+                conflict.resolution = strategy.resolve(&conflict);
+
+                let value = match conflict.resolution {
+                    Some(MergeSide::Remote) => remote_value.clone(),
+                    _ => local_value.clone()
+                };
+
+                conflicts.push(conflict);
+                value
+            }
+        };
+
+        merged.insert(field.clone(), value);
+    }
+
+    from_fields(merged)
+}
+
+/// The changes of both sides, each once, oldest first.
+fn merge_history<T: Clone + PartialEq> (local: &[T], remote: &[T], changed_at: fn(&T) -> &DateTime<Utc>) -> Vec<T> {
+    let mut changes = local.to_vec();
+    changes.extend(remote.iter().filter(|change| !local.contains(change)).cloned());
+    changes.sort_by(|a, b| changed_at(a).cmp(changed_at(b)));
+
+    changes
+}
+
+/// Tags of either side, less the tags of `base` one side removed.
+fn merge_tags (base: Option<&Task>, local: &Task, remote: &Task) -> Value {
+    let base_tags = base.map(|task| task.get_tags().as_slice()).unwrap_or_default();
+    let mut tags = vec![];
+
+    for tag in local.get_tags().iter().chain(remote.get_tags()) {
+        let removed = base_tags.contains(tag) && !(local.get_tags().contains(tag) && remote.get_tags().contains(tag));
+
+        if !removed && !tags.contains(tag) {
+            tags.push(tag.clone());
+        }
+    }
+
+    serde_json::to_value(tags).unwrap_or_default()
+}
+
+pub(crate) fn to_fields (task: &Task) -> Map<String, Value> {
+    match serde_json::to_value(task) {
+        Ok(Value::Object(fields)) => fields,
+        _ => Map::new()
+    }
+}
+
+pub(crate) fn from_fields (fields: Map<String, Value>) -> Result<Task, &'static str> {
+    serde_json::from_value(Value::Object(fields)).map_err(|_| "The value doesn't fit the field")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use chrono::Duration;
+
+    use super::*;
+
+    struct Copies {
+        base: TodoList,
+        local: TodoList,
+        remote: TodoList,
+        clock: Arc<FixedClock>,
+        id: Uuid
+    }
+
+    /// One task in three copies of a list, each with its own copy of `clock`.
+    fn copies () -> Copies {
+        let clock = Arc::new(FixedClock::new(Utc::now()));
+        let mut base = TodoList::default().with_clock(clock.clone());
+        let task = base.new_task("Call mom").unwrap().description("Sunday").tags(&[Tag::new("family").unwrap()]).build();
+        let id = *task.get_id();
+        base.add_task(task).unwrap();
+
+        let copy = |list: &TodoList| {
+            let mut copy = TodoList::default().with_clock(clock.clone());
+            copy.add_task(list.get_tasks()[0].clone()).unwrap();
+            copy
+        };
+
+        Copies { local: copy(&base), remote: copy(&base), base, clock, id }
+    }
+
+    #[test]
+    fn merges_fields () {
+        let Copies { base, mut local, mut remote, clock, id } = copies();
+
+        clock.advance(Duration::minutes(1));
+        local.get_task_mut(&id).unwrap().set_priority(Priority::Red);
+        local.get_task_mut(&id).unwrap().add_tag(Tag::new("phone").unwrap()).unwrap();
+        clock.advance(Duration::minutes(1));
+        remote.get_task_mut(&id).unwrap().set_description("Saturday");
+        remote.get_task_mut(&id).unwrap().remove_tag(Tag::new("family").unwrap());
+        remote.get_task_mut(&id).unwrap().add_tag(Tag::new("weekend").unwrap()).unwrap();
+
+        let merge = local.merge(&base, &remote, &ResolveManually);
+        let task = &merge.get_todolist().get_tasks()[0];
+
+        assert!(merge.get_conflicts().is_empty());
+        assert_eq!(task.get_priority(), &Priority::Red);
+        assert_eq!(task.get_description(), "Saturday");
+        assert_eq!(task.get_tags(), &vec![Tag::new("phone").unwrap(), Tag::new("weekend").unwrap()]);
+        assert_eq!(task.get_updated_at(), remote.get_tasks()[0].get_updated_at());
+    }
+
+    #[test]
+    fn adds_and_removes_tasks () {
+        let Copies { base, mut local, mut remote, clock, id } = copies();
+        let ids = |list: &TodoList| list.get_tasks().iter().map(|task| *task.get_id()).collect::<Vec<_>>();
+
+        let task = local.new_task("Buy milk").unwrap().build();
+        local.add_task(task).unwrap();
+        local.new_category("Home").unwrap();
+        let task = remote.new_task("Pay rent").unwrap().build();
+        remote.add_task(task).unwrap();
+        remote.new_tag("money").unwrap();
+
+        let merged = local.merge(&base, &remote, &NewestWins).into_todolist();
+        assert_eq!(ids(&merged), vec![id, ids(&local)[1], ids(&remote)[1]]);
+        assert_eq!(merged.get_categories(), local.get_categories());
+        assert_eq!(merged.get_tags(), remote.get_tags());
+
+        // removed remotely, unchanged here
+        remote.remove_task_by_id(&id).unwrap();
+        assert!(local.merge(&base, &remote, &NewestWins).get_todolist().get_task(&id).is_none());
+
+        // removed remotely, changed here
+        clock.advance(Duration::minutes(1));
+        local.get_task_mut(&id).unwrap().set_description("Monday");
+        assert_eq!(local.merge(&base, &remote, &NewestWins).get_todolist().get_task(&id).unwrap().get_description(), "Monday");
+    }
+
+    #[test]
+    fn merges_histories () {
+        let Copies { base, mut local, mut remote, clock, id } = copies();
+
+        clock.advance(Duration::minutes(1));
+        local.get_task_mut(&id).unwrap().set_status(Status::Progress);
+        local.get_task_mut(&id).unwrap().set_deadline(clock.now() + Duration::days(1));
+        clock.advance(Duration::minutes(1));
+        remote.get_task_mut(&id).unwrap().set_status(Status::Completed);
+        clock.advance(Duration::minutes(1));
+        local.get_task_mut(&id).unwrap().set_description("Monday");
+
+        // the local task was updated last, the status is still the one changed last
+        let merge = local.merge(&base, &remote, &NewestWins);
+        let task = &merge.get_todolist().get_tasks()[0];
+
+        assert!(merge.get_conflicts().is_empty());
+        assert_eq!(task.get_status(), &Status::Completed);
+        assert_eq!(task.get_status_history().iter().map(StatusChange::get_to).collect::<Vec<_>>(), vec![&Status::Progress, &Status::Completed]);
+        assert_eq!(task.get_deadline_changes().len(), 1);
+        assert_eq!(task.get_description(), "Monday");
+
+        let merge = remote.merge(&base, &local, &PreferLocal);
+        assert!(merge.get_todolist().get_tasks()[0] == *task);
+    }
+
+    #[test]
+    fn strategies () {
+        let Copies { base, mut local, mut remote, clock, id } = copies();
+
+        clock.advance(Duration::minutes(1));
+        local.get_task_mut(&id).unwrap().set_description("Monday");
+        clock.advance(Duration::minutes(1));
+        remote.get_task_mut(&id).unwrap().set_description("Tuesday");
+
+        let description = |merge: &Merge| merge.get_todolist().get_tasks()[0].get_description().to_owned();
+
+        let merge = local.merge(&base, &remote, &NewestWins);
+        assert_eq!(description(&merge), "Tuesday");
+        assert_eq!(merge.get_conflicts().len(), 1);
+        assert_eq!(merge.get_conflicts()[0].get_resolution(), Some(MergeSide::Remote));
+
+        let merge = local.merge(&base, &remote, &PreferLocal);
+        assert_eq!(description(&merge), "Monday");
+        assert!(merge.is_resolved());
+
+        let mut merge = local.merge(&base, &remote, &ResolveManually);
+        let conflict = &merge.get_conflicts()[0];
+        assert_eq!(conflict.get_field(), "description");
+        assert_eq!(conflict.get_task(), &id);
+        assert_eq!(conflict.get_base(), Some(&Value::from("Sunday")));
+        assert_eq!(conflict.get_local(), &Value::from("Monday"));
+        assert_eq!(conflict.get_remote(), &Value::from("Tuesday"));
+        assert_eq!(merge.get_unresolved_conflicts().len(), 1);
+        assert_eq!(description(&merge), "Monday");
+
+        merge.resolve(0, MergeSide::Remote).unwrap();
+        assert_eq!(description(&merge), "Tuesday");
+        assert!(merge.is_resolved());
+        assert!(merge.resolve(1, MergeSide::Local).is_err());
+    }
+}
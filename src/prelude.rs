@@ -2,8 +2,53 @@ pub mod load;
 
 pub use crate::task::{
     Task,
+    TaskBuilder,
+    Deadline,
+    DeadlineChange,
+    Reminder,
+    QuickAdd,
+    QuickAddToken,
     Category,
     Priority,
     Tag,
-    TaskStatus as Status
+    TaskStatus as Status,
+    StatusChange,
+    TaskField
+};
+
+pub use crate::agenda::Agenda;
+
+pub use crate::drift::DeadlineDrift;
+
+pub use crate::merge::{
+    Merge,
+    MergeConflict,
+    MergeSide,
+    MergeStrategy,
+    NewestWins,
+    PreferLocal,
+    ResolveManually,
+    TaskError
+};
+
+pub use crate::replica::Replica;
+
+pub use crate::reminder::{
+    ReminderScheduler,
+    ReminderEvent,
+    ReminderSink,
+    WriterSink,
+    FileSink,
+    CommandSink
+};
+
+pub use crate::calendar::WorkingCalendar;
+
+pub use crate::clock::{
+    Clock,
+    IdGenerator,
+    SystemClock,
+    FixedClock,
+    RandomIds,
+    SequentialIds
 };
\ No newline at end of file
@@ -0,0 +1,590 @@
+//! Python bindings, built as the `todolist` extension module.
+//!
+//! A `Task` created from Python owns its data until it is added to a
+//! `TodoList`; from then on the same object is a view of the task stored in
+//! the list, so edits made through it are visible to the list.
+//!
+//! Crate errors are mapped to Python exceptions: invalid values raise
+//! `ValueError`, duplicates raise `AlreadyExistsError` (a `ValueError`) and
+//! missing tasks raise `KeyError`.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyKeyError, PyRuntimeError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDateTime;
+use uuid::Uuid;
+
+use crate::prelude::{Category, Deadline, Priority, Status, Tag, Task};
+use crate::task::TaskBuilder;
+use crate::{By, TodoList};
+
+create_exception!(todolist, AlreadyExistsError, PyValueError);
+
+fn value_error (message: &str) -> PyErr {
+    PyValueError::new_err(message.to_owned())
+}
+
+fn removed_error () -> PyErr {
+    PyKeyError::new_err("The task is not exists")
+}
+
+fn deadline_to_py (py: Python<'_>, deadline: Deadline) -> PyResult<PyObject> {
+    Ok(match deadline {
+        Deadline::Date(date) => date.into_pyobject(py)?.into_any().unbind(),
+        Deadline::DateTime(at) => at.into_pyobject(py)?.into_any().unbind(),
+        Deadline::Window { start, end } => (start, end).into_pyobject(py)?.into_any().unbind()
+    })
+}
+
+fn deadline_from_py (value: &Bound<'_, PyAny>) -> PyResult<Deadline> {
+    // `datetime` is a subclass of `date`, check it first so naive datetimes
+    // are rejected instead of being truncated to their date.
+    if value.is_instance_of::<PyDateTime>() {
+        return Ok(value.extract::<DateTime<Utc>>()?.into());
+    }
+
+    if let Ok(date) = value.extract::<NaiveDate>() {
+        return Ok(date.into());
+    }
+
+    if let Ok((start, end)) = value.extract::<(DateTime<Utc>, DateTime<Utc>)>() {
+        return Deadline::window(start, end).map_err(value_error);
+    }
+
+    Err(PyTypeError::new_err("expected a date, an aware datetime or a (start, end) tuple"))
+}
+
+#[pyclass(name = "Priority", module = "todolist", eq, eq_int, ord)]
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub enum PyPriority {
+    Common,
+    Green,
+    Yellow,
+    Red
+}
+
+#[pymethods]
+impl PyPriority {
+    fn up (&self) -> Self {
+        Priority::from(*self).up().into()
+    }
+
+    fn down (&self) -> Self {
+        Priority::from(*self).down().into()
+    }
+
+    fn __str__ (&self) -> String {
+        Priority::from(*self).to_string()
+    }
+}
+
+impl From<PyPriority> for Priority {
+    fn from(priority: PyPriority) -> Self {
+        match priority {
+            PyPriority::Common => Self::Common,
+            PyPriority::Green => Self::Green,
+            PyPriority::Yellow => Self::Yellow,
+            PyPriority::Red => Self::Red
+        }
+    }
+}
+
+impl From<Priority> for PyPriority {
+    fn from(priority: Priority) -> Self {
+        match priority {
+            Priority::Common => Self::Common,
+            Priority::Green => Self::Green,
+            Priority::Yellow => Self::Yellow,
+            Priority::Red => Self::Red
+        }
+    }
+}
+
+#[pyclass(name = "TaskStatus", module = "todolist", eq, eq_int)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum PyTaskStatus {
+    Created,
+    Progress,
+    Completed,
+    Archived
+}
+
+#[pymethods]
+impl PyTaskStatus {
+    fn __str__ (&self) -> String {
+        Status::from(*self).to_string()
+    }
+}
+
+impl From<PyTaskStatus> for Status {
+    fn from(status: PyTaskStatus) -> Self {
+        match status {
+            PyTaskStatus::Created => Self::Created,
+            PyTaskStatus::Progress => Self::Progress,
+            PyTaskStatus::Completed => Self::Completed,
+            PyTaskStatus::Archived => Self::Archived
+        }
+    }
+}
+
+impl From<Status> for PyTaskStatus {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::Created => Self::Created,
+            Status::Progress => Self::Progress,
+            Status::Completed => Self::Completed,
+            Status::Archived => Self::Archived
+        }
+    }
+}
+
+#[pyclass(name = "Category", module = "todolist", eq)]
+#[derive(Clone, PartialEq)]
+pub struct PyCategory(Category);
+
+#[pymethods]
+impl PyCategory {
+    #[new]
+    fn new (title: &str) -> PyResult<Self> {
+        Ok(Self(Category::new(title).map_err(value_error)?))
+    }
+
+    #[staticmethod]
+    fn default () -> Self {
+        Self(Category::default())
+    }
+
+    #[getter]
+    fn title (&self) -> &str {
+        self.0.get_title()
+    }
+
+    #[setter]
+    fn set_title (&mut self, title: &str) -> PyResult<()> {
+        self.0.set_title(title).map_err(value_error)?;
+        Ok(())
+    }
+
+    fn __str__ (&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__ (&self) -> String {
+        format!("Category({:?})", self.0.get_title())
+    }
+}
+
+#[pyclass(name = "Tag", module = "todolist", eq)]
+#[derive(Clone, PartialEq)]
+pub struct PyTag(Tag);
+
+#[pymethods]
+impl PyTag {
+    #[new]
+    fn new (title: &str) -> PyResult<Self> {
+        Ok(Self(Tag::new(title).map_err(value_error)?))
+    }
+
+    #[getter]
+    fn title (&self) -> &str {
+        self.0.get_title()
+    }
+
+    #[setter]
+    fn set_title (&mut self, title: &str) -> PyResult<()> {
+        self.0.set_title(title).map_err(value_error)?;
+        Ok(())
+    }
+
+    fn __hash__ (&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.0.get_title().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn __str__ (&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__ (&self) -> String {
+        format!("Tag({:?})", self.0.get_title())
+    }
+}
+
+#[pyclass(name = "TaskBuilder", module = "todolist")]
+pub struct PyTaskBuilder {
+    inner: Option<TaskBuilder>
+}
+
+impl PyTaskBuilder {
+    fn update (mut slf: PyRefMut<'_, Self>, f: impl FnOnce(TaskBuilder) -> TaskBuilder) -> PyResult<PyRefMut<'_, Self>> {
+        let builder = slf.inner.take().ok_or_else(|| PyRuntimeError::new_err("the task is already built"))?;
+        slf.inner = Some(f(builder));
+
+        Ok(slf)
+    }
+}
+
+#[pymethods]
+impl PyTaskBuilder {
+    #[new]
+    fn new (title: &str) -> PyResult<Self> {
+        Ok(Self { inner: Some(Task::new(title).map_err(value_error)?) })
+    }
+
+    fn description<'py> (slf: PyRefMut<'py, Self>, description: &str) -> PyResult<PyRefMut<'py, Self>> {
+        Self::update(slf, |builder| builder.description(description))
+    }
+
+    fn status (slf: PyRefMut<'_, Self>, status: PyTaskStatus) -> PyResult<PyRefMut<'_, Self>> {
+        Self::update(slf, |builder| builder.status(status.into()))
+    }
+
+    fn category<'py> (slf: PyRefMut<'py, Self>, category: &PyCategory) -> PyResult<PyRefMut<'py, Self>> {
+        Self::update(slf, |builder| builder.category(category.0.clone()))
+    }
+
+    fn tags (slf: PyRefMut<'_, Self>, tags: Vec<PyTag>) -> PyResult<PyRefMut<'_, Self>> {
+        let tags: Vec<Tag> = tags.into_iter().map(|tag| tag.0).collect();
+        Self::update(slf, |builder| builder.tags(&tags))
+    }
+
+    fn priority (slf: PyRefMut<'_, Self>, priority: PyPriority) -> PyResult<PyRefMut<'_, Self>> {
+        Self::update(slf, |builder| builder.priority(priority.into()))
+    }
+
+    fn deadline<'py> (slf: PyRefMut<'py, Self>, deadline: &Bound<'_, PyAny>) -> PyResult<PyRefMut<'py, Self>> {
+        let deadline = deadline_from_py(deadline)?;
+        Self::update(slf, |builder| builder.deadline(deadline))
+    }
+
+    fn build (&mut self) -> PyResult<PyTask> {
+        let builder = self.inner.take().ok_or_else(|| PyRuntimeError::new_err("the task is already built"))?;
+
+        Ok(PyTask { handle: Handle::Owned(Box::new(builder.build())) })
+    }
+}
+
+enum Handle {
+    Owned(Box<Task>),
+    Listed { list: Py<PyTodoList>, id: Uuid }
+}
+
+#[pyclass(name = "Task", module = "todolist")]
+pub struct PyTask {
+    handle: Handle
+}
+
+impl PyTask {
+    fn with<R> (&self, py: Python<'_>, f: impl FnOnce(&Task) -> R) -> PyResult<R> {
+        match &self.handle {
+            Handle::Owned(task) => Ok(f(task)),
+            Handle::Listed { list, id } => {
+                let list = list.borrow(py);
+                list.inner.get_task(id).map(f).ok_or_else(removed_error)
+            }
+        }
+    }
+
+    fn with_mut<R> (&mut self, py: Python<'_>, f: impl FnOnce(&mut Task) -> R) -> PyResult<R> {
+        match &mut self.handle {
+            Handle::Owned(task) => Ok(f(task)),
+            Handle::Listed { list, id } => {
+                let mut list = list.borrow_mut(py);
+                list.inner.get_task_mut(id).map(f).ok_or_else(removed_error)
+            }
+        }
+    }
+}
+
+#[pymethods]
+impl PyTask {
+    #[staticmethod]
+    #[allow(clippy::new_ret_no_self)]
+    fn new (title: &str) -> PyResult<PyTaskBuilder> {
+        PyTaskBuilder::new(title)
+    }
+
+    #[getter]
+    fn id (&self, py: Python<'_>) -> PyResult<String> {
+        self.with(py, |task| task.get_id().to_string())
+    }
+
+    #[getter]
+    fn title (&self, py: Python<'_>) -> PyResult<String> {
+        self.with(py, |task| task.get_title().to_owned())
+    }
+
+    #[setter]
+    fn set_title (&mut self, py: Python<'_>, title: &str) -> PyResult<()> {
+        self.with_mut(py, |task| task.set_title(title))?.map_err(value_error)
+    }
+
+    #[getter]
+    fn description (&self, py: Python<'_>) -> PyResult<String> {
+        self.with(py, |task| task.get_description().to_owned())
+    }
+
+    #[setter]
+    fn set_description (&mut self, py: Python<'_>, description: &str) -> PyResult<()> {
+        self.with_mut(py, |task| task.set_description(description))
+    }
+
+    #[getter]
+    fn status (&self, py: Python<'_>) -> PyResult<PyTaskStatus> {
+        self.with(py, |task| (*task.get_status()).into())
+    }
+
+    #[setter]
+    fn set_status (&mut self, py: Python<'_>, status: PyTaskStatus) -> PyResult<()> {
+        self.with_mut(py, |task| task.set_status(status.into()))
+    }
+
+    #[getter]
+    fn category (&self, py: Python<'_>) -> PyResult<PyCategory> {
+        self.with(py, |task| PyCategory(task.get_category().clone()))
+    }
+
+    #[setter]
+    fn set_category (&mut self, py: Python<'_>, category: PyCategory) -> PyResult<()> {
+        self.with_mut(py, |task| task.set_category(category.0))
+    }
+
+    #[getter]
+    fn tags (&self, py: Python<'_>) -> PyResult<Vec<PyTag>> {
+        self.with(py, |task| task.get_tags().iter().cloned().map(PyTag).collect())
+    }
+
+    #[setter]
+    fn set_tags (&mut self, py: Python<'_>, tags: Vec<PyTag>) -> PyResult<()> {
+        self.with_mut(py, |task| task.set_tags(tags.into_iter().map(|tag| tag.0).collect()))
+    }
+
+    fn add_tag (&mut self, py: Python<'_>, tag: PyTag) -> PyResult<()> {
+        self.with_mut(py, |task| task.add_tag(tag.0).map_err(|message| AlreadyExistsError::new_err(message.to_owned())))?
+    }
+
+    fn remove_tag (&mut self, py: Python<'_>, tag: PyTag) -> PyResult<()> {
+        self.with_mut(py, |task| task.remove_tag(tag.0))
+    }
+
+    #[getter]
+    fn priority (&self, py: Python<'_>) -> PyResult<PyPriority> {
+        self.with(py, |task| (*task.get_priority()).into())
+    }
+
+    #[setter]
+    fn set_priority (&mut self, py: Python<'_>, priority: PyPriority) -> PyResult<()> {
+        self.with_mut(py, |task| task.set_priority(priority.into()))
+    }
+
+    fn up_priority (&mut self, py: Python<'_>) -> PyResult<()> {
+        self.with_mut(py, |task| task.up_priority())
+    }
+
+    fn down_priority (&mut self, py: Python<'_>) -> PyResult<()> {
+        self.with_mut(py, |task| task.down_priority())
+    }
+
+    #[getter]
+    fn created_at (&self, py: Python<'_>) -> PyResult<DateTime<Utc>> {
+        self.with(py, |task| *task.get_created_at())
+    }
+
+    #[getter]
+    fn updated_at (&self, py: Python<'_>) -> PyResult<DateTime<Utc>> {
+        self.with(py, |task| *task.get_updated_at())
+    }
+
+    /// A `date` for an all-day deadline, an aware `datetime`, or a
+    /// `(start, end)` tuple of datetimes for a window.
+    #[getter]
+    fn deadline (&self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        let deadline = self.with(py, |task| task.get_deadline().copied())?;
+        deadline.map(|deadline| deadline_to_py(py, deadline)).transpose()
+    }
+
+    /// Assigning `None` clears the deadline.
+    #[setter]
+    fn set_deadline (&mut self, py: Python<'_>, deadline: &Bound<'_, PyAny>) -> PyResult<()> {
+        if deadline.is_none() {
+            return self.with_mut(py, |task| task.clear_deadline());
+        }
+
+        let deadline = deadline_from_py(deadline)?;
+        self.with_mut(py, |task| task.set_deadline(deadline))
+    }
+
+    /// Moves the deadline by a `timedelta`, whole days for an all-day deadline.
+    fn shift_deadline (&mut self, py: Python<'_>, by: chrono::Duration) -> PyResult<()> {
+        self.with_mut(py, |task| task.shift_deadline(by))?.map_err(value_error)
+    }
+
+    fn has_deadline (&self, py: Python<'_>) -> PyResult<bool> {
+        self.with(py, |task| task.has_deadline())
+    }
+
+    fn is_for_today (&self, py: Python<'_>) -> PyResult<bool> {
+        self.with(py, |task| task.is_for_today())
+    }
+
+    fn __str__ (&self, py: Python<'_>) -> PyResult<String> {
+        self.with(py, |task| task.to_string())
+    }
+
+    fn __repr__ (&self, py: Python<'_>) -> PyResult<String> {
+        self.with(py, |task| format!("Task({:?}, id={})", task.get_title(), task.get_id()))
+    }
+}
+
+#[pyclass(name = "TaskIterator", module = "todolist")]
+pub struct PyTaskIterator {
+    list: Py<PyTodoList>,
+    ids: std::vec::IntoIter<Uuid>
+}
+
+#[pymethods]
+impl PyTaskIterator {
+    fn __iter__ (slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__ (&mut self, py: Python<'_>) -> Option<PyTask> {
+        self.ids.next().map(|id| PyTask { handle: Handle::Listed { list: self.list.clone_ref(py), id } })
+    }
+}
+
+#[pyclass(name = "TodoList", module = "todolist")]
+pub struct PyTodoList {
+    inner: TodoList
+}
+
+impl PyTodoList {
+    fn iter_of (slf: &Bound<'_, Self>, ids: Vec<Uuid>) -> PyTaskIterator {
+        PyTaskIterator { list: slf.clone().unbind(), ids: ids.into_iter() }
+    }
+}
+
+#[pymethods]
+impl PyTodoList {
+    #[new]
+    fn new () -> Self {
+        Self { inner: TodoList::default() }
+    }
+
+    fn __len__ (&self) -> usize {
+        self.inner.get_tasks().len()
+    }
+
+    fn __iter__ (slf: &Bound<'_, Self>) -> PyTaskIterator {
+        let ids = slf.borrow().inner.get_tasks().iter().map(|task| *task.get_id()).collect();
+        Self::iter_of(slf, ids)
+    }
+
+    #[getter]
+    fn tasks (slf: &Bound<'_, Self>) -> Vec<PyTask> {
+        Self::__iter__(slf).collect_tasks(slf.py())
+    }
+
+    /// Moves the task into the list, the passed object becomes a view of it.
+    fn add_task (slf: &Bound<'_, Self>, task: &Bound<'_, PyTask>) -> PyResult<()> {
+        let mut task = task.borrow_mut();
+
+        let Handle::Owned(owned) = &task.handle else {
+            return Err(AlreadyExistsError::new_err("The task is already in a list"));
+        };
+        let id = *owned.get_id();
+
+        // the object keeps its own task until the list took a copy
+        if let Err(message) = slf.borrow_mut().inner.add_task((**owned).clone()) {
+            return Err(AlreadyExistsError::new_err(message.to_owned()));
+        }
+
+        task.handle = Handle::Listed { list: slf.clone().unbind(), id };
+        Ok(())
+    }
+
+    /// Removes the task from the list, the passed object keeps a detached copy.
+    fn remove_task (slf: &Bound<'_, Self>, task: &Bound<'_, PyTask>) -> PyResult<()> {
+        let mut task = task.borrow_mut();
+        let id = task.with(slf.py(), |task| *task.get_id())?;
+
+        let removed = slf.borrow_mut().inner.remove_task_by_id(&id).map_err(|_| removed_error())?;
+        task.handle = Handle::Owned(Box::new(removed));
+
+        Ok(())
+    }
+
+    #[getter]
+    fn categories (&self) -> Vec<PyCategory> {
+        self.inner.get_categories().iter().cloned().map(PyCategory).collect()
+    }
+
+    fn new_category (&mut self, title: &str) -> PyResult<()> {
+        Category::new(title).map_err(value_error)?;
+        self.inner.new_category(title).map_err(|message| AlreadyExistsError::new_err(message.to_owned()))
+    }
+
+    #[getter]
+    fn tags (&self) -> Vec<PyTag> {
+        self.inner.get_tags().iter().cloned().map(PyTag).collect()
+    }
+
+    fn new_tag (&mut self, title: &str) -> PyResult<()> {
+        Tag::new(title).map_err(value_error)?;
+        self.inner.new_tag(title).map_err(|message| AlreadyExistsError::new_err(message.to_owned()))
+    }
+
+    /// Iterates over the tasks matching a `Category`, `Tag`, `Priority` or `TaskStatus`.
+    fn get_tasks_by (slf: &Bound<'_, Self>, by: &Bound<'_, PyAny>) -> PyResult<PyTaskIterator> {
+        let ids = {
+            let list = slf.borrow();
+            let ids = |tasks: Vec<&Task>| -> Vec<Uuid> { tasks.into_iter().map(|task| *task.get_id()).collect() };
+
+            if let Ok(category) = by.downcast::<PyCategory>() {
+                ids(list.inner.get_tasks_by(By::Category(&category.borrow().0)))
+            } else if let Ok(tag) = by.downcast::<PyTag>() {
+                ids(list.inner.get_tasks_by(By::Tag(&tag.borrow().0)))
+            } else if let Ok(priority) = by.extract::<PyPriority>() {
+                ids(list.inner.get_tasks_by(By::Priority(priority.into())))
+            } else if let Ok(status) = by.extract::<PyTaskStatus>() {
+                ids(list.inner.get_tasks_by(By::Status(status.into())))
+            } else {
+                return Err(PyTypeError::new_err("expected a Category, Tag, Priority or TaskStatus"));
+            }
+        };
+
+        Ok(Self::iter_of(slf, ids))
+    }
+}
+
+impl PyTaskIterator {
+    fn collect_tasks (mut self, py: Python<'_>) -> Vec<PyTask> {
+        let mut tasks = vec![];
+
+        while let Some(task) = self.__next__(py) {
+            tasks.push(task);
+        }
+
+        tasks
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "todolist")]
+fn python_module (m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyTodoList>()?;
+    m.add_class::<PyTask>()?;
+    m.add_class::<PyTaskBuilder>()?;
+    m.add_class::<PyTaskIterator>()?;
+    m.add_class::<PyCategory>()?;
+    m.add_class::<PyTag>()?;
+    m.add_class::<PyPriority>()?;
+    m.add_class::<PyTaskStatus>()?;
+    m.add("AlreadyExistsError", m.py().get_type::<AlreadyExistsError>())?;
+
+    Ok(())
+}
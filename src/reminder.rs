@@ -0,0 +1,508 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use uuid::Uuid;
+
+use crate::clock::{Clock, ClockHandle};
+use crate::prelude::*;
+use crate::TodoList;
+
+/// A reminder that came due.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReminderEvent {
+    task_id: Uuid,
+    title: String,
+    reminder: Reminder,
+    fire_at: DateTime<Utc>,
+    missed: bool,
+    snoozed: bool
+}
+
+impl ReminderEvent {
+    pub fn get_task_id (&self) -> &Uuid {
+        &self.task_id
+    }
+
+    pub fn get_title (&self) -> &str {
+        &self.title
+    }
+
+    pub fn get_reminder (&self) -> &Reminder {
+        &self.reminder
+    }
+
+    /// When the reminder was due, the end of the snooze for a snoozed one.
+    pub fn get_fire_at (&self) -> &DateTime<Utc> {
+        &self.fire_at
+    }
+
+    /// Whether the reminder was due longer than the grace period ago, after
+    /// the scheduler did not run for a while.
+    pub fn is_missed (&self) -> bool {
+        self.missed
+    }
+}
+
+impl std::fmt::Display for ReminderEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} | {} ({})", self.fire_at.format("%Y-%m-%d %H:%M"), self.title, self.reminder)?;
+
+        if self.missed {
+            write!(f, " [missed]")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Finds the reminders of a `TodoList` that came due since the last poll.
+///
+/// Only open tasks are reminded about. Each reminder is yielded once, unless
+/// it is snoozed. The scheduler keeps no state on disk, store `get_last_poll`
+/// and pass it to `resume_after` on restart so reminders are not repeated.
+pub struct ReminderScheduler {
+    clock: ClockHandle,
+    last_poll: Option<DateTime<Utc>>,
+    grace: Duration,
+    skip_missed: bool,
+    dismissed: HashSet<(Uuid, Reminder)>,
+    snoozed: HashMap<(Uuid, Reminder), DateTime<Utc>>,
+    // reminders a failed dispatch delivered before the failure, by when they
+    // were due, which the next poll leaves out
+    delivered: HashSet<(Uuid, Reminder, DateTime<Utc>)>
+}
+
+impl ReminderScheduler {
+    /// The first poll yields every reminder already due.
+    pub fn new (clock: Arc<dyn Clock>) -> Self {
+        Self {
+            clock: ClockHandle(clock),
+            last_poll: None,
+            grace: Duration::minutes(1),
+            skip_missed: false,
+            dismissed: HashSet::new(),
+            snoozed: HashMap::new(),
+            delivered: HashSet::new()
+        }
+    }
+
+    /// Reminders due up to `last_poll` count as already delivered.
+    pub fn resume_after (mut self, last_poll: DateTime<Utc>) -> Self {
+        self.last_poll = Some(last_poll);
+        self
+    }
+
+    /// How late a reminder may be found before it counts as missed, a minute by default.
+    pub fn grace (mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    /// Drops missed reminders instead of yielding them.
+    pub fn skip_missed (mut self, skip: bool) -> Self {
+        self.skip_missed = skip;
+        self
+    }
+
+    pub fn get_last_poll (&self) -> Option<&DateTime<Utc>> {
+        self.last_poll.as_ref()
+    }
+
+    /// Reminders due since the last poll, oldest first. All-day deadlines are
+    /// evaluated in `tz`.
+    pub fn poll<Tz: TimeZone> (&mut self, todolist: &TodoList, tz: &Tz) -> Vec<ReminderEvent> {
+        let now = self.clock.now();
+        let mut events = vec![];
+
+        let open = todolist.get_tasks().iter().filter(|task| matches!(task.get_status(), Status::Created | Status::Progress));
+
+        for task in open {
+            for reminder in task.get_reminders() {
+                let key = (*task.get_id(), *reminder);
+
+                if self.dismissed.contains(&key) {
+                    continue;
+                }
+
+                let (fire_at, snoozed) = match self.snoozed.get(&key) {
+                    Some(until) if *until <= now => {
+                        let until = *until;
+                        self.snoozed.remove(&key);
+                        (until, true)
+                    },
+                    Some(_) => continue,
+                    None => match reminder.fires_at(task.get_deadline(), tz) {
+                        Some(at) if at <= now && self.last_poll.is_none_or(|last| at > last) && !self.delivered.contains(&(key.0, key.1, at)) => (at, false),
+                        _ => continue
+                    }
+                };
+
+                // a grace longer than the range of dates misses nothing
+                let missed = now.checked_sub_signed(self.grace).is_some_and(|limit| fire_at < limit);
+
+                if missed && self.skip_missed {
+                    continue;
+                }
+
+                events.push(ReminderEvent {
+                    task_id: key.0,
+                    title: task.get_title().to_owned(),
+                    reminder: *reminder,
+                    fire_at,
+                    missed,
+                    snoozed
+                });
+            }
+        }
+
+        events.sort_by(|a, b| a.fire_at.cmp(&b.fire_at).then_with(|| a.task_id.cmp(&b.task_id)));
+        self.last_poll = Some(now);
+        self.delivered.clear();
+
+        events
+    }
+
+    /// Polls and hands every event to `sink`, returns how many were delivered.
+    /// Stops at the first failed delivery, the next poll yields the failed
+    /// event and the ones after it again, but not the delivered ones.
+    pub fn dispatch<Tz: TimeZone> (&mut self, todolist: &TodoList, tz: &Tz, sink: &mut dyn ReminderSink) -> io::Result<usize> {
+        let events = self.poll(todolist, tz);
+
+        for (index, event) in events.iter().enumerate() {
+            if let Err(err) = sink.deliver(event) {
+                self.undeliver(&events[..index], &events[index..]);
+                return Err(err);
+            }
+        }
+
+        Ok(events.len())
+    }
+
+    /// Moves the last poll back before `failed` and snoozes the snoozed
+    /// ones again, so the next poll yields them. The `delivered` ones due at
+    /// the same time are left out of it.
+    fn undeliver (&mut self, delivered: &[ReminderEvent], failed: &[ReminderEvent]) {
+        self.delivered = delivered.iter()
+            .filter(|event| !event.snoozed)
+            .map(|event| (event.task_id, event.reminder, event.fire_at))
+            .collect();
+
+        for event in failed {
+            if event.snoozed {
+                self.snoozed.insert((event.task_id, event.reminder), event.fire_at);
+            } else {
+                let before = event.fire_at.checked_sub_signed(Duration::nanoseconds(1)).unwrap_or(event.fire_at);
+                self.last_poll = self.last_poll.map(|last| last.min(before));
+            }
+        }
+    }
+
+    /// Never yields the reminder again, whether it has fired or not.
+    pub fn dismiss (&mut self, task_id: &Uuid, reminder: &Reminder) {
+        self.snoozed.remove(&(*task_id, *reminder));
+        self.dismissed.insert((*task_id, *reminder));
+    }
+
+    /// Yields the reminder again after `duration` from now. Fails when that
+    /// is out of the range of dates.
+    pub fn snooze (&mut self, event: &ReminderEvent, duration: Duration) -> Result<(), &'static str> {
+        let key = (event.task_id, event.reminder);
+        let until = self.clock.now().checked_add_signed(duration).ok_or("The snooze ends out of range")?;
+
+        self.dismissed.remove(&key);
+        self.snoozed.insert(key, until);
+
+        Ok(())
+    }
+}
+
+/// Where reminder events are delivered.
+pub trait ReminderSink {
+    fn deliver (&mut self, event: &ReminderEvent) -> io::Result<()>;
+}
+
+/// Writes one line per event.
+pub struct WriterSink<W: Write> {
+    writer: W
+}
+
+impl<W: Write> WriterSink<W> {
+    pub fn new (writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn into_inner (self) -> W {
+        self.writer
+    }
+}
+
+impl WriterSink<io::Stdout> {
+    pub fn stdout () -> Self {
+        Self::new(io::stdout())
+    }
+}
+
+impl<W: Write> ReminderSink for WriterSink<W> {
+    fn deliver (&mut self, event: &ReminderEvent) -> io::Result<()> {
+        writeln!(self.writer, "{}", event)?;
+        self.writer.flush()
+    }
+}
+
+/// Appends one line per event to a file, creating it if needed.
+pub struct FileSink {
+    path: PathBuf
+}
+
+impl FileSink {
+    pub fn new (path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl ReminderSink for FileSink {
+    fn deliver (&mut self, event: &ReminderEvent) -> io::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", event)
+    }
+}
+
+/// Runs a command per event with the event line as the last argument and
+/// the details in the environment: `TODOLIST_TASK_ID`, `TODOLIST_TASK_TITLE`,
+/// `TODOLIST_REMINDER_AT` (RFC 3339) and `TODOLIST_REMINDER_MISSED` (`0` or `1`).
+/// A command exiting with an error fails the delivery.
+pub struct CommandSink {
+    program: String,
+    args: Vec<String>
+}
+
+impl CommandSink {
+    pub fn new (program: &str) -> Self {
+        Self { program: program.to_owned(), args: vec![] }
+    }
+
+    pub fn arg (mut self, arg: &str) -> Self {
+        self.args.push(arg.to_owned());
+        self
+    }
+}
+
+impl ReminderSink for CommandSink {
+    fn deliver (&mut self, event: &ReminderEvent) -> io::Result<()> {
+        let status = Command::new(&self.program)
+            .args(&self.args)
+            .arg(event.to_string())
+            .env("TODOLIST_TASK_ID", event.task_id.to_string())
+            .env("TODOLIST_TASK_TITLE", &event.title)
+            .env("TODOLIST_REMINDER_AT", event.fire_at.to_rfc3339())
+            .env("TODOLIST_REMINDER_MISSED", if event.missed { "1" } else { "0" })
+            .status()?;
+
+        if !status.success() {
+            return Err(io::Error::other(format!("The reminder command failed: {}", status)));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Monday 10:00
+    fn now () -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 6, 10, 0, 0).unwrap()
+    }
+
+    fn setup () -> (Arc<FixedClock>, TodoList) {
+        let clock = Arc::new(FixedClock::new(now()));
+        let mut todolist = TodoList::default().with_clock(clock.clone()).with_id_generator(Arc::new(SequentialIds::new()));
+
+        let call = todolist.new_task("Call").unwrap()
+            .deadline(now() + Duration::hours(2))
+            .reminders(&[Reminder::parse("1h before").unwrap(), Reminder::parse("30 min").unwrap()])
+            .build();
+        let write = todolist.new_task("Write").unwrap()
+            .reminders(&[Reminder::at(now() + Duration::minutes(90)), Reminder::parse("1h").unwrap()])
+            .build();
+        let done = todolist.new_task("Done").unwrap()
+            .status(Status::Completed)
+            .reminders(&[Reminder::at(now() + Duration::minutes(10))])
+            .build();
+
+        for task in [call, write, done] {
+            todolist.add_task(task).unwrap();
+        }
+
+        (clock, todolist)
+    }
+
+    fn titles (events: &[ReminderEvent]) -> Vec<String> {
+        events.iter().map(|event| format!("{} {}", event.get_title(), event.get_fire_at().format("%H:%M"))).collect()
+    }
+
+    #[test]
+    fn yields_due_reminders_once_in_order () {
+        let (clock, todolist) = setup();
+        let mut scheduler = ReminderScheduler::new(clock.clone());
+
+        assert!(scheduler.poll(&todolist, &Utc).is_empty());
+
+        clock.advance(Duration::minutes(60));
+        assert_eq!(titles(&scheduler.poll(&todolist, &Utc)), vec!["Call 11:00"]);
+        assert!(scheduler.poll(&todolist, &Utc).is_empty());
+
+        clock.advance(Duration::minutes(40));
+        assert_eq!(titles(&scheduler.poll(&todolist, &Utc)), vec!["Call 11:30", "Write 11:30"]);
+    }
+
+    #[test]
+    fn missed_after_downtime () {
+        let (clock, todolist) = setup();
+
+        clock.set(now() + Duration::hours(3));
+        let events = ReminderScheduler::new(clock.clone()).resume_after(now() + Duration::minutes(70)).poll(&todolist, &Utc);
+
+        assert_eq!(titles(&events), vec!["Call 11:30", "Write 11:30"]);
+        assert!(events.iter().all(|event| event.is_missed()));
+
+        let mut skipping = ReminderScheduler::new(clock.clone()).skip_missed(true);
+        assert!(skipping.poll(&todolist, &Utc).is_empty());
+        assert_eq!(skipping.get_last_poll(), Some(&(now() + Duration::hours(3))));
+    }
+
+    #[test]
+    fn dismiss_and_snooze () {
+        let (clock, todolist) = setup();
+        let mut scheduler = ReminderScheduler::new(clock.clone());
+        let write = todolist.get_tasks()[1].get_id();
+
+        scheduler.dismiss(write, &Reminder::at(now() + Duration::minutes(90)));
+
+        clock.advance(Duration::minutes(60));
+        let events = scheduler.poll(&todolist, &Utc);
+        assert_eq!(titles(&events), vec!["Call 11:00"]);
+
+        scheduler.snooze(&events[0], Duration::minutes(45)).unwrap();
+
+        clock.advance(Duration::minutes(40));
+        assert_eq!(titles(&scheduler.poll(&todolist, &Utc)), vec!["Call 11:30"]);
+
+        clock.advance(Duration::minutes(5));
+        let events = scheduler.poll(&todolist, &Utc);
+        assert_eq!(titles(&events), vec!["Call 11:45"]);
+        assert!(!events[0].is_missed());
+    }
+
+    #[test]
+    fn writer_and_file_sinks () {
+        let (clock, todolist) = setup();
+        clock.advance(Duration::hours(2));
+
+        let mut sink = WriterSink::new(Vec::new());
+        assert_eq!(ReminderScheduler::new(clock.clone()).dispatch(&todolist, &Utc, &mut sink).unwrap(), 3);
+        assert_eq!(String::from_utf8(sink.into_inner()).unwrap(), "\
+            2024-05-06 11:00 | Call (1h before) [missed]\n\
+            2024-05-06 11:30 | Call (30m before) [missed]\n\
+            2024-05-06 11:30 | Write (2024-05-06 11:30) [missed]\n");
+
+        let path = std::env::temp_dir().join(format!("todolist-reminders-{}.log", Uuid::new_v4()));
+        let mut sink = FileSink::new(&path);
+        ReminderScheduler::new(clock.clone()).skip_missed(true).dispatch(&todolist, &Utc, &mut sink).unwrap();
+        assert!(!path.exists());
+
+        clock.advance(Duration::hours(-1));
+        ReminderScheduler::new(clock.clone()).dispatch(&todolist, &Utc, &mut sink).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "2024-05-06 11:00 | Call (1h before)\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    struct FailingSink {
+        fail_at: usize,
+        lines: Vec<String>
+    }
+
+    impl ReminderSink for FailingSink {
+        fn deliver (&mut self, event: &ReminderEvent) -> io::Result<()> {
+            if self.lines.len() == self.fail_at {
+                return Err(io::Error::other("The sink is full"));
+            }
+
+            self.lines.push(titles(std::slice::from_ref(event)).remove(0));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn failed_delivery_is_retried () {
+        let (clock, todolist) = setup();
+        let mut scheduler = ReminderScheduler::new(clock.clone());
+
+        clock.advance(Duration::minutes(60));
+        let events = scheduler.poll(&todolist, &Utc);
+        scheduler.snooze(&events[0], Duration::minutes(20)).unwrap();
+
+        clock.advance(Duration::minutes(40));
+        let mut sink = FailingSink { fail_at: 1, lines: vec![] };
+        assert!(scheduler.dispatch(&todolist, &Utc, &mut sink).is_err());
+        assert!(scheduler.dispatch(&todolist, &Utc, &mut sink).is_err());
+        assert_eq!(sink.lines, vec!["Call 11:20"]);
+
+        sink.fail_at = 3;
+        assert_eq!(scheduler.dispatch(&todolist, &Utc, &mut sink).unwrap(), 2);
+        assert_eq!(sink.lines, vec!["Call 11:20", "Call 11:30", "Write 11:30"]);
+        assert!(scheduler.poll(&todolist, &Utc).is_empty());
+    }
+
+    #[test]
+    fn delivered_events_are_not_retried () {
+        let (clock, todolist) = setup();
+        let mut scheduler = ReminderScheduler::new(clock.clone());
+        clock.advance(Duration::minutes(100));
+
+        // "Call 11:30" is delivered, "Write 11:30" at the same instant fails
+        let mut sink = FailingSink { fail_at: 2, lines: vec![] };
+        assert!(scheduler.dispatch(&todolist, &Utc, &mut sink).is_err());
+
+        sink.fail_at = 3;
+        assert_eq!(scheduler.dispatch(&todolist, &Utc, &mut sink).unwrap(), 1);
+        assert_eq!(sink.lines, vec!["Call 11:00", "Call 11:30", "Write 11:30"]);
+    }
+
+    #[test]
+    fn large_durations () {
+        let (clock, todolist) = setup();
+        clock.advance(Duration::minutes(60));
+
+        let mut scheduler = ReminderScheduler::new(clock.clone()).grace(Duration::MAX);
+        let events = scheduler.poll(&todolist, &Utc);
+        assert!(!events[0].is_missed());
+
+        assert!(scheduler.snooze(&events[0], Duration::MAX).is_err());
+        assert!(scheduler.snooze(&events[0], Duration::minutes(5)).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn command_sink () {
+        let (clock, todolist) = setup();
+        clock.advance(Duration::hours(1));
+
+        let path = std::env::temp_dir().join(format!("todolist-command-{}.log", Uuid::new_v4()));
+        let script = format!(r#"echo "$TODOLIST_TASK_TITLE $TODOLIST_REMINDER_MISSED $1" >> {}"#, path.display());
+        let mut sink = CommandSink::new("sh").arg("-c").arg(&script).arg("sh");
+
+        ReminderScheduler::new(clock.clone()).dispatch(&todolist, &Utc, &mut sink).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Call 0 2024-05-06 11:00 | Call (1h before)\n");
+        std::fs::remove_file(&path).unwrap();
+
+        let mut failing = CommandSink::new("sh").arg("-c").arg("exit 3");
+        assert!(ReminderScheduler::new(clock.clone()).dispatch(&todolist, &Utc, &mut failing).is_err());
+    }
+}
@@ -0,0 +1,868 @@
+//! Copies of a list edited on several devices without a server, kept as
+//! conflict-free replicated data types so they converge:
+//!
+//! * Every field of a task but its tags is a last-writer-wins register.
+//! * The tasks of the list, the tags of each task and the categories and
+//!   tags of the list are observed-remove sets, an element added again
+//!   while another replica removes it stays.
+//!
+//! Edits are stamped with a Lamport counter and the id of the replica, the
+//! highest stamp wins a register. Two replicas that received the same edits,
+//! in any order and any number of times, build the same list. A task removed
+//! on one replica is removed even if another changed it meanwhile, and the
+//! tasks are listed in order of creation.
+//!
+//! Replicas sync over any byte channel: one sends its `get_version`, the
+//! other answers with `delta`, holding only the edits the first has not
+//! seen, which the first passes to `apply`. A delta with a counter past
+//! half the range of `u64` is refused, so it can't use up the counters of a
+//! replica. Both are laid out as:
+//!
+//! | Bytes | Content                                              |
+//! |-------|------------------------------------------------------|
+//! | 4     | `TDLR`                                               |
+//! | 1     | format version, 1                                    |
+//! | 1     | 0 for a version, 1 for a delta                       |
+//! | rest  | varint counts, each followed by that many entries    |
+//!
+//! A version holds the highest counter seen per replica, 16 byte ids. A
+//! delta holds the table of replica ids, the table of task ids, the table of
+//! field names, its version, the registers, the added elements and the
+//! removed elements. Entries refer to the tables by index, the values of
+//! registers and elements are JSON.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+use crate::merge::{from_fields, to_fields, TaskError};
+use crate::prelude::*;
+use crate::TodoList;
+
+const MAGIC: &[u8; 4] = b"TDLR";
+const FORMAT: u8 = 1;
+const VERSION: u8 = 0;
+const DELTA: u8 = 1;
+
+/// The highest Lamport counter a replica takes in.
+const MAX_COUNTER: u64 = u64::MAX / 2;
+
+/// An edit: the Lamport counter of the replica that made it and its id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Stamp {
+    counter: u64,
+    replica: Uuid
+}
+
+/// An element of one of the observed-remove sets.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Key {
+    Task(Uuid),
+    TaskTag(Uuid, String),
+    Category(String),
+    Tag(String)
+}
+
+/// The highest counter seen per replica.
+type Version = BTreeMap<Uuid, u64>;
+
+/// One copy of a list, see the module documentation.
+pub struct Replica {
+    id: Uuid,
+    counter: u64,
+    version: Version,
+    // the value of a task field and the edit that wrote it
+    registers: BTreeMap<(Uuid, String), (Stamp, Value)>,
+    // elements by the edit that added them, with the tag or category they stand for
+    added: BTreeMap<Stamp, (Key, Value)>,
+    // the edits adding elements that were removed, with the edit removing them
+    removed: BTreeMap<Stamp, Stamp>
+}
+
+impl Default for Replica {
+    fn default () -> Self {
+        Self::new()
+    }
+}
+
+impl Replica {
+    /// An empty replica with a new id.
+    pub fn new () -> Self {
+        Self::with_id(Uuid::new_v4())
+    }
+
+    /// An empty replica with `id`, which no other replica may use.
+    pub fn with_id (id: Uuid) -> Self {
+        Self {
+            id,
+            counter: 0,
+            version: Version::new(),
+            registers: BTreeMap::new(),
+            added: BTreeMap::new(),
+            removed: BTreeMap::new()
+        }
+    }
+
+    pub fn get_id (&self) -> &Uuid {
+        &self.id
+    }
+
+    /// The list built from every edit this replica has seen. Fails when the
+    /// fields of a task don't make a valid task.
+    pub fn get_todolist (&self) -> Result<TodoList, TaskError> {
+        let mut fields: BTreeMap<Uuid, Map<String, Value>> = BTreeMap::new();
+        let mut task_tags: BTreeMap<Uuid, Vec<Value>> = BTreeMap::new();
+        let mut categories = vec![];
+        let mut tags = vec![];
+
+        for (key, value) in self.get_elements() {
+            match key {
+                Key::Task(id) => {
+                    fields.insert(*id, Map::new());
+                }
+                Key::TaskTag(id, _) => task_tags.entry(*id).or_default().push(value.clone()),
+                Key::Category(_) => categories.extend(serde_json::from_value::<Category>(value.clone())),
+                Key::Tag(_) => tags.extend(serde_json::from_value::<Tag>(value.clone()))
+            }
+        }
+
+        for ((id, field), (_, value)) in self.registers.iter() {
+            if let Some(fields) = fields.get_mut(id) {
+                fields.insert(field.clone(), value.clone());
+            }
+        }
+
+        let mut tasks = vec![];
+        for (id, mut fields) in fields {
+            fields.insert("tags".to_owned(), Value::Array(task_tags.remove(&id).unwrap_or_default()));
+            tasks.push(from_fields(fields).map_err(|message| TaskError::new(id, message))?);
+        }
+        tasks.sort_by(|a: &Task, b| a.get_created_at().cmp(b.get_created_at()).then(a.get_id().cmp(b.get_id())));
+
+        Ok(TodoList { tasks, categories, tags, ..Default::default() })
+    }
+
+    /// Records the changes from `base`, the list `get_todolist` returned, to
+    /// `todolist` as edits of this replica. Edits applied since `base` was
+    /// taken are kept unless `todolist` changed the same thing. Fails when
+    /// the counter of this replica is used up.
+    pub fn commit (&mut self, base: &TodoList, todolist: &TodoList) -> Result<(), &'static str> {
+        let before = elements(base);
+        let wanted = elements(todolist);
+
+        for task in todolist.get_tasks() {
+            let old = base.get_task(task.get_id()).map(to_fields).unwrap_or_default();
+
+            for (field, value) in to_fields(task) {
+                if field != "tags" && old.get(&field) != Some(&value) {
+                    let stamp = self.stamp()?;
+                    self.registers.insert((*task.get_id(), field), (stamp, value));
+                }
+            }
+        }
+
+        let alive: Vec<(Stamp, Key)> = self.alive().map(|(dot, (key, _))| (*dot, key.clone())).collect();
+        let present: BTreeSet<&Key> = alive.iter().map(|(_, key)| key).collect();
+
+        for (key, value) in wanted.iter() {
+            if !before.contains_key(key) && !present.contains(key) {
+                let stamp = self.stamp()?;
+                self.added.insert(stamp, (key.clone(), value.clone()));
+            }
+        }
+
+        for (dot, key) in alive.iter() {
+            if before.contains_key(key) && !wanted.contains_key(key) {
+                let stamp = self.stamp()?;
+                self.removed.insert(*dot, stamp);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The highest counter this replica has seen from every replica, to send
+    /// to another one for a `delta`.
+    pub fn get_version (&self) -> Vec<u8> {
+        let mut writer = Writer::new(VERSION);
+        writer.varint(self.version.len() as u64);
+
+        for (replica, counter) in self.version.iter() {
+            writer.uuid(replica);
+            writer.varint(*counter);
+        }
+
+        writer.bytes
+    }
+
+    /// The edits of this replica missing from the replica whose
+    /// `get_version` is `version`.
+    pub fn delta (&self, version: &[u8]) -> Result<Vec<u8>, &'static str> {
+        let mut reader = Reader::new(version, VERSION)?;
+        let mut seen = Version::new();
+
+        for _ in 0..reader.varint()? {
+            let replica = reader.uuid()?;
+            seen.insert(replica, reader.varint()?);
+        }
+        reader.end()?;
+
+        let unseen = |stamp: &Stamp| seen.get(&stamp.replica).is_none_or(|counter| *counter < stamp.counter);
+        let mut replicas = Table::default();
+        let mut tasks = Table::default();
+        let mut fields = Table::default();
+        let mut body = Writer::default();
+
+        body.varint(self.version.len() as u64);
+        for (replica, counter) in self.version.iter() {
+            body.varint(replicas.index(*replica));
+            body.varint(*counter);
+        }
+
+        let registers: Vec<_> = self.registers.iter().filter(|(_, (stamp, _))| unseen(stamp)).collect();
+        body.varint(registers.len() as u64);
+        for ((task, field), (stamp, value)) in registers {
+            body.varint(tasks.index(*task));
+            body.varint(fields.index(field.clone()));
+            body.stamp(stamp, &mut replicas);
+            body.data(&serde_json::to_vec(value).unwrap_or_default());
+        }
+
+        let added: Vec<_> = self.added.iter().filter(|(dot, _)| unseen(dot)).collect();
+        body.varint(added.len() as u64);
+        for (dot, (key, value)) in added {
+            body.stamp(dot, &mut replicas);
+
+            match key {
+                Key::Task(task) => {
+                    body.bytes.push(0);
+                    body.varint(tasks.index(*task));
+                }
+                Key::TaskTag(task, title) => {
+                    body.bytes.push(1);
+                    body.varint(tasks.index(*task));
+                    body.data(title.as_bytes());
+                }
+                Key::Category(title) => {
+                    body.bytes.push(2);
+                    body.data(title.as_bytes());
+                }
+                Key::Tag(title) => {
+                    body.bytes.push(3);
+                    body.data(title.as_bytes());
+                }
+            }
+
+            // a task has no value
+            if !matches!(key, Key::Task(_)) {
+                body.data(&serde_json::to_vec(value).unwrap_or_default());
+            }
+        }
+
+        let removed: Vec<_> = self.removed.iter().filter(|(_, removal)| unseen(removal)).collect();
+        body.varint(removed.len() as u64);
+        for (dot, removal) in removed {
+            body.stamp(dot, &mut replicas);
+            body.stamp(removal, &mut replicas);
+        }
+
+        let mut writer = Writer::new(DELTA);
+        for table in [&replicas.items, &tasks.items] {
+            writer.varint(table.len() as u64);
+            table.iter().for_each(|id| writer.uuid(id));
+        }
+        writer.varint(fields.items.len() as u64);
+        fields.items.iter().for_each(|field| writer.data(field.as_bytes()));
+        writer.bytes.extend(body.bytes);
+
+        Ok(writer.bytes)
+    }
+
+    /// Takes in a `delta` of another replica. Applying a delta again or out
+    /// of order is harmless.
+    pub fn apply (&mut self, delta: &[u8]) -> Result<(), &'static str> {
+        let mut reader = Reader::new(delta, DELTA)?;
+
+        let replicas = reader.list(Reader::uuid)?;
+        let tasks = reader.list(Reader::uuid)?;
+        let fields = reader.list(Reader::string)?;
+        let lookup = |items: &[Uuid], index: u64| items.get(index as usize).copied().ok_or("The delta refers to a missing id");
+
+        let mut version = Version::new();
+        for _ in 0..reader.varint()? {
+            let replica = lookup(&replicas, reader.varint()?)?;
+            version.insert(replica, reader.counter()?);
+        }
+
+        let mut registers = vec![];
+        for _ in 0..reader.varint()? {
+            let task = lookup(&tasks, reader.varint()?)?;
+            let field = fields.get(reader.varint()? as usize).ok_or("The delta refers to a missing field")?.clone();
+            let stamp = reader.stamp(&replicas)?;
+            registers.push(((task, field), (stamp, reader.json()?)));
+        }
+
+        let mut added = vec![];
+        for _ in 0..reader.varint()? {
+            let dot = reader.stamp(&replicas)?;
+
+            let key = match reader.byte()? {
+                0 => Key::Task(lookup(&tasks, reader.varint()?)?),
+                1 => Key::TaskTag(lookup(&tasks, reader.varint()?)?, reader.string()?),
+                2 => Key::Category(reader.string()?),
+                3 => Key::Tag(reader.string()?),
+                _ => return Err("The delta has an unknown kind of element")
+            };
+            let value = match key {
+                Key::Task(_) => Value::Null,
+                _ => reader.json()?
+            };
+
+            added.push((dot, (key, value)));
+        }
+
+        let mut removed = vec![];
+        for _ in 0..reader.varint()? {
+            removed.push((reader.stamp(&replicas)?, reader.stamp(&replicas)?));
+        }
+        reader.end()?;
+
+        // nothing is changed before the whole delta was read
+        for (replica, counter) in version {
+            self.counter = self.counter.max(counter);
+            let seen = self.version.entry(replica).or_default();
+            *seen = (*seen).max(counter);
+        }
+
+        for (key, (stamp, value)) in registers {
+            if self.registers.get(&key).is_none_or(|(old, _)| *old < stamp) {
+                self.registers.insert(key, (stamp, value));
+            }
+        }
+
+        self.added.extend(added);
+        self.removed.extend(removed);
+
+        Ok(())
+    }
+
+    fn stamp (&mut self) -> Result<Stamp, &'static str> {
+        self.counter = self.counter.checked_add(1).filter(|counter| *counter <= MAX_COUNTER).ok_or("The counter of the replica is used up")?;
+        self.version.insert(self.id, self.counter);
+
+        Ok(Stamp { counter: self.counter, replica: self.id })
+    }
+
+    /// The added elements that were not removed, by the edit adding them.
+    fn alive (&self) -> impl Iterator<Item = (&Stamp, &(Key, Value))> {
+        self.added.iter().filter(|(dot, _)| !self.removed.contains_key(dot))
+    }
+
+    /// Every element in the sets once, in order of the edit first adding it.
+    fn get_elements (&self) -> Vec<(&Key, &Value)> {
+        let mut seen = BTreeSet::new();
+
+        self.alive()
+            .filter(|(_, (key, _))| seen.insert(key))
+            .map(|(_, (key, value))| (key, value))
+            .collect()
+    }
+}
+
+/// The elements of the sets `todolist` holds.
+fn elements (todolist: &TodoList) -> BTreeMap<Key, Value> {
+    let mut elements = BTreeMap::new();
+
+    for task in todolist.get_tasks() {
+        let id = *task.get_id();
+        elements.insert(Key::Task(id), Value::Null);
+
+        for tag in task.get_tags() {
+            elements.entry(Key::TaskTag(id, tag.get_title().to_owned())).or_insert_with(|| json(tag));
+        }
+    }
+
+    for category in todolist.get_categories() {
+        elements.entry(Key::Category(category.get_title().to_owned())).or_insert_with(|| json(category));
+    }
+    for tag in todolist.get_tags() {
+        elements.entry(Key::Tag(tag.get_title().to_owned())).or_insert_with(|| json(tag));
+    }
+
+    elements
+}
+
+fn json (value: &impl serde::Serialize) -> Value {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+/// Items numbered in order of first use.
+struct Table<T> {
+    items: Vec<T>,
+    indexes: BTreeMap<T, u64>
+}
+
+impl<T> Default for Table<T> {
+    fn default () -> Self {
+        Self { items: vec![], indexes: BTreeMap::new() }
+    }
+}
+
+impl<T: Ord + Clone> Table<T> {
+    fn index (&mut self, item: T) -> u64 {
+        if let Some(index) = self.indexes.get(&item) {
+            return *index;
+        }
+
+        let index = self.items.len() as u64;
+        self.items.push(item.clone());
+        self.indexes.insert(item, index);
+
+        index
+    }
+}
+
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>
+}
+
+impl Writer {
+    fn new (kind: u8) -> Self {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend([FORMAT, kind]);
+
+        Self { bytes }
+    }
+
+    /// LEB128, seven bits a byte with the high bit set on all but the last.
+    fn varint (&mut self, mut number: u64) {
+        while number >= 0x80 {
+            self.bytes.push(number as u8 | 0x80);
+            number >>= 7;
+        }
+
+        self.bytes.push(number as u8);
+    }
+
+    fn uuid (&mut self, id: &Uuid) {
+        self.bytes.extend_from_slice(id.as_bytes());
+    }
+
+    fn data (&mut self, data: &[u8]) {
+        self.varint(data.len() as u64);
+        self.bytes.extend_from_slice(data);
+    }
+
+    fn stamp (&mut self, stamp: &Stamp, replicas: &mut Table<Uuid>) {
+        self.varint(stamp.counter);
+        self.varint(replicas.index(stamp.replica));
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8]
+}
+
+impl<'a> Reader<'a> {
+    fn new (bytes: &'a [u8], kind: u8) -> Result<Self, &'static str> {
+        if bytes.len() < MAGIC.len() + 2 || &bytes[..MAGIC.len()] != MAGIC {
+            return Err("The bytes are not sync data");
+        }
+        if bytes[MAGIC.len()] != FORMAT {
+            return Err("The sync data has an unknown format version");
+        }
+        if bytes[MAGIC.len() + 1] != kind {
+            return Err("The sync data is a version where a delta is expected or the other way around");
+        }
+
+        Ok(Self { bytes: &bytes[MAGIC.len() + 2..] })
+    }
+
+    fn take (&mut self, count: usize) -> Result<&'a [u8], &'static str> {
+        if self.bytes.len() < count {
+            return Err("The sync data is cut short");
+        }
+
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+
+        Ok(taken)
+    }
+
+    fn byte (&mut self) -> Result<u8, &'static str> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint (&mut self) -> Result<u64, &'static str> {
+        let mut number = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            number |= u64::from(byte & 0x7f) << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(number);
+            }
+        }
+
+        Err("The sync data has a number too large")
+    }
+
+    fn uuid (&mut self) -> Result<Uuid, &'static str> {
+        Ok(Uuid::from_bytes(self.take(16)?.try_into().unwrap_or_default()))
+    }
+
+    fn data (&mut self) -> Result<&'a [u8], &'static str> {
+        let length = self.varint()?;
+        self.take(usize::try_from(length).map_err(|_| "The sync data is cut short")?)
+    }
+
+    fn string (&mut self) -> Result<String, &'static str> {
+        String::from_utf8(self.data()?.to_vec()).map_err(|_| "The sync data has a text that is not UTF-8")
+    }
+
+    fn json (&mut self) -> Result<Value, &'static str> {
+        serde_json::from_slice(self.data()?).map_err(|_| "The sync data has an invalid value")
+    }
+
+    fn counter (&mut self) -> Result<u64, &'static str> {
+        match self.varint()? {
+            counter if counter <= MAX_COUNTER => Ok(counter),
+            _ => Err("The delta has a counter out of range")
+        }
+    }
+
+    fn stamp (&mut self, replicas: &[Uuid]) -> Result<Stamp, &'static str> {
+        let counter = self.counter()?;
+        let replica = replicas.get(self.varint()? as usize).copied().ok_or("The delta refers to a missing id")?;
+
+        Ok(Stamp { counter, replica })
+    }
+
+    /// A count followed by that many items.
+    fn list<T> (&mut self, item: fn (&mut Self) -> Result<T, &'static str>) -> Result<Vec<T>, &'static str> {
+        let count = self.varint()?;
+        let mut items = Vec::with_capacity(count.min(self.bytes.len() as u64) as usize);
+
+        for _ in 0..count {
+            items.push(item(self)?);
+        }
+
+        Ok(items)
+    }
+
+    fn end (&self) -> Result<(), &'static str> {
+        match self.bytes.is_empty() {
+            true => Ok(()),
+            false => Err("The sync data has bytes past its end")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sync (from: &Replica, to: &mut Replica) {
+        to.apply(&from.delta(&to.get_version()).unwrap()).unwrap();
+    }
+
+    fn edit (replica: &mut Replica, change: impl FnOnce (&mut TodoList)) {
+        let base = replica.get_todolist().unwrap();
+        let mut todolist = replica.get_todolist().unwrap();
+        change(&mut todolist);
+        replica.commit(&base, &todolist).unwrap();
+    }
+
+    fn same (a: &Replica, b: &Replica) -> bool {
+        json(&a.get_todolist().unwrap()) == json(&b.get_todolist().unwrap())
+    }
+
+    fn laptop_and_phone () -> (Replica, Replica, Uuid) {
+        let mut laptop = Replica::new();
+        let mut phone = Replica::new();
+        let mut id = Uuid::nil();
+
+        edit(&mut laptop, |todolist| {
+            let task = todolist.new_task("Call mom").unwrap().tags(&[Tag::new("family").unwrap()]).build();
+            id = *task.get_id();
+            todolist.add_task(task).unwrap();
+            todolist.new_category("Home").unwrap();
+        });
+        sync(&laptop, &mut phone);
+
+        (laptop, phone, id)
+    }
+
+    #[test]
+    fn converges () {
+        let (mut laptop, mut phone, id) = laptop_and_phone();
+        assert!(same(&laptop, &phone));
+        assert_eq!(phone.get_todolist().unwrap().get_categories()[0].get_title(), "Home");
+
+        edit(&mut laptop, |todolist| {
+            let task = todolist.get_task_mut(&id).unwrap();
+            task.set_title("Call dad").unwrap();
+            task.add_tag(Tag::new("phone").unwrap()).unwrap();
+        });
+        edit(&mut phone, |todolist| {
+            let task = todolist.get_task_mut(&id).unwrap();
+            task.set_priority(Priority::Red);
+            task.remove_tag(Tag::new("family").unwrap());
+        });
+        edit(&mut phone, |todolist| todolist.get_task_mut(&id).unwrap().set_title("Call mom and dad").unwrap());
+
+        sync(&laptop, &mut phone);
+        sync(&phone, &mut laptop);
+        assert!(same(&laptop, &phone));
+
+        let todolist = laptop.get_todolist().unwrap();
+        let task = todolist.get_task(&id).unwrap();
+        // the phone edited the title later, by Lamport counter
+        assert_eq!(task.get_title(), "Call mom and dad");
+        assert_eq!(task.get_priority(), &Priority::Red);
+        assert_eq!(task.get_tags(), &vec![Tag::new("phone").unwrap()]);
+    }
+
+    #[test]
+    fn removal_wins_over_edit () {
+        let (mut laptop, mut phone, id) = laptop_and_phone();
+
+        edit(&mut laptop, |todolist| {
+            todolist.remove_task_by_id(&id).unwrap();
+        });
+        edit(&mut phone, |todolist| todolist.get_task_mut(&id).unwrap().set_description("Sunday"));
+
+        sync(&laptop, &mut phone);
+        sync(&phone, &mut laptop);
+
+        assert!(laptop.get_todolist().unwrap().get_tasks().is_empty());
+        assert!(same(&laptop, &phone));
+    }
+
+    #[test]
+    fn concurrent_add_wins_over_removal () {
+        let (mut laptop, mut phone, id) = laptop_and_phone();
+
+        edit(&mut laptop, |todolist| todolist.get_task_mut(&id).unwrap().remove_tag(Tag::new("family").unwrap()));
+        edit(&mut phone, |todolist| todolist.get_task_mut(&id).unwrap().remove_tag(Tag::new("family").unwrap()));
+        edit(&mut phone, |todolist| todolist.get_task_mut(&id).unwrap().add_tag(Tag::new("family").unwrap()).unwrap());
+
+        sync(&laptop, &mut phone);
+        sync(&phone, &mut laptop);
+
+        assert_eq!(laptop.get_todolist().unwrap().get_tasks()[0].get_tags(), &vec![Tag::new("family").unwrap()]);
+        assert!(same(&laptop, &phone));
+    }
+
+    #[test]
+    fn delta_holds_unseen_edits () {
+        let (mut laptop, phone, id) = laptop_and_phone();
+
+        let nothing = laptop.delta(&phone.get_version()).unwrap();
+        let everything = laptop.delta(&Replica::new().get_version()).unwrap();
+        // the header, the id of the laptop and its counter
+        assert!(nothing.len() < 40);
+
+        edit(&mut laptop, |todolist| todolist.get_task_mut(&id).unwrap().set_priority(Priority::Green));
+        let change = laptop.delta(&phone.get_version()).unwrap();
+        assert!(change.len() > nothing.len() && change.len() < everything.len() / 4);
+
+        // applying twice changes nothing
+        let mut copy = Replica::new();
+        copy.apply(&everything).unwrap();
+        copy.apply(&change).unwrap();
+        copy.apply(&everything).unwrap();
+        assert!(same(&copy, &laptop));
+    }
+
+    #[test]
+    fn commit_keeps_edits_applied_meanwhile () {
+        let (mut laptop, mut phone, id) = laptop_and_phone();
+        let base = phone.get_todolist().unwrap();
+        let mut todolist = phone.get_todolist().unwrap();
+        todolist.get_task_mut(&id).unwrap().set_priority(Priority::Red);
+
+        // the laptop adds a task and renames the other while the phone edits
+        edit(&mut laptop, |todolist| {
+            let task = todolist.new_task("Buy milk").unwrap().build();
+            todolist.add_task(task).unwrap();
+            todolist.get_task_mut(&id).unwrap().set_title("Call dad").unwrap();
+        });
+        sync(&laptop, &mut phone);
+        phone.commit(&base, &todolist).unwrap();
+
+        let todolist = phone.get_todolist().unwrap();
+        assert_eq!(todolist.get_tasks().len(), 2);
+        assert_eq!(todolist.get_task(&id).unwrap().get_title(), "Call dad");
+        assert_eq!(todolist.get_task(&id).unwrap().get_priority(), &Priority::Red);
+
+        sync(&phone, &mut laptop);
+        assert!(same(&laptop, &phone));
+    }
+
+    #[test]
+    fn rejects_counters_out_of_range () {
+        let (laptop, _, _) = laptop_and_phone();
+        let mut hostile = Replica::new();
+        hostile.version.insert(*hostile.get_id(), u64::MAX);
+
+        let mut replica = Replica::new();
+        assert!(replica.apply(&hostile.delta(&replica.get_version()).unwrap()).is_err());
+
+        replica.counter = MAX_COUNTER;
+        let base = laptop.get_todolist().unwrap();
+        assert!(replica.commit(&TodoList::default(), &base).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_bytes () {
+        let (laptop, phone, _) = laptop_and_phone();
+        let delta = laptop.delta(&Replica::new().get_version()).unwrap();
+        let mut replica = Replica::new();
+
+        assert!(replica.apply(&delta[..delta.len() - 1]).is_err());
+        assert!(replica.apply(&[delta.as_slice(), &[0]].concat()).is_err());
+        assert!(replica.apply(&phone.get_version()).is_err());
+        assert!(replica.apply(b"TDLR\x02\x01").is_err());
+        assert!(laptop.delta(b"{}").is_err());
+        assert!(replica.get_todolist().unwrap().get_tasks().is_empty());
+
+        replica.apply(&delta).unwrap();
+        assert!(same(&replica, &laptop));
+    }
+
+    // proptest is not built for the browser
+    #[cfg(not(target_arch = "wasm32"))]
+    mod properties {
+        use proptest::prelude::*;
+
+        use super::*;
+
+        const TITLES: [&str; 4] = ["Call mom", "Buy milk", "Pay rent", "Walk the dog"];
+        const TAGS: [&str; 3] = ["home", "work", "errand"];
+
+        #[derive(Debug, Clone)]
+        enum Op {
+            Add(usize, usize),
+            Rename(usize, usize, usize),
+            Prioritize(usize, usize, bool),
+            Tag(usize, usize, usize),
+            Untag(usize, usize, usize),
+            Remove(usize, usize),
+            Category(usize, usize),
+            Sync(usize, usize)
+        }
+
+        fn op () -> impl Strategy<Value = Op> {
+            let replica = 0..3usize;
+            let task = 0..8usize;
+
+            prop_oneof![
+                (replica.clone(), 0..TITLES.len()).prop_map(|(replica, title)| Op::Add(replica, title)),
+                (replica.clone(), task.clone(), 0..TITLES.len()).prop_map(|(replica, task, title)| Op::Rename(replica, task, title)),
+                (replica.clone(), task.clone(), any::<bool>()).prop_map(|(replica, task, red)| Op::Prioritize(replica, task, red)),
+                (replica.clone(), task.clone(), 0..TAGS.len()).prop_map(|(replica, task, tag)| Op::Tag(replica, task, tag)),
+                (replica.clone(), task.clone(), 0..TAGS.len()).prop_map(|(replica, task, tag)| Op::Untag(replica, task, tag)),
+                (replica.clone(), task).prop_map(|(replica, task)| Op::Remove(replica, task)),
+                (replica.clone(), 0..TAGS.len()).prop_map(|(replica, category)| Op::Category(replica, category)),
+                (replica.clone(), replica).prop_map(|(from, to)| Op::Sync(from, to))
+            ]
+        }
+
+        /// The id of the task at `index`, wrapped around, if there are any.
+        fn pick (todolist: &TodoList, index: usize) -> Option<Uuid> {
+            let tasks = todolist.get_tasks();
+            (!tasks.is_empty()).then(|| *tasks[index % tasks.len()].get_id())
+        }
+
+        fn run (replicas: &mut [Replica], op: &Op) {
+            let replica = match *op {
+                Op::Sync(from, to) => {
+                    if from != to {
+                        let delta = replicas[from].delta(&replicas[to].get_version()).unwrap();
+                        replicas[to].apply(&delta).unwrap();
+                    }
+                    return;
+                }
+                Op::Add(replica, ..) | Op::Rename(replica, ..) | Op::Prioritize(replica, ..) | Op::Tag(replica, ..)
+                    | Op::Untag(replica, ..) | Op::Remove(replica, ..) | Op::Category(replica, ..) => replica
+            };
+
+            let base = replicas[replica].get_todolist().unwrap();
+            let mut todolist = replicas[replica].get_todolist().unwrap();
+            let task = |index| pick(&todolist, index);
+
+            match *op {
+                Op::Add(_, title) => {
+                    let task = todolist.new_task(TITLES[title]).unwrap().build();
+                    todolist.add_task(task).unwrap();
+                }
+                Op::Rename(_, index, title) => if let Some(id) = task(index) {
+                    todolist.get_task_mut(&id).unwrap().set_title(TITLES[title]).unwrap();
+                }
+                Op::Prioritize(_, index, red) => if let Some(id) = task(index) {
+                    todolist.get_task_mut(&id).unwrap().set_priority(if red { Priority::Red } else { Priority::Green });
+                }
+                Op::Tag(_, index, tag) => if let Some(id) = task(index) {
+                    let _ = todolist.get_task_mut(&id).unwrap().add_tag(Tag::new(TAGS[tag]).unwrap());
+                }
+                Op::Untag(_, index, tag) => if let Some(id) = task(index) {
+                    todolist.get_task_mut(&id).unwrap().remove_tag(Tag::new(TAGS[tag]).unwrap());
+                }
+                Op::Remove(_, index) => if let Some(id) = task(index) {
+                    todolist.remove_task_by_id(&id).unwrap();
+                }
+                Op::Category(_, category) => {
+                    let _ = todolist.new_category(TAGS[category]);
+                }
+                Op::Sync(..) => {}
+            }
+
+            replicas[replica].commit(&base, &todolist).unwrap();
+        }
+
+        proptest! {
+            #[test]
+            fn replicas_converge (ops in prop::collection::vec(op(), 0..40)) {
+                let mut replicas = [Replica::new(), Replica::new(), Replica::new()];
+
+                for op in ops.iter() {
+                    run(&mut replicas, op);
+                }
+
+                for (from, to) in [(0, 1), (1, 2), (2, 0), (0, 1)] {
+                    run(&mut replicas, &Op::Sync(from, to));
+                }
+
+                prop_assert!(same(&replicas[0], &replicas[1]));
+                prop_assert!(same(&replicas[1], &replicas[2]));
+            }
+
+            #[test]
+            fn delivery_order_does_not_matter (ops in prop::collection::vec(op(), 0..30), order in Just([0usize, 1, 2]).prop_shuffle()) {
+                let mut replicas = [Replica::new(), Replica::new(), Replica::new()];
+
+                for op in ops.iter() {
+                    run(&mut replicas, op);
+                }
+
+                let empty = Replica::new().get_version();
+                let deltas: Vec<Vec<u8>> = replicas.iter().map(|replica| replica.delta(&empty).unwrap()).collect();
+
+                let mut forward = Replica::new();
+                deltas.iter().for_each(|delta| forward.apply(delta).unwrap());
+
+                let mut shuffled = Replica::new();
+                for index in order {
+                    shuffled.apply(&deltas[index]).unwrap();
+                    shuffled.apply(&deltas[index]).unwrap();
+                }
+
+                prop_assert!(same(&forward, &shuffled));
+            }
+        }
+    }
+}
@@ -0,0 +1,317 @@
+//! Keeping a `TodoList` in a file between runs. A store loads and saves a
+//! whole list, a codec turns the list into the bytes a store writes.
+//!
+//! The stores keeping a list in one file lock it while they use it, replace
+//! it atomically and notice when another process saved it since it was
+//! loaded, see `OnConflict`. They can keep copies of the replaced files with
+//! `backups`. `GitStore` keeps the history of a list in a git repository
+//! instead.
+//!
+//! ```no_run
+//! use todolist::store::{FileStore, JsonCodec, TodoListStore};
+//!
+//! let mut store = FileStore::new("tasks.json", JsonCodec);
+//! let todolist = store.load()?;
+//! store.save(&todolist)?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+pub mod encrypted;
+mod file;
+pub mod git;
+
+use std::io;
+use std::path::Path;
+
+use crate::TodoList;
+
+pub use encrypted::EncryptedStore;
+pub use git::GitStore;
+
+use file::FileAccess;
+
+/// Loads and saves a whole list.
+pub trait TodoListStore {
+    fn load (&mut self) -> io::Result<TodoList>;
+    fn save (&mut self, todolist: &TodoList) -> io::Result<()>;
+}
+
+/// Turns a list into bytes and back.
+pub trait TodoListCodec {
+    fn encode (&self, todolist: &TodoList) -> io::Result<Vec<u8>>;
+    fn decode (&self, bytes: &[u8]) -> io::Result<TodoList>;
+}
+
+/// The list with all task fields as JSON.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonCodec;
+
+impl TodoListCodec for JsonCodec {
+    fn encode (&self, todolist: &TodoList) -> io::Result<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(todolist)?)
+    }
+
+    fn decode (&self, bytes: &[u8]) -> io::Result<TodoList> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+/// What a save does when the file was changed since this store last loaded
+/// or saved it, or when it exists but was never loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnConflict {
+    /// Fails with an `Other` error, the list has to be loaded again.
+    #[default]
+    Refuse,
+    /// Saves the changes of both sides with `TodoList::merge`, a field
+    /// changed on both sides takes the value of the task updated last
+    /// (`NewestWins`). Load the list again to see the merged one, until then
+    /// every save merges the saved list into the file.
+    Merge
+}
+
+/// The bytes a store writes for a list, a codec or a codec and a cipher.
+trait Encoding {
+    fn encode (&mut self, todolist: &TodoList) -> io::Result<Vec<u8>>;
+    fn decode (&mut self, bytes: &[u8]) -> io::Result<TodoList>;
+}
+
+impl<C: TodoListCodec> Encoding for C {
+    fn encode (&mut self, todolist: &TodoList) -> io::Result<Vec<u8>> {
+        TodoListCodec::encode(self, todolist)
+    }
+
+    fn decode (&mut self, bytes: &[u8]) -> io::Result<TodoList> {
+        TodoListCodec::decode(self, bytes)
+    }
+}
+
+/// A list kept as is in one file.
+pub struct FileStore<C: TodoListCodec> {
+    file: FileAccess,
+    codec: C
+}
+
+impl<C: TodoListCodec> FileStore<C> {
+    pub fn new (path: impl AsRef<Path>, codec: C) -> Self {
+        Self { file: FileAccess::new(path.as_ref()), codec }
+    }
+
+    /// Keeps the last `count` replaced files, none by default.
+    pub fn backups (mut self, count: usize) -> Self {
+        self.file.backups = count;
+        self
+    }
+
+    pub fn on_conflict (mut self, on_conflict: OnConflict) -> Self {
+        self.file.on_conflict = on_conflict;
+        self
+    }
+
+    pub fn get_path (&self) -> &Path {
+        self.file.get_path()
+    }
+}
+
+impl<C: TodoListCodec> TodoListStore for FileStore<C> {
+    fn load (&mut self) -> io::Result<TodoList> {
+        self.file.load(&mut self.codec)
+    }
+
+    fn save (&mut self, todolist: &TodoList) -> io::Result<()> {
+        self.file.save(todolist, &mut self.codec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::*;
+
+    use crate::prelude::*;
+
+    /// A path in the temporary directory, unique to the test.
+    pub(super) fn temp_path (name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("todolist-{}-{}", std::process::id(), name))
+    }
+
+    /// Removes the file at `path` with its lock, backups and temporary files.
+    pub(super) fn remove_files (path: &Path) {
+        let name = path.file_name().unwrap().to_str().unwrap();
+
+        for entry in std::fs::read_dir(path.parent().unwrap()).unwrap().flatten() {
+            if entry.file_name().to_str().is_some_and(|file| file.starts_with(name)) {
+                std::fs::remove_file(entry.path()).unwrap();
+            }
+        }
+    }
+
+    fn todolist (titles: &[&str]) -> TodoList {
+        let mut todolist = TodoList::default();
+
+        for title in titles {
+            let task = todolist.new_task(title).unwrap().build();
+            todolist.add_task(task).unwrap();
+        }
+
+        todolist
+    }
+
+    fn titles (todolist: &TodoList) -> Vec<&str> {
+        todolist.get_tasks().iter().map(|task| task.get_title()).collect()
+    }
+
+    #[test]
+    fn file_store () {
+        let path = temp_path("store.json");
+        let mut store = FileStore::new(&path, JsonCodec);
+
+        assert_eq!(store.load().err().map(|err| err.kind()), Some(io::ErrorKind::NotFound));
+
+        let mut todolist = TodoList::default();
+        let mut task = todolist.new_task("Call mom").unwrap().deadline(chrono::Utc::now()).build();
+        task.set_status(Status::Progress);
+        todolist.add_task(task).unwrap();
+
+        store.save(&todolist).unwrap();
+        let loaded = store.load().unwrap();
+        assert!(loaded.get_tasks() == todolist.get_tasks());
+
+        std::fs::write(&path, "{").unwrap();
+        assert_eq!(store.load().err().map(|err| err.kind()), Some(io::ErrorKind::UnexpectedEof));
+
+        remove_files(&path);
+    }
+
+    #[test]
+    fn rotating_backups () {
+        let path = temp_path("backups.json");
+        let mut store = FileStore::new(&path, JsonCodec).backups(2);
+
+        for title in ["First", "Second", "Third", "Fourth"] {
+            store.save(&todolist(&[title])).unwrap();
+        }
+
+        let name = path.file_name().unwrap().to_str().unwrap();
+        let read = |file: String| std::fs::read(path.with_file_name(file)).map(|bytes| titles(&JsonCodec.decode(&bytes).unwrap()).concat());
+
+        assert_eq!(read(name.to_owned()).unwrap(), "Fourth");
+        assert_eq!(read(format!("{}.1", name)).unwrap(), "Third");
+        assert_eq!(read(format!("{}.2", name)).unwrap(), "Second");
+        assert!(read(format!("{}.3", name)).is_err());
+
+        let temporary = std::fs::read_dir(path.parent().unwrap()).unwrap().flatten()
+            .any(|entry| entry.file_name().to_str().is_some_and(|file| file.starts_with(name) && file.ends_with(".tmp")));
+        assert!(!temporary);
+
+        remove_files(&path);
+    }
+
+    #[test]
+    fn refuses_changed_file () {
+        let path = temp_path("refuse.json");
+        FileStore::new(&path, JsonCodec).save(&todolist(&["First"])).unwrap();
+
+        let mut cli = FileStore::new(&path, JsonCodec);
+        let mut tui = FileStore::new(&path, JsonCodec);
+        let list = cli.load().unwrap();
+        tui.load().unwrap();
+
+        tui.save(&todolist(&["From the TUI"])).unwrap();
+
+        let error = cli.save(&list).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::Other);
+        assert_eq!(titles(&cli.load().unwrap()), vec!["From the TUI"]);
+        cli.save(&list).unwrap();
+
+        // rewriting the same content is not a change
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes).unwrap();
+        cli.save(&list).unwrap();
+
+        // nor is a removed file, a file saved over without loading it is
+        std::fs::remove_file(&path).unwrap();
+        cli.save(&list).unwrap();
+        assert!(FileStore::new(&path, JsonCodec).save(&list).is_err());
+
+        remove_files(&path);
+    }
+
+    #[test]
+    fn merges_changed_file () {
+        let path = temp_path("merge.json");
+        let clock = Arc::new(FixedClock::new(chrono::Utc::now()));
+        let mut first = todolist(&["Both change", "We change", "They remove", "We remove", "They change"]).with_clock(clock.clone());
+        first.new_category("Work").unwrap();
+        FileStore::new(&path, JsonCodec).save(&first).unwrap();
+
+        let mut ours_store = FileStore::new(&path, JsonCodec).on_conflict(OnConflict::Merge);
+        let mut theirs_store = FileStore::new(&path, JsonCodec);
+        let mut ours = ours_store.load().unwrap().with_clock(clock.clone());
+        let mut theirs = theirs_store.load().unwrap().with_clock(clock.clone());
+        let id = |list: &TodoList, index: usize| *list.get_tasks()[index].get_id();
+
+        clock.advance(chrono::Duration::minutes(1));
+        theirs.get_task_mut(&id(&theirs, 0)).unwrap().set_description("theirs");
+        theirs.get_task_mut(&id(&theirs, 4)).unwrap().set_priority(Priority::Red);
+        theirs.remove_task_by_id(&id(&theirs, 2)).unwrap();
+        let task = theirs.new_task("They add").unwrap().build();
+        theirs.add_task(task).unwrap();
+        theirs.new_tag("shared").unwrap();
+        theirs_store.save(&theirs).unwrap();
+
+        clock.advance(chrono::Duration::minutes(1));
+        ours.get_task_mut(&id(&ours, 0)).unwrap().set_description("ours");
+        ours.get_task_mut(&id(&ours, 1)).unwrap().set_priority(Priority::Green);
+        ours.remove_task_by_id(&id(&ours, 3)).unwrap();
+        let task = ours.new_task("We add").unwrap().build();
+        ours.add_task(task).unwrap();
+        ours_store.save(&ours).unwrap();
+
+        // saving again before loading the merge keeps their changes
+        clock.advance(chrono::Duration::minutes(1));
+        ours.get_task_mut(&id(&ours, 1)).unwrap().set_description("later");
+        ours_store.save(&ours).unwrap();
+        ours_store.save(&ours).unwrap();
+
+        let merged = ours_store.load().unwrap();
+        let tasks = merged.get_tasks();
+
+        assert_eq!(titles(&merged), vec!["Both change", "We change", "They change", "We add", "They add"]);
+        assert_eq!(tasks[0].get_description(), "ours");
+        assert_eq!(tasks[1].get_priority(), &Priority::Green);
+        assert_eq!(tasks[1].get_description(), "later");
+        assert_eq!(tasks[2].get_priority(), &Priority::Red);
+        assert_eq!(merged.get_categories().len(), first.get_categories().len());
+        assert!(merged.get_tags().contains(&Tag::new("shared").unwrap()));
+
+        remove_files(&path);
+    }
+
+    #[test]
+    fn waits_for_lock () {
+        let path = temp_path("lock.json");
+        let mut lock_name = path.file_name().unwrap().to_owned();
+        lock_name.push(".lock");
+
+        let lock = std::fs::File::create(path.with_file_name(lock_name)).unwrap();
+        lock.lock().unwrap();
+
+        let saving = std::thread::spawn({
+            let path = path.clone();
+            move || FileStore::new(&path, JsonCodec).save(&todolist(&["Later"]))
+        });
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!path.exists());
+
+        lock.unlock().unwrap();
+        saving.join().unwrap().unwrap();
+        assert!(path.exists());
+
+        remove_files(&path);
+    }
+}
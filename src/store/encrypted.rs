@@ -0,0 +1,339 @@
+//! A store encrypting the bytes of any codec with a key derived from a
+//! passphrase. The file is written, locked and checked for changes like
+//! the one of a `FileStore`, and laid out as:
+//!
+//! | Bytes | Content                                                  |
+//! |-------|----------------------------------------------------------|
+//! | 8     | `TDLCRYPT`                                               |
+//! | 1     | format version, 1                                        |
+//! | 12    | Argon2id memory (KiB), iterations and parallelism, u32 LE |
+//! | 16    | salt                                                     |
+//! | 24    | nonce                                                    |
+//! | rest  | the encoded list, encrypted with XChaCha20-Poly1305      |
+//!
+//! The header is authenticated with the list, so changing any byte of the
+//! file makes loading fail instead of reading a changed list. A wrong
+//! passphrase fails the same way. Backups are encrypted like the file.
+
+use std::io;
+use std::path::Path;
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+
+use crate::TodoList;
+
+use super::file::FileAccess;
+use super::{Encoding, OnConflict, TodoListCodec, TodoListStore};
+
+const MAGIC: &[u8; 8] = b"TDLCRYPT";
+const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 12 + SALT_LEN + NONCE_LEN;
+/// Files asking for more memory to derive their key are refused, 4 GiB.
+const MAX_MEMORY_KIB: u32 = 4 * 1024 * 1024;
+
+/// A derived key with the salt and cost it was derived with.
+struct DerivedKey {
+    salt: [u8; SALT_LEN],
+    params: Params,
+    key: [u8; 32]
+}
+
+/// A list kept encrypted in one file, see the module documentation.
+pub struct EncryptedStore<C: TodoListCodec> {
+    file: FileAccess,
+    cipher: Cipher<C>
+}
+
+/// The codec of an encrypted store with the key to the file.
+struct Cipher<C: TodoListCodec> {
+    codec: C,
+    secret: Secret
+}
+
+/// A passphrase with the cost of new keys.
+struct Secret {
+    passphrase: String,
+    params: Params,
+    // the key of the last load or save, derived again when the salt or cost changes
+    key: Option<DerivedKey>
+}
+
+impl<C: TodoListCodec> EncryptedStore<C> {
+    /// Uses the default Argon2id cost (19 MiB, 2 iterations).
+    pub fn new (path: impl AsRef<Path>, passphrase: &str, codec: C) -> Self {
+        Self {
+            file: FileAccess::new(path.as_ref()),
+            cipher: Cipher {
+                codec,
+                secret: Secret {
+                    passphrase: passphrase.to_owned(),
+                    params: Params::default(),
+                    key: None
+                }
+            }
+        }
+    }
+
+    /// Sets the Argon2id cost used for the next save. Loading uses the
+    /// cost written in the file.
+    pub fn cost (mut self, memory_kib: u32, iterations: u32, parallelism: u32) -> Result<Self, &'static str> {
+        if memory_kib > MAX_MEMORY_KIB {
+            return Err("The key derivation needs too much memory");
+        }
+
+        self.cipher.secret.params = Params::new(memory_kib, iterations, parallelism, None).map_err(|_| "Invalid key derivation cost")?;
+        Ok(self)
+    }
+
+    /// Keeps the last `count` replaced files, none by default.
+    pub fn backups (mut self, count: usize) -> Self {
+        self.file.backups = count;
+        self
+    }
+
+    pub fn on_conflict (mut self, on_conflict: OnConflict) -> Self {
+        self.file.on_conflict = on_conflict;
+        self
+    }
+
+    pub fn get_path (&self) -> &Path {
+        self.file.get_path()
+    }
+
+    /// Encrypts the file and its backups again under `passphrase`, with a
+    /// new salt and the current cost. The old passphrase no longer opens
+    /// them.
+    pub fn rotate (&mut self, passphrase: &str) -> io::Result<()> {
+        let todolist = self.load()?;
+
+        let mut old = Secret {
+            passphrase: std::mem::replace(&mut self.cipher.secret.passphrase, passphrase.to_owned()),
+            params: self.cipher.secret.params.clone(),
+            key: self.cipher.secret.key.take()
+        };
+
+        self.save(&todolist)?;
+
+        let new = &mut self.cipher.secret;
+        self.file.rewrite_backups(|bytes| new.seal(&old.open(bytes)?))
+    }
+}
+
+impl<C: TodoListCodec> TodoListStore for EncryptedStore<C> {
+    fn load (&mut self) -> io::Result<TodoList> {
+        self.file.load(&mut self.cipher)
+    }
+
+    fn save (&mut self, todolist: &TodoList) -> io::Result<()> {
+        self.file.save(todolist, &mut self.cipher)
+    }
+}
+
+impl Secret {
+    fn derive (&self, salt: [u8; SALT_LEN], params: Params) -> io::Result<DerivedKey> {
+        let mut key = [0; 32];
+
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone())
+            .hash_password_into(self.passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|_| invalid_data("The key can't be derived"))?;
+
+        Ok(DerivedKey { salt, params, key })
+    }
+
+    /// The encoded list in the encrypted `bytes`.
+    fn open (&mut self, bytes: &[u8]) -> io::Result<Vec<u8>> {
+        if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(invalid_data("The file is not an encrypted list"));
+        }
+        if bytes[MAGIC.len()] != VERSION {
+            return Err(invalid_data("The encrypted list has an unknown version"));
+        }
+
+        let (header, ciphertext) = bytes.split_at(HEADER_LEN);
+        let cost = &header[MAGIC.len() + 1..MAGIC.len() + 13];
+        let number = |index: usize| u32::from_le_bytes(cost[index * 4..index * 4 + 4].try_into().unwrap());
+
+        if number(0) > MAX_MEMORY_KIB {
+            return Err(invalid_data("The key derivation needs too much memory"));
+        }
+
+        let params = Params::new(number(0), number(1), number(2), None).map_err(|_| invalid_data("Invalid key derivation cost"))?;
+        let salt: [u8; SALT_LEN] = header[MAGIC.len() + 13..MAGIC.len() + 13 + SALT_LEN].try_into().unwrap();
+        let nonce = XNonce::from_slice(&header[HEADER_LEN - NONCE_LEN..]);
+
+        let key = match self.key.take() {
+            Some(key) if key.salt == salt && key.params == params => key,
+            _ => self.derive(salt, params)?
+        };
+
+        let plaintext = XChaCha20Poly1305::new(&key.key.into())
+            .decrypt(nonce, Payload { msg: ciphertext, aad: header })
+            .map_err(|_| invalid_data("The encrypted list was changed or the passphrase is wrong"))?;
+
+        self.key = Some(key);
+        Ok(plaintext)
+    }
+
+    /// The encoded list in `plaintext` encrypted, see the module documentation.
+    fn seal (&mut self, plaintext: &[u8]) -> io::Result<Vec<u8>> {
+        let key = match self.key.take() {
+            Some(key) if key.params == self.params => key,
+            _ => {
+                let mut salt = [0; SALT_LEN];
+                OsRng.fill_bytes(&mut salt);
+                self.derive(salt, self.params.clone())?
+            }
+        };
+
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.push(VERSION);
+        for number in [key.params.m_cost(), key.params.t_cost(), key.params.p_cost()] {
+            header.extend_from_slice(&number.to_le_bytes());
+        }
+        header.extend_from_slice(&key.salt);
+        header.extend_from_slice(&nonce);
+
+        let ciphertext = XChaCha20Poly1305::new(&key.key.into())
+            .encrypt(&nonce, Payload { msg: plaintext, aad: &header })
+            .map_err(|_| invalid_data("The list can't be encrypted"))?;
+
+        self.key = Some(key);
+
+        header.extend_from_slice(&ciphertext);
+        Ok(header)
+    }
+}
+
+impl<C: TodoListCodec> Encoding for Cipher<C> {
+    fn decode (&mut self, bytes: &[u8]) -> io::Result<TodoList> {
+        self.codec.decode(&self.secret.open(bytes)?)
+    }
+
+    fn encode (&mut self, todolist: &TodoList) -> io::Result<Vec<u8>> {
+        self.secret.seal(&self.codec.encode(todolist)?)
+    }
+}
+
+fn invalid_data (message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::store::tests::{remove_files, temp_path};
+    use crate::store::JsonCodec;
+
+    fn store (path: &Path, passphrase: &str) -> EncryptedStore<JsonCodec> {
+        EncryptedStore::new(path, passphrase, JsonCodec).cost(64, 1, 1).unwrap()
+    }
+
+    fn todolist () -> TodoList {
+        let mut todolist = TodoList::default();
+        let task = todolist.new_task("Call Jane Doe").unwrap().description("ACME account").build();
+        todolist.add_task(task).unwrap();
+        todolist
+    }
+
+    #[test]
+    fn round_trip () {
+        let path = temp_path("encrypted-round-trip");
+        let todolist = todolist();
+
+        store(&path, "correct horse").save(&todolist).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(bytes.starts_with(MAGIC));
+        assert!(!bytes.windows(8).any(|window| window == b"Jane Doe"));
+
+        let loaded = store(&path, "correct horse").load().unwrap();
+        assert!(loaded.get_tasks() == todolist.get_tasks());
+
+        let error = store(&path, "wrong").load().err().map(|err| err.kind());
+        assert_eq!(error, Some(io::ErrorKind::InvalidData));
+
+        remove_files(&path);
+    }
+
+    #[test]
+    fn tampering_fails () {
+        let path = temp_path("encrypted-tampering");
+        store(&path, "secret").save(&todolist()).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+
+        // the version, the cost, the salt, the nonce and the list
+        for index in [MAGIC.len(), MAGIC.len() + 2, MAGIC.len() + 20, HEADER_LEN - 1, bytes.len() - 1, HEADER_LEN + 3] {
+            let mut changed = bytes.clone();
+            changed[index] ^= 1;
+            std::fs::write(&path, changed).unwrap();
+
+            let error = store(&path, "secret").load().err().map(|err| err.kind());
+            assert_eq!(error, Some(io::ErrorKind::InvalidData), "byte {}", index);
+        }
+
+        std::fs::write(&path, &bytes[..HEADER_LEN - 1]).unwrap();
+        assert!(store(&path, "secret").load().is_err());
+
+        std::fs::write(&path, "{}").unwrap();
+        assert!(store(&path, "secret").load().is_err());
+
+        remove_files(&path);
+    }
+
+    #[test]
+    fn rotate () {
+        let path = temp_path("encrypted-rotate");
+        let mut encrypted = store(&path, "old");
+        encrypted.save(&todolist()).unwrap();
+
+        let salt = std::fs::read(&path).unwrap()[MAGIC.len() + 13..MAGIC.len() + 29].to_vec();
+
+        // saving again keeps the key
+        let loaded = encrypted.load().unwrap();
+        encrypted.save(&loaded).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap()[MAGIC.len() + 13..MAGIC.len() + 29], salt[..]);
+
+        encrypted.rotate("new").unwrap();
+        assert_ne!(std::fs::read(&path).unwrap()[MAGIC.len() + 13..MAGIC.len() + 29], salt[..]);
+
+        assert!(store(&path, "old").load().is_err());
+        assert_eq!(store(&path, "new").load().unwrap().get_tasks()[0].get_title(), "Call Jane Doe");
+
+        remove_files(&path);
+    }
+
+    #[test]
+    fn rotate_backups () {
+        let path = temp_path("encrypted-rotate-backups");
+        let mut encrypted = store(&path, "old").backups(3);
+        encrypted.save(&todolist()).unwrap();
+        let loaded = encrypted.load().unwrap();
+        encrypted.save(&loaded).unwrap();
+
+        encrypted.rotate("new").unwrap();
+
+        for backup in ["1", "2"] {
+            let backup = path.with_extension(backup);
+            assert!(store(&backup, "old").load().is_err());
+            assert_eq!(store(&backup, "new").load().unwrap().get_tasks()[0].get_title(), "Call Jane Doe");
+        }
+        assert!(!path.with_extension("3").exists());
+
+        remove_files(&path);
+    }
+
+    #[test]
+    fn cost () {
+        assert!(EncryptedStore::new("unused", "secret", JsonCodec).cost(1, 1, 1).is_err());
+        assert!(EncryptedStore::new("unused", "secret", JsonCodec).cost(MAX_MEMORY_KIB + 1, 1, 1).is_err());
+    }
+}
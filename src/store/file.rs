@@ -0,0 +1,195 @@
+//! Reading and writing the file of a store so that processes sharing it
+//! don't lose each other's changes.
+//!
+//! * Loading and saving take an advisory lock on `<file>.lock`, shared to
+//!   load and exclusive to save.
+//! * A save writes a temporary file next to the list, syncs it to disk and
+//!   renames it over the list, so a crash leaves the old or the new list.
+//! * The content of the file is hashed on load and save, a save finding
+//!   another content refuses or merges, see `OnConflict`.
+//! * The replaced file is kept as `<file>.1`, moving older copies up to
+//!   `<file>.<backups>`.
+
+use std::fs::{self, File, OpenOptions};
+use std::hash::{DefaultHasher, Hasher};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::prelude::*;
+use crate::TodoList;
+
+use super::{Encoding, OnConflict};
+
+pub(super) struct FileAccess {
+    path: PathBuf,
+    pub(super) backups: usize,
+    pub(super) on_conflict: OnConflict,
+    // hash of the file and the list as last loaded or saved
+    seen: Option<(u64, TodoList)>,
+    // the file holds a merge the caller's list doesn't have
+    behind: bool
+}
+
+impl FileAccess {
+    pub(super) fn new (path: &Path) -> Self {
+        Self { path: path.to_owned(), backups: 0, on_conflict: OnConflict::default(), seen: None, behind: false }
+    }
+
+    pub(super) fn get_path (&self) -> &Path {
+        &self.path
+    }
+
+    pub(super) fn load (&mut self, encoding: &mut impl Encoding) -> io::Result<TodoList> {
+        let _lock = self.lock(false)?;
+
+        let bytes = fs::read(&self.path)?;
+        let todolist = encoding.decode(&bytes)?;
+
+        self.seen = Some((hash(&bytes), snapshot(&todolist)));
+        self.behind = false;
+        Ok(todolist)
+    }
+
+    pub(super) fn save (&mut self, todolist: &TodoList, encoding: &mut impl Encoding) -> io::Result<()> {
+        let _lock = self.lock(true)?;
+
+        let current = match fs::read(&self.path) {
+            Ok(bytes) => Some(bytes),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err)
+        };
+
+        // a removed file is written again, a file saved without loading it first
+        // is a change and so is a file merged on the last save
+        let changed = match (&self.seen, &current) {
+            (_, None) => false,
+            (None, Some(_)) => true,
+            (Some((seen, _)), Some(bytes)) => self.behind || *seen != hash(bytes)
+        };
+
+        let ours = todolist;
+        let merged;
+        let todolist = match (changed, self.on_conflict) {
+            (false, _) => todolist,
+            (true, OnConflict::Refuse) => return Err(io::Error::other("The file was changed since it was loaded")),
+            (true, OnConflict::Merge) => {
+                let theirs = encoding.decode(current.as_deref().unwrap_or_default())?;
+                let empty = TodoList::default();
+                let base = self.seen.as_ref().map_or(&empty, |(_, base)| base);
+
+                let merge = todolist.merge(base, &theirs, &NewestWins);
+                if let Some(error) = merge.get_errors().first() {
+                    return Err(io::Error::other(error.to_string()));
+                }
+
+                merged = merge.into_todolist();
+                &merged
+            }
+        };
+
+        let bytes = encoding.encode(todolist)?;
+
+        if current.is_some() {
+            self.back_up()?;
+        }
+        write_atomic(&self.path, &bytes)?;
+
+        // after a merge the list of the caller stays the base of the next one
+        self.seen = Some((hash(&bytes), snapshot(ours)));
+        self.behind = changed;
+        Ok(())
+    }
+
+    /// Locks `<file>.lock` until the returned file is dropped.
+    fn lock (&self, exclusive: bool) -> io::Result<File> {
+        let file = OpenOptions::new().create(true).truncate(false).write(true).open(sibling(&self.path, "lock"))?;
+
+        if exclusive {
+            file.lock()?;
+        } else {
+            file.lock_shared()?;
+        }
+
+        Ok(file)
+    }
+
+    /// Replaces every backup with `rewrite` of its bytes.
+    pub(super) fn rewrite_backups (&self, mut rewrite: impl FnMut (&[u8]) -> io::Result<Vec<u8>>) -> io::Result<()> {
+        let _lock = self.lock(true)?;
+
+        for index in 1..=self.backups {
+            let path = sibling(&self.path, &index.to_string());
+
+            match fs::read(&path) {
+                Ok(bytes) => write_atomic(&path, &rewrite(&bytes)?)?,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err)
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copies the file to `<file>.1`, moving the older copies up.
+    fn back_up (&self) -> io::Result<()> {
+        if self.backups == 0 {
+            return Ok(());
+        }
+
+        for index in (1..self.backups).rev() {
+            match fs::rename(sibling(&self.path, &index.to_string()), sibling(&self.path, &(index + 1).to_string())) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+                _ => {}
+            }
+        }
+
+        fs::copy(&self.path, sibling(&self.path, "1")).map(|_| ())
+    }
+}
+
+/// The tasks, categories and tags of `todolist`.
+fn snapshot (todolist: &TodoList) -> TodoList {
+    TodoList {
+        tasks: todolist.tasks.clone(),
+        categories: todolist.categories.clone(),
+        tags: todolist.tags.clone(),
+        ..Default::default()
+    }
+}
+
+/// `<file>.<extension>` in the directory of `path`.
+fn sibling (path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(".");
+    name.push(extension);
+
+    path.with_file_name(name)
+}
+
+fn hash (bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+fn write_atomic (path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let temp = sibling(path, &format!("{}.tmp", std::process::id()));
+
+    let written = File::create(&temp).and_then(|mut file| {
+        file.write_all(bytes)?;
+        file.sync_all()
+    });
+
+    if let Err(err) = written.and_then(|_| fs::rename(&temp, path)) {
+        let _ = fs::remove_file(&temp);
+        return Err(err);
+    }
+
+    // the rename is only durable once the directory is synced
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        File::open(dir)?.sync_all()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,341 @@
+//! A store keeping a list in a git repository, committing every save. The
+//! working tree holds:
+//!
+//! | Path                 | Content                          |
+//! |----------------------|----------------------------------|
+//! | `tasks/<uuid>.json`  | one task with all its fields     |
+//! | `categories.json`    | the categories of the list       |
+//! | `tags.json`          | the tags of the list             |
+//!
+//! A save writes the files, removes the files of removed tasks and commits
+//! when anything changed, with a message naming the added, updated and
+//! removed tasks and the changed registries. Tasks are loaded in order of creation, so the order of a
+//! list is not kept. `push` and `pull` share the list through any remote,
+//! pulls only fast-forward.
+//!
+//! The `git` command has to be installed.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::prelude::*;
+use crate::prelude::load::TodoListLoad;
+use crate::TodoList;
+
+use super::TodoListStore;
+
+const TASKS: &str = "tasks";
+const CATEGORIES: &str = "categories.json";
+const TAGS: &str = "tags.json";
+
+/// A list kept in a git repository, see the module documentation.
+pub struct GitStore {
+    dir: PathBuf,
+    author: Option<(String, String)>
+}
+
+struct Files {
+    tasks: Vec<Task>,
+    categories: Vec<Category>,
+    tags: Vec<Tag>
+}
+
+impl TodoListLoad for Files {
+    fn get_todolist_categories (&self) -> Vec<Category> {
+        self.categories.clone()
+    }
+
+    fn get_todolist_tasks (&self) -> Vec<Task> {
+        self.tasks.clone()
+    }
+
+    fn get_todolist_tags (&self) -> Vec<Tag> {
+        self.tags.clone()
+    }
+}
+
+impl GitStore {
+    /// Uses the repository at `dir`, created by the first save when `dir`
+    /// is not one yet.
+    pub fn new (dir: impl AsRef<Path>) -> Self {
+        Self { dir: dir.as_ref().to_owned(), author: None }
+    }
+
+    /// Commits as `name <email>` instead of the user of the git config.
+    pub fn author (mut self, name: &str, email: &str) -> Self {
+        self.author = Some((name.to_owned(), email.to_owned()));
+        self
+    }
+
+    pub fn get_dir (&self) -> &Path {
+        &self.dir
+    }
+
+    /// Pushes the current branch to the branch of the same name on `remote`,
+    /// a remote name or url.
+    pub fn push (&self, remote: &str) -> io::Result<()> {
+        let branch = self.branch()?;
+        self.git(&["push", remote, &format!("HEAD:refs/heads/{}", branch)])
+    }
+
+    /// Fast-forwards the current branch to the branch of the same name on
+    /// `remote`. Fails when both have commits the other has not.
+    pub fn pull (&self, remote: &str) -> io::Result<()> {
+        let branch = self.branch()?;
+        self.git(&["pull", "--ff-only", remote, &branch])
+    }
+
+    fn branch (&self) -> io::Result<String> {
+        self.init()?;
+        self.output(&["symbolic-ref", "--short", "HEAD"])
+    }
+
+    fn init (&self) -> io::Result<()> {
+        if !self.dir.join(".git").exists() {
+            fs::create_dir_all(&self.dir)?;
+            self.git(&["init", "--quiet"])?;
+        }
+
+        Ok(())
+    }
+
+    fn git (&self, args: &[&str]) -> io::Result<()> {
+        self.output(args).map(|_| ())
+    }
+
+    /// Runs git in the repository and returns its trimmed output.
+    fn output (&self, args: &[&str]) -> io::Result<String> {
+        let mut command = Command::new("git");
+        command.arg("-C").arg(&self.dir).args(args);
+
+        if let Some((name, email)) = &self.author {
+            command
+                .env("GIT_AUTHOR_NAME", name)
+                .env("GIT_AUTHOR_EMAIL", email)
+                .env("GIT_COMMITTER_NAME", name)
+                .env("GIT_COMMITTER_EMAIL", email);
+        }
+
+        let output = command.output()?;
+
+        if !output.status.success() {
+            let message = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+            return Err(io::Error::other(format!("git {}: {}", args[0], message)));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    }
+
+    fn task_path (&self, task: &Task) -> PathBuf {
+        self.dir.join(TASKS).join(format!("{}.json", task.get_id()))
+    }
+
+    /// The tasks in the working tree by the name of their file.
+    fn read_tasks (&self) -> io::Result<BTreeMap<String, Task>> {
+        let mut tasks = BTreeMap::new();
+
+        let entries = match fs::read_dir(self.dir.join(TASKS)) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(tasks),
+            Err(err) => return Err(err)
+        };
+
+        for entry in entries {
+            let path = entry?.path();
+
+            if path.extension().is_some_and(|extension| extension == "json") {
+                let task = serde_json::from_slice(&fs::read(&path)?)?;
+                tasks.insert(path.file_name().unwrap_or_default().to_string_lossy().into_owned(), task);
+            }
+        }
+
+        Ok(tasks)
+    }
+}
+
+impl TodoListStore for GitStore {
+    fn load (&mut self) -> io::Result<TodoList> {
+        let mut tasks: Vec<Task> = self.read_tasks()?.into_values().collect();
+        tasks.sort_by(|a, b| a.get_created_at().cmp(b.get_created_at()).then(a.get_id().cmp(b.get_id())));
+
+        let categories = serde_json::from_slice(&fs::read(self.dir.join(CATEGORIES))?)?;
+        let tags = serde_json::from_slice(&fs::read(self.dir.join(TAGS))?)?;
+
+        Ok(TodoList::init(Files { tasks, categories, tags }))
+    }
+
+    fn save (&mut self, todolist: &TodoList) -> io::Result<()> {
+        self.init()?;
+        fs::create_dir_all(self.dir.join(TASKS))?;
+
+        let mut old = self.read_tasks()?;
+        let mut changes = vec![];
+        let mut registries = vec![];
+
+        for task in todolist.get_tasks() {
+            match old.remove(&format!("{}.json", task.get_id())) {
+                None => changes.push(format!("Add {}", task.get_title())),
+                Some(old) if old != *task => changes.push(format!("Update {}", task.get_title())),
+                Some(_) => continue
+            }
+
+            fs::write(self.task_path(task), serde_json::to_vec_pretty(task)?)?;
+        }
+
+        for (file, task) in old {
+            changes.push(format!("Remove {}", task.get_title()));
+            fs::remove_file(self.dir.join(TASKS).join(file))?;
+        }
+
+        for (file, registry, json) in [
+            (CATEGORIES, "categories", serde_json::to_vec_pretty(todolist.get_categories())?),
+            (TAGS, "tags", serde_json::to_vec_pretty(todolist.get_tags())?)
+        ] {
+            if fs::read(self.dir.join(file)).ok().as_ref() != Some(&json) {
+                registries.push(registry);
+                fs::write(self.dir.join(file), json)?;
+            }
+        }
+
+        self.git(&["add", "--all", "--", TASKS, CATEGORIES, TAGS])?;
+
+        if self.output(&["status", "--porcelain", "--", TASKS, CATEGORIES, TAGS])?.is_empty() {
+            return Ok(());
+        }
+
+        self.git(&["commit", "--quiet", "--message", &message(&changes, &registries), "--", TASKS, CATEGORIES, TAGS])
+    }
+}
+
+/// The only change, or a summary followed by all changes. `changes` are
+/// the changes of tasks, `registries` the names of the changed registries.
+fn message (changes: &[String], registries: &[&str]) -> String {
+    let lines: Vec<String> = changes.iter().cloned()
+        .chain(registries.iter().map(|registry| format!("Update {}", registry)))
+        .collect();
+
+    if let [line] = lines.as_slice() {
+        return line.clone();
+    }
+
+    let count = |verb: &str| changes.iter().filter(|change| change.starts_with(verb)).count();
+    let mut summary: Vec<String> = [("Add", "added"), ("Update", "updated"), ("Remove", "removed")]
+        .into_iter()
+        .map(|(verb, done)| (count(verb), done))
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, done)| format!("{} {} {}", count, if count == 1 { "task" } else { "tasks" }, done))
+        .collect();
+
+    if !registries.is_empty() {
+        summary.push(format!("{} updated", registries.join(" and ")));
+    }
+
+    format!("{}\n\n{}", summary.join(", "), lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::store::tests::temp_path;
+
+    fn store (dir: &Path) -> GitStore {
+        GitStore::new(dir).author("Jane Doe", "jane@example.com")
+    }
+
+    fn git (dir: &Path, args: &[&str]) -> String {
+        GitStore::new(dir).output(args).unwrap()
+    }
+
+    #[test]
+    fn commits_every_save () {
+        let dir = temp_path("git-commits");
+        let mut store = store(&dir);
+
+        let mut todolist = TodoList::default();
+        todolist.new_category("Work").unwrap();
+        let task = todolist.new_task("Call mom").unwrap().build();
+        let id = *task.get_id();
+        todolist.add_task(task).unwrap();
+        let task = todolist.new_task("Buy milk").unwrap().build();
+        todolist.add_task(task).unwrap();
+
+        store.save(&todolist).unwrap();
+        assert!(dir.join(TASKS).join(format!("{}.json", id)).exists());
+        assert_eq!(git(&dir, &["log", "-1", "--format=%B"]), "2 tasks added, categories and tags updated\n\nAdd Call mom\nAdd Buy milk\nUpdate categories\nUpdate tags");
+        assert_eq!(git(&dir, &["log", "-1", "--format=%an"]), "Jane Doe");
+
+        // nothing changed, nothing committed
+        store.save(&todolist).unwrap();
+        assert_eq!(git(&dir, &["rev-list", "--count", "HEAD"]), "1");
+
+        todolist.get_task_mut(&id).unwrap().set_description("Sunday");
+        store.save(&todolist).unwrap();
+        assert_eq!(git(&dir, &["log", "-1", "--format=%s"]), "Update Call mom");
+
+        todolist.get_task_mut(&id).unwrap().set_description("Saturday");
+        todolist.new_tag("family").unwrap();
+        store.save(&todolist).unwrap();
+        assert_eq!(git(&dir, &["log", "-1", "--format=%s"]), "1 task updated, tags updated");
+
+        todolist.remove_task_by_id(&id).unwrap();
+        store.save(&todolist).unwrap();
+        assert_eq!(git(&dir, &["log", "-1", "--format=%s"]), "Remove Call mom");
+        assert!(!dir.join(TASKS).join(format!("{}.json", id)).exists());
+
+        let loaded = store.load().unwrap();
+        assert!(loaded.get_tasks() == todolist.get_tasks());
+        assert_eq!(loaded.get_categories(), todolist.get_categories());
+        assert_eq!(git(&dir, &["rev-list", "--count", "HEAD"]), "4");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn syncs_through_bare_remote () {
+        let remote = temp_path("git-remote.git");
+        let first = temp_path("git-first");
+        let second = temp_path("git-second");
+        fs::create_dir_all(&remote).unwrap();
+        git(&remote, &["init", "--quiet", "--bare"]);
+        let remote = remote.to_str().unwrap();
+
+        let mut ours = store(&first);
+        let mut todolist = TodoList::default();
+        let task = todolist.new_task("Call mom").unwrap().build();
+        todolist.add_task(task).unwrap();
+        ours.save(&todolist).unwrap();
+        ours.push(remote).unwrap();
+
+        let mut theirs = store(&second);
+        theirs.pull(remote).unwrap();
+        let mut todolist = theirs.load().unwrap();
+        assert_eq!(todolist.get_tasks()[0].get_title(), "Call mom");
+
+        let task = todolist.new_task("Buy milk").unwrap().build();
+        todolist.add_task(task).unwrap();
+        theirs.save(&todolist).unwrap();
+        theirs.push(remote).unwrap();
+
+        ours.pull(remote).unwrap();
+        let titles: Vec<String> = ours.load().unwrap().get_tasks().iter().map(|task| task.get_title().to_owned()).collect();
+        assert_eq!(titles, vec!["Call mom", "Buy milk"]);
+
+        // diverged, the pull is refused
+        let mut todolist = ours.load().unwrap();
+        todolist.new_tag("home").unwrap();
+        ours.save(&todolist).unwrap();
+        let mut todolist = theirs.load().unwrap();
+        todolist.new_tag("errand").unwrap();
+        theirs.save(&todolist).unwrap();
+        theirs.push(remote).unwrap();
+        assert!(ours.pull(remote).is_err());
+
+        for dir in [Path::new(remote), &first, &second] {
+            fs::remove_dir_all(dir).unwrap();
+        }
+    }
+}
@@ -3,19 +3,33 @@ mod category;
 mod tag;
 mod priority;
 mod builder;
+mod quick_add;
+mod deadline;
+mod reminder;
+mod field;
 
-pub use status::TaskStatus;
+pub use status::{TaskStatus, StatusChange};
 pub use category::Category;
 pub use tag::Tag;
 pub use priority::Priority;
 
 use uuid::Uuid;
 use chrono::prelude::*;
+use chrono::Duration;
 use std::cmp::PartialEq;
+use serde::{Serialize, Deserialize};
+
+use crate::calendar::WorkingCalendar;
+use crate::clock::{ClockHandle, IdsHandle};
 
 pub use builder::TaskBuilder;
+pub use quick_add::{QuickAdd, QuickAddToken};
+pub use deadline::{Deadline, DeadlineChange};
+pub use reminder::Reminder;
+pub use field::TaskField;
 
-#[derive(PartialEq)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "TaskFields")]
 pub struct Task {
     id: Uuid,
 
@@ -23,19 +37,95 @@ pub struct Task {
     description: String,
 
     status: TaskStatus,
+    /// Every change of the status after the task was created, oldest first.
+    status_changes: Vec<StatusChange>,
 
     category: Category,
     tags: Vec<Tag>,
 
-    // TP IMPROVE
-    // add sub tasks
+    /// The task this one is a subtask of.
+    parent: Option<Uuid>,
 
     priority: Priority,
 
     created_at: DateTime<Utc>,
     updated_at: DateTime<Utc>,
 
-    deadline: Option<DateTime<Utc>>
+    deadline: Option<Deadline>,
+    /// Every change of the deadline after the task was created, oldest first.
+    deadline_changes: Vec<DeadlineChange>,
+
+    /// When work on the task is planned to start.
+    scheduled: Option<Deadline>,
+    /// The task is deferred and left out of active views until then.
+    hidden_until: Option<DateTime<Utc>>,
+    snooze_count: u32,
+
+    reminders: Vec<Reminder>,
+
+    #[serde(skip)]
+    clock: ClockHandle
+}
+
+/// A task as serialized, checked like a new one. Fields added after the
+/// first version may be missing.
+#[derive(Deserialize)]
+struct TaskFields {
+    id: Uuid,
+    title: String,
+    description: String,
+    status: TaskStatus,
+    #[serde(default)]
+    status_changes: Vec<StatusChange>,
+    category: Category,
+    tags: Vec<Tag>,
+    #[serde(default)]
+    parent: Option<Uuid>,
+    priority: Priority,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    deadline: Option<Deadline>,
+    #[serde(default)]
+    deadline_changes: Vec<DeadlineChange>,
+    #[serde(default)]
+    scheduled: Option<Deadline>,
+    #[serde(default)]
+    hidden_until: Option<DateTime<Utc>>,
+    #[serde(default)]
+    snooze_count: u32,
+    #[serde(default)]
+    reminders: Vec<Reminder>
+}
+
+impl TryFrom<TaskFields> for Task {
+    type Error = &'static str;
+
+    fn try_from (fields: TaskFields) -> Result<Self, Self::Error> {
+        if fields.parent == Some(fields.id) {
+            return Err("The task can't be its own parent");
+        }
+
+        Ok(Self {
+            id: fields.id,
+            title: Self::prepare_title(&fields.title)?,
+            description: fields.description,
+            status: fields.status,
+            status_changes: fields.status_changes,
+            category: fields.category,
+            tags: fields.tags,
+            parent: fields.parent,
+            priority: fields.priority,
+            created_at: fields.created_at,
+            updated_at: fields.updated_at,
+            deadline: fields.deadline,
+            deadline_changes: fields.deadline_changes,
+            scheduled: fields.scheduled,
+            hidden_until: fields.hidden_until,
+            snooze_count: fields.snooze_count,
+            reminders: fields.reminders,
+            clock: ClockHandle::default()
+        })
+    }
 }
 
 impl Task {
@@ -47,6 +137,7 @@ impl Task {
         Ok(title.trim().to_owned())
     }
 
+    #[allow(clippy::new_ret_no_self)]
     pub fn new (title: &str) -> Result<TaskBuilder, &'static str> {
         Ok(TaskBuilder {
             title: Self::prepare_title(title)?,
@@ -55,10 +146,26 @@ impl Task {
             category: None,
             tags: None,
             priority: None,
-            deadline: None
+            deadline: None,
+            scheduled: None,
+            hidden_until: None,
+            reminders: None,
+            parent: None,
+            id: None,
+            created_at: None,
+            updated_at: None,
+            status_changes: None,
+            deadline_changes: None,
+            snooze_count: None,
+            clock: ClockHandle::default(),
+            ids: IdsHandle::default()
         })
     }
 
+    pub fn get_id (&self) -> &Uuid {
+        &self.id
+    }
+
     pub fn set_title (&mut self, title: &str) -> Result<(), &'static str> {
         self.title = Self::prepare_title(title)?;
         self.update_time();
@@ -81,13 +188,21 @@ impl Task {
 
     pub fn set_status (&mut self, status: TaskStatus) {
         self.update_time();
-        self.status = status;
+
+        if self.status != status {
+            self.status_changes.push(StatusChange::new(self.updated_at, self.status, status));
+            self.status = status;
+        }
     }
 
     pub fn get_status (&self) -> &TaskStatus {
         &self.status
     }
 
+    pub fn get_status_history (&self) -> &Vec<StatusChange> {
+        &self.status_changes
+    }
+
     pub fn set_category (&mut self, category: Category) {
         self.update_time();
         self.category = category;
@@ -125,6 +240,22 @@ impl Task {
         self.update_time();
     }
 
+    pub fn get_parent (&self) -> Option<&Uuid> {
+        self.parent.as_ref()
+    }
+
+    /// Makes the task a subtask of `parent`, or a top-level task with `None`.
+    pub fn set_parent (&mut self, parent: Option<Uuid>) -> Result<(), &'static str> {
+        if parent == Some(self.id) {
+            return Err("The task can't be its own parent");
+        }
+
+        self.parent = parent;
+        self.update_time();
+
+        Ok(())
+    }
+
     pub fn set_priority (&mut self, priority: Priority) {
         self.priority = priority;
         self.update_time();
@@ -135,11 +266,11 @@ impl Task {
     }
 
     pub fn up_priority (&mut self) {
-        self.priority = self.priority.clone().up();
+        self.priority = self.priority.up();
     }
 
     pub fn down_priority (&mut self) {
-        self.priority = self.priority.clone().down();
+        self.priority = self.priority.down();
     }
 
     pub fn get_created_at (&self) -> &DateTime<Utc> {
@@ -151,23 +282,264 @@ impl Task {
     }
 
     fn update_time (&mut self) {
-        self.updated_at = Utc::now();
+        self.updated_at = self.clock.now();
+    }
+
+    pub(crate) fn set_clock (&mut self, clock: ClockHandle) {
+        self.clock = clock;
+    }
+
+    /// Uses `clock` unless the task was built with its own.
+    pub(crate) fn adopt_clock (&mut self, clock: &ClockHandle) {
+        if self.clock.is_default() {
+            self.clock = clock.clone();
+        }
+    }
+
+    /// Whether the `fields` of both tasks are equal.
+    pub(crate) fn same_fields (&self, other: &Task, fields: &[TaskField]) -> bool {
+        fields.iter().all(|field| match field {
+            TaskField::Title => self.title == other.title,
+            TaskField::Description => self.description == other.description,
+            TaskField::Status => self.status == other.status,
+            TaskField::Category => self.category == other.category,
+            TaskField::Tags => self.tags == other.tags,
+            TaskField::Parent => self.parent == other.parent,
+            TaskField::Priority => self.priority == other.priority,
+            TaskField::CreatedAt => self.created_at == other.created_at,
+            TaskField::UpdatedAt => self.updated_at == other.updated_at,
+            TaskField::Deadline => self.deadline == other.deadline,
+            TaskField::Scheduled => self.scheduled == other.scheduled,
+            TaskField::HiddenUntil => self.hidden_until == other.hidden_until,
+            TaskField::Reminders => self.reminders == other.reminders
+        })
+    }
+
+    /// Takes the `fields` of `imported` as an import updating this task. A
+    /// changed status or deadline is added to the history, the update time
+    /// is now unless `UpdatedAt` is imported.
+    pub(crate) fn import_fields (&mut self, imported: &Task, fields: &[TaskField]) {
+        for field in fields {
+            match field {
+                TaskField::Title => self.title = imported.title.clone(),
+                TaskField::Description => self.description = imported.description.clone(),
+                TaskField::Status => self.set_status(imported.status),
+                TaskField::Category => self.category = imported.category.clone(),
+                TaskField::Tags => self.tags = imported.tags.clone(),
+                TaskField::Parent => self.parent = imported.parent,
+                TaskField::Priority => self.priority = imported.priority,
+                TaskField::CreatedAt => self.created_at = imported.created_at,
+                // set last, changing the others updates it
+                TaskField::UpdatedAt => {}
+                TaskField::Deadline => self.change_deadline(imported.deadline),
+                TaskField::Scheduled => self.scheduled = imported.scheduled,
+                TaskField::HiddenUntil => self.hidden_until = imported.hidden_until,
+                TaskField::Reminders => self.reminders = imported.reminders.clone()
+            }
+        }
+
+        self.updated_at = if fields.contains(&TaskField::UpdatedAt) { imported.updated_at } else { self.clock.now() };
     }
 
     pub fn has_deadline (&self) -> bool {
         self.deadline.is_some()
     }
 
-    pub fn set_deadline (&mut self, deadline: DateTime<Utc>) {
-        self.deadline = Some(deadline);
+    pub fn get_deadline (&self) -> Option<&Deadline> {
+        self.deadline.as_ref()
+    }
+
+    /// Accepts a `NaiveDate` for an all-day deadline, a `DateTime` in any
+    /// timezone for an exact one, or a `Deadline`.
+    pub fn set_deadline (&mut self, deadline: impl Into<Deadline>) {
+        self.change_deadline(Some(deadline.into()));
+    }
+
+    pub fn clear_deadline (&mut self) {
+        self.change_deadline(None);
+    }
+
+    /// Moves the deadline by `by`, see `Deadline::shift`.
+    pub fn shift_deadline (&mut self, by: Duration) -> Result<(), &'static str> {
+        let deadline = self.deadline.ok_or("The task has no deadline")?;
+        self.change_deadline(Some(deadline.shift(by)?));
+
+        Ok(())
+    }
+
+    /// Moves the deadline to the next working day of `calendar`, see `Deadline::next_working_day`.
+    pub fn postpone_to_next_working_day<Tz: TimeZone> (&mut self, calendar: &WorkingCalendar, tz: &Tz) -> Result<(), &'static str> {
+        self.postpone_by_working_days(1, calendar, tz)
+    }
+
+    /// Moves the deadline `days` working days later, see `Deadline::add_working_days`.
+    pub fn postpone_by_working_days<Tz: TimeZone> (&mut self, days: u32, calendar: &WorkingCalendar, tz: &Tz) -> Result<(), &'static str> {
+        let deadline = self.deadline.ok_or("The task has no deadline")?;
+        self.change_deadline(Some(deadline.add_working_days(days, calendar, tz)?));
+
+        Ok(())
+    }
+
+    pub fn get_deadline_changes (&self) -> &Vec<DeadlineChange> {
+        &self.deadline_changes
+    }
+
+    /// How many times the deadline was moved later.
+    pub fn get_slip_count<Tz: TimeZone> (&self, tz: &Tz) -> usize {
+        self.deadline_changes.iter().filter(|change| change.slip(tz).is_some()).count()
     }
 
+    /// How far the deadline was moved later in total, moving it earlier does
+    /// not make up for it.
+    pub fn get_total_slip<Tz: TimeZone> (&self, tz: &Tz) -> Duration {
+        self.deadline_changes.iter().filter_map(|change| change.slip(tz)).sum()
+    }
+
+    fn change_deadline (&mut self, deadline: Option<Deadline>) {
+        if self.deadline == deadline {
+            return;
+        }
+
+        self.deadline_changes.push(DeadlineChange::new(self.clock.now(), self.deadline, deadline));
+        self.deadline = deadline;
+        self.update_time();
+    }
+
+    /// Same as `is_due_today(&Utc)`.
     pub fn is_for_today (&self) -> bool {
-        if !self.has_deadline() {
+        self.is_due_today(&Utc)
+    }
+
+    /// Whether the deadline falls on the current calendar day in `tz`.
+    pub fn is_due_today<Tz: TimeZone> (&self, tz: &Tz) -> bool {
+        self.deadline_date(tz) == Some(self.today(tz))
+    }
+
+    /// Whether the deadline falls in the current ISO week (Monday to Sunday) in `tz`.
+    pub fn is_due_this_week<Tz: TimeZone> (&self, tz: &Tz) -> bool {
+        match self.deadline_date(tz) {
+            Some(date) => date.iso_week() == self.today(tz).iso_week(),
+            None => false
+        }
+    }
+
+    /// Whether the deadline has passed while the task is still open, an
+    /// all-day deadline passes at the end of its day in `tz`.
+    /// Completed and archived tasks are never overdue.
+    pub fn is_overdue<Tz: TimeZone> (&self, tz: &Tz) -> bool {
+        self.is_overdue_at(tz, self.clock.now())
+    }
+
+    /// `is_overdue` at the instant `now` instead of the task clock.
+    pub(crate) fn is_overdue_at<Tz: TimeZone> (&self, tz: &Tz, now: DateTime<Utc>) -> bool {
+        if matches!(self.status, TaskStatus::Completed | TaskStatus::Archived) {
             return false;
         }
 
-        self.deadline.unwrap().signed_duration_since(Utc::now()).num_days() == 0
+        match self.deadline {
+            Some(deadline) => deadline.due_at(tz) <= now,
+            None => false
+        }
+    }
+
+    pub fn get_scheduled (&self) -> Option<&Deadline> {
+        self.scheduled.as_ref()
+    }
+
+    /// Accepts the same values as `set_deadline`.
+    pub fn set_scheduled (&mut self, scheduled: impl Into<Deadline>) {
+        self.scheduled = Some(scheduled.into());
+        self.update_time();
+    }
+
+    pub fn clear_scheduled (&mut self) {
+        self.scheduled = None;
+        self.update_time();
+    }
+
+    pub fn get_hidden_until (&self) -> Option<&DateTime<Utc>> {
+        self.hidden_until.as_ref()
+    }
+
+    /// Hides the task from active views until `until`.
+    pub fn defer_until (&mut self, until: DateTime<Utc>) {
+        self.hidden_until = Some(until);
+        self.update_time();
+    }
+
+    /// Shows a deferred task again.
+    pub fn undefer (&mut self) {
+        self.hidden_until = None;
+        self.update_time();
+    }
+
+    /// Hides the task for `duration` from now and counts the snooze. Fails
+    /// when the end of the snooze is past the supported dates.
+    pub fn snooze (&mut self, duration: Duration) -> Result<(), &'static str> {
+        let until = self.clock.now().checked_add_signed(duration).ok_or("The snooze is out of range")?;
+
+        self.hidden_until = Some(until);
+        self.snooze_count = self.snooze_count.saturating_add(1);
+        self.update_time();
+
+        Ok(())
+    }
+
+    pub fn get_snooze_count (&self) -> u32 {
+        self.snooze_count
+    }
+
+    pub fn is_deferred (&self) -> bool {
+        self.hidden_until.is_some_and(|until| until > self.clock.now())
+    }
+
+    /// Whether the task can be worked on now: open, not deferred and its
+    /// scheduled start (evaluated in `tz` for all-day dates) has come.
+    pub fn is_available<Tz: TimeZone> (&self, tz: &Tz) -> bool {
+        self.is_available_at(tz, self.clock.now())
+    }
+
+    /// `is_available` at the instant `now` instead of the task clock.
+    pub(crate) fn is_available_at<Tz: TimeZone> (&self, tz: &Tz, now: DateTime<Utc>) -> bool {
+        matches!(self.status, TaskStatus::Created | TaskStatus::Progress)
+            && self.hidden_until.is_none_or(|until| until <= now)
+            && self.scheduled.is_none_or(|scheduled| scheduled.starts_at(tz) <= now)
+    }
+
+    /// Whether the scheduled start falls on the current calendar day in `tz`.
+    pub fn is_starting_today<Tz: TimeZone> (&self, tz: &Tz) -> bool {
+        match self.scheduled {
+            Some(scheduled) => scheduled.starts_at(tz).with_timezone(tz).date_naive() == self.today(tz),
+            None => false
+        }
+    }
+
+    pub fn get_reminders (&self) -> &Vec<Reminder> {
+        &self.reminders
+    }
+
+    pub fn add_reminder (&mut self, reminder: Reminder) -> Result<(), &str> {
+        if self.reminders.contains(&reminder) {
+            return Err("Reminder already exists");
+        }
+
+        self.reminders.push(reminder);
+        self.update_time();
+
+        Ok(())
+    }
+
+    pub fn remove_reminder (&mut self, reminder: &Reminder) {
+        self.reminders.retain(|item| item != reminder);
+        self.update_time();
+    }
+
+    fn deadline_date<Tz: TimeZone> (&self, tz: &Tz) -> Option<NaiveDate> {
+        self.deadline.map(|deadline| deadline.date_in(tz))
+    }
+
+    fn today<Tz: TimeZone> (&self, tz: &Tz) -> NaiveDate {
+        self.clock.now().with_timezone(tz).date_naive()
     }
 }
 
@@ -179,9 +551,12 @@ impl std::fmt::Display for Task {
 
 #[cfg(test)]
 mod tests {
-    use chrono::{Days, Duration};
+    use std::sync::Arc;
+
+    use chrono::{Days, FixedOffset};
 
     use super::*;
+    use crate::clock::{FixedClock, SequentialIds};
 
     #[test]
     fn create() {
@@ -248,7 +623,7 @@ mod tests {
     #[test]
     fn add_tag () {
         let mut task = Task::new("First").unwrap()
-                                .tags(&vec![Tag::new("Rust").unwrap()])
+                                .tags(&[Tag::new("Rust").unwrap()])
                                 .build();
 
         task.add_tag(Tag::new("code").unwrap()).unwrap();
@@ -259,7 +634,7 @@ mod tests {
     #[test]
     fn remove_tag () {
         let mut task = Task::new("First").unwrap()
-                                .tags(&vec![
+                                .tags(&[
                                     Tag::new("code").unwrap(), 
                                     Tag::new("Rust").unwrap()]
                                 )
@@ -299,9 +674,23 @@ mod tests {
 
     #[test]
     fn update_time () {
-        let mut task = Task::new("First").unwrap().build();
+        let clock = Arc::new(FixedClock::new(Utc.with_ymd_and_hms(2024, 5, 6, 10, 0, 0).unwrap()));
+        let mut task = Task::new("First").unwrap().clock(clock.clone()).build();
+        assert_eq!(task.get_updated_at(), task.get_created_at());
+
+        clock.advance(Duration::minutes(1));
         task.set_priority(Priority::Red);
-        assert_ne!(task.get_updated_at(), task.get_created_at());
+        assert_eq!(*task.get_updated_at(), *task.get_created_at() + Duration::minutes(1));
+    }
+
+    #[test]
+    fn injected_ids () {
+        let ids = Arc::new(SequentialIds::new());
+        let first = Task::new("First").unwrap().id_generator(ids.clone()).build();
+        let second = Task::new("Second").unwrap().id_generator(ids).build();
+
+        assert_eq!(first.get_id(), &Uuid::from_u128(1));
+        assert_eq!(second.get_id(), &Uuid::from_u128(2));
     }
 
     #[test]
@@ -319,11 +708,21 @@ mod tests {
         assert!(task.has_deadline());
     }
 
+    fn at (day: u32, hour: u32) -> DateTime<Utc> {
+        // 2024-05-06 is a Monday
+        Utc.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap()
+    }
+
+    fn task_due (now: DateTime<Utc>, deadline: DateTime<Utc>) -> Task {
+        Task::new("First").unwrap()
+            .clock(Arc::new(FixedClock::new(now)))
+            .deadline(deadline)
+            .build()
+    }
+
     #[test]
     fn deadline_set_for_today () {
-        let task = Task::new("First").unwrap()
-                                .deadline(Utc::now().checked_add_signed(Duration::hours(2)).unwrap())
-                                .build();
+        let task = task_due(at(6, 10), at(6, 12));
 
         assert!(task.is_for_today());
     }
@@ -336,4 +735,192 @@ mod tests {
 
         assert!(! task.is_for_today());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn deadline_within_a_day_but_yesterday () {
+        let task = task_due(at(6, 10), at(5, 23));
+
+        assert!(!task.is_due_today(&Utc));
+        assert!(task.is_overdue(&Utc));
+    }
+
+    #[test]
+    fn due_today_depends_on_timezone () {
+        // 22:00 UTC on the 6th is already the 7th in UTC+3
+        let task = task_due(at(6, 10), at(6, 22));
+        let east = FixedOffset::east_opt(3 * 3600).unwrap();
+        let west = FixedOffset::west_opt(5 * 3600).unwrap();
+
+        assert!(task.is_due_today(&Utc));
+        assert!(!task.is_due_today(&east));
+        assert!(task.is_due_today(&west));
+    }
+
+    #[test]
+    fn due_this_week () {
+        let east = FixedOffset::east_opt(3 * 3600).unwrap();
+
+        assert!(task_due(at(6, 10), at(12, 20)).is_due_this_week(&Utc));
+        assert!(!task_due(at(6, 10), at(12, 22)).is_due_this_week(&east));
+        assert!(!task_due(at(6, 10), at(5, 20)).is_due_this_week(&Utc));
+        assert!(!Task::new("First").unwrap().build().is_due_this_week(&Utc));
+    }
+
+    #[test]
+    fn overdue () {
+        let mut task = task_due(at(6, 10), at(6, 9));
+        assert!(task.is_overdue(&Utc));
+
+        task.set_status(TaskStatus::Completed);
+        assert!(!task.is_overdue(&Utc));
+
+        assert!(!task_due(at(6, 10), at(6, 11)).is_overdue(&Utc));
+    }
+
+    #[test]
+    fn snooze () {
+        let clock = Arc::new(FixedClock::new(at(6, 10)));
+        let mut task = Task::new("First").unwrap().clock(clock.clone()).build();
+        assert!(!task.is_deferred());
+
+        task.snooze(Duration::hours(2)).unwrap();
+        task.snooze(Duration::hours(3)).unwrap();
+        assert_eq!(task.get_hidden_until(), Some(&at(6, 13)));
+        assert_eq!(task.get_snooze_count(), 2);
+        assert!(task.is_deferred());
+
+        assert!(task.snooze(Duration::MAX).is_err());
+        assert_eq!(task.get_hidden_until(), Some(&at(6, 13)));
+        assert_eq!(task.get_snooze_count(), 2);
+        assert!(!task.is_available(&Utc));
+
+        clock.set(at(6, 13));
+        assert!(!task.is_deferred());
+        assert!(task.is_available(&Utc));
+    }
+
+    #[test]
+    fn scheduled_start () {
+        let clock = Arc::new(FixedClock::new(at(6, 10)));
+        let mut task = Task::new("First").unwrap()
+            .clock(clock.clone())
+            .scheduled(NaiveDate::from_ymd_opt(2024, 5, 7).unwrap())
+            .build();
+
+        assert!(!task.is_available(&Utc));
+        assert!(!task.is_starting_today(&Utc));
+
+        clock.set(at(7, 0));
+        assert!(task.is_available(&Utc));
+        assert!(task.is_starting_today(&Utc));
+
+        task.set_status(TaskStatus::Completed);
+        assert!(!task.is_available(&Utc));
+    }
+
+    #[test]
+    fn all_day_deadline () {
+        let today = NaiveDate::from_ymd_opt(2024, 5, 6).unwrap();
+        let mut task = task_due(at(6, 22), at(6, 22));
+        task.set_deadline(today);
+
+        let east = FixedOffset::east_opt(3 * 3600).unwrap();
+
+        assert!(task.get_deadline().unwrap().is_all_day());
+        assert!(task.is_due_today(&Utc));
+        assert!(!task.is_overdue(&Utc));
+
+        // it is already the 7th in UTC+3, the day has passed there
+        assert!(!task.is_due_today(&east));
+        assert!(task.is_overdue(&east));
+    }
+
+    #[test]
+    fn restore_from_parts () {
+        let clock = Arc::new(FixedClock::new(at(6, 9)));
+        let mut task = Task::new("First").unwrap().clock(clock.clone()).deadline(at(8, 12)).build();
+
+        clock.set(at(6, 10));
+        task.set_status(TaskStatus::Progress);
+        task.set_status(TaskStatus::Progress);
+        task.shift_deadline(Duration::days(1)).unwrap();
+        task.snooze(Duration::hours(1)).unwrap();
+
+        clock.set(at(7, 10));
+        task.set_status(TaskStatus::Completed);
+
+        let history = task.get_status_history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0], StatusChange::new(at(6, 10), TaskStatus::Created, TaskStatus::Progress));
+        assert_eq!(history[1].get_changed_at(), &at(7, 10));
+        assert_eq!(history[1].get_to(), &TaskStatus::Completed);
+
+        clock.set(at(8, 10));
+        let restored = Task::new(task.get_title()).unwrap()
+            .clock(clock.clone())
+            .id(*task.get_id())
+            .status(*task.get_status())
+            .status_history(task.get_status_history())
+            .deadline(*task.get_deadline().unwrap())
+            .deadline_changes(task.get_deadline_changes())
+            .hidden_until(*task.get_hidden_until().unwrap())
+            .snooze_count(task.get_snooze_count())
+            .created_at(*task.get_created_at())
+            .updated_at(*task.get_updated_at())
+            .build();
+
+        assert!(restored == task);
+        assert!(Task::new(" ").is_err());
+    }
+
+    #[test]
+    fn deserialize_checks_like_new () {
+        let task = Task::new("First").unwrap().reminders(&[Reminder::parse("1h").unwrap()]).build();
+        let json = serde_json::to_string(&task).unwrap();
+
+        assert!(serde_json::from_str::<Task>(&json).unwrap() == task);
+        assert!(serde_json::from_str::<Task>(&json.replace(r#""title":"First""#, r#""title":"  ""#)).is_err());
+
+        let id = format!(r#""parent":"{}""#, task.get_id());
+        assert!(serde_json::from_str::<Task>(&json.replace(r#""parent":null"#, &id)).is_err());
+    }
+
+    #[test]
+    fn deadline_lifecycle () {
+        let clock = Arc::new(FixedClock::new(at(6, 9)));
+        let mut task = Task::new("First").unwrap().clock(clock.clone()).build();
+
+        assert!(task.shift_deadline(Duration::days(1)).is_err());
+
+        clock.set(at(6, 10));
+        task.set_deadline(at(8, 12));
+        assert_eq!(task.get_updated_at(), &at(6, 10));
+
+        clock.set(at(7, 10));
+        task.shift_deadline(Duration::days(1)).unwrap();
+        assert_eq!(task.get_deadline(), Some(&at(9, 12).into()));
+        assert_eq!(task.get_updated_at(), &at(7, 10));
+
+        // Thursday to Friday, then earlier again
+        task.postpone_to_next_working_day(&WorkingCalendar::new(), &Utc).unwrap();
+        task.shift_deadline(Duration::hours(-2)).unwrap();
+        assert_eq!(task.get_deadline(), Some(&at(10, 10).into()));
+
+        task.set_deadline(at(10, 10));
+        assert_eq!(task.get_deadline_changes().len(), 4);
+
+        clock.set(at(8, 10));
+        task.clear_deadline();
+        assert!(!task.has_deadline());
+        assert_eq!(task.get_updated_at(), &at(8, 10));
+
+        let changes = task.get_deadline_changes();
+        assert_eq!(changes.len(), 5);
+        assert_eq!(changes[0].get_from(), None);
+        assert_eq!(changes[4].get_to(), None);
+        assert_eq!(changes[4].get_changed_at(), &at(8, 10));
+
+        assert_eq!(task.get_slip_count(&Utc), 2);
+        assert_eq!(task.get_total_slip(&Utc), Duration::days(2));
+    }
+}
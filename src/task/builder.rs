@@ -1,4 +1,9 @@
-use super::{TaskStatus, Category, Priority, Tag, Task, DateTime, Utc, Uuid};
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use super::{TaskStatus, StatusChange, Category, Priority, Tag, Task, Deadline, DeadlineChange, Reminder, DateTime, Utc};
+use crate::clock::{Clock, ClockHandle, IdGenerator, IdsHandle};
 
 pub struct TaskBuilder {
     pub(super) title: String,
@@ -11,7 +16,21 @@ pub struct TaskBuilder {
 
     pub(super) priority: Option<Priority>,
 
-    pub(super) deadline: Option<DateTime<Utc>>
+    pub(super) deadline: Option<Deadline>,
+    pub(super) scheduled: Option<Deadline>,
+    pub(super) hidden_until: Option<DateTime<Utc>>,
+    pub(super) reminders: Option<Vec<Reminder>>,
+    pub(super) parent: Option<Uuid>,
+
+    pub(super) id: Option<Uuid>,
+    pub(super) created_at: Option<DateTime<Utc>>,
+    pub(super) updated_at: Option<DateTime<Utc>>,
+    pub(super) status_changes: Option<Vec<StatusChange>>,
+    pub(super) deadline_changes: Option<Vec<DeadlineChange>>,
+    pub(super) snooze_count: Option<u32>,
+
+    pub(super) clock: ClockHandle,
+    pub(super) ids: IdsHandle
 }
 
 impl TaskBuilder {
@@ -40,23 +59,109 @@ impl TaskBuilder {
         self
     }
 
-    pub fn deadline(mut self, deadline: DateTime<Utc>) -> Self {
-        self.deadline = Some(deadline);
+    /// Accepts a `NaiveDate` for an all-day deadline, a `DateTime` in any
+    /// timezone for an exact one, or a `Deadline`.
+    pub fn deadline(mut self, deadline: impl Into<Deadline>) -> Self {
+        self.deadline = Some(deadline.into());
+        self
+    }
+
+    /// When work on the task is planned to start, accepts the same values as `deadline`.
+    pub fn scheduled(mut self, scheduled: impl Into<Deadline>) -> Self {
+        self.scheduled = Some(scheduled.into());
+        self
+    }
+
+    /// Creates the task deferred until `until`.
+    pub fn hidden_until(mut self, until: DateTime<Utc>) -> Self {
+        self.hidden_until = Some(until);
+        self
+    }
+
+    pub fn reminders(mut self, reminders: &[Reminder]) -> Self {
+        self.reminders = Some(reminders.to_vec());
+        self
+    }
+
+    /// Makes the task a subtask of `parent`. Dropped when it is the id of
+    /// the task, which can't be its own parent.
+    pub fn parent(mut self, parent: Uuid) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    /// Clock used for the timestamps of the task, now and on later updates.
+    /// A list keeps it when the task is added, a task built without one
+    /// takes the clock of the list.
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = ClockHandle(clock);
+        self
+    }
+
+    pub fn id_generator(mut self, ids: Arc<dyn IdGenerator>) -> Self {
+        self.ids = IdsHandle(ids);
+        self
+    }
+
+    /// Keeps the id of a stored or imported task instead of generating one.
+    pub fn id(mut self, id: Uuid) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Keeps the creation time of a stored task instead of taking now.
+    pub fn created_at(mut self, created_at: DateTime<Utc>) -> Self {
+        self.created_at = Some(created_at);
+        self
+    }
+
+    /// Keeps the time of the last update, the creation time when not given.
+    pub fn updated_at(mut self, updated_at: DateTime<Utc>) -> Self {
+        self.updated_at = Some(updated_at);
+        self
+    }
+
+    /// Restores the status changes of a stored task, oldest first.
+    pub fn status_history(mut self, changes: &[StatusChange]) -> Self {
+        self.status_changes = Some(changes.to_vec());
+        self
+    }
+
+    /// Restores the deadline changes of a stored task, oldest first.
+    pub fn deadline_changes(mut self, changes: &[DeadlineChange]) -> Self {
+        self.deadline_changes = Some(changes.to_vec());
+        self
+    }
+
+    pub fn snooze_count(mut self, count: u32) -> Self {
+        self.snooze_count = Some(count);
         self
     }
 
     pub fn build (self) -> Task {
+        let now = self.clock.now();
+        let created_at = self.created_at.unwrap_or(now);
+        let id = self.id.unwrap_or_else(|| self.ids.next_id());
+
         Task {
-            id: Uuid::new_v4(),
+            id,
             title: self.title,
             description: self.description.unwrap_or_default(),
             status: self.status.unwrap_or_default(),
+            status_changes: self.status_changes.unwrap_or_default(),
             category: self.category.unwrap_or_default(),
             tags: self.tags.unwrap_or_default(),
             priority: self.priority.unwrap_or_default(),
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-            deadline: self.deadline
+            created_at,
+            updated_at: self.updated_at.unwrap_or(created_at),
+            deadline: self.deadline,
+            deadline_changes: self.deadline_changes.unwrap_or_default(),
+            scheduled: self.scheduled,
+            hidden_until: self.hidden_until,
+            snooze_count: self.snooze_count.unwrap_or_default(),
+            reminders: self.reminders.unwrap_or_default(),
+            parent: self.parent.filter(|parent| *parent != id),
+            clock: self.clock
         }
     }
 }
\ No newline at end of file
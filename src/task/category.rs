@@ -1,8 +1,10 @@
 use uuid::Uuid;
 use std::cmp::{PartialEq, Eq};
 use std::sync::OnceLock;
+use serde::{Serialize, Deserialize};
 
-#[derive(Clone, Eq, Debug)]
+#[derive(Clone, Eq, Debug, Serialize, Deserialize)]
+#[serde(try_from = "CategoryFields")]
 pub struct Category {
     // TO IMPROVE
     // add sub categoryes
@@ -42,14 +44,25 @@ impl Category {
     }
 }
 
+/// A category as serialized, checked like a new one.
+#[derive(Deserialize)]
+struct CategoryFields {
+    id: Uuid,
+    title: String
+}
+
+impl TryFrom<CategoryFields> for Category {
+    type Error = &'static str;
+
+    fn try_from (fields: CategoryFields) -> Result<Self, Self::Error> {
+        Ok(Self { id: fields.id, title: Self::prepare_title(&fields.title)? })
+    }
+}
+
 impl PartialEq for Category {
     fn eq(&self, other: &Self) -> bool {
         self.title == other.title
     }
-
-    fn ne(&self, other: &Self) -> bool {
-        self.title != other.title
-    }
 }
 
 impl std::fmt::Display for Category {
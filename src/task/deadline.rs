@@ -0,0 +1,315 @@
+use chrono::{DateTime, Days, Duration, NaiveDate, TimeZone, Utc};
+use serde::{Serialize, Deserialize};
+use std::cmp::Ordering;
+
+use crate::calendar::WorkingCalendar;
+
+/// When a task is due.
+///
+/// Serialized untagged: a date as `"2024-05-10"`, an exact time as
+/// `"2024-05-10T17:00:00Z"` and a window as `{"start": ..., "end": ...}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged, try_from = "DeadlineFields")]
+pub enum Deadline {
+    /// Due some time during a calendar day, in whatever timezone the user is.
+    Date(NaiveDate),
+    /// Due at an exact instant.
+    DateTime(DateTime<Utc>),
+    /// Due within a time window.
+    Window { start: DateTime<Utc>, end: DateTime<Utc> }
+}
+
+/// A deadline as serialized, a window is checked like a new one.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DeadlineFields {
+    Date(NaiveDate),
+    DateTime(DateTime<Utc>),
+    Window { start: DateTime<Utc>, end: DateTime<Utc> }
+}
+
+impl TryFrom<DeadlineFields> for Deadline {
+    type Error = &'static str;
+
+    fn try_from (fields: DeadlineFields) -> Result<Self, Self::Error> {
+        match fields {
+            DeadlineFields::Date(date) => Ok(Self::Date(date)),
+            DeadlineFields::DateTime(at) => Ok(Self::DateTime(at)),
+            DeadlineFields::Window { start, end } => Self::window(start, end)
+        }
+    }
+}
+
+impl Deadline {
+    pub fn window (start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Self, &'static str> {
+        if end < start {
+            return Err("The window ends before it starts");
+        }
+
+        Ok(Self::Window { start, end })
+    }
+
+    pub fn is_all_day (&self) -> bool {
+        matches!(self, Self::Date(_))
+    }
+
+    /// The calendar day in `tz` the task is due on, the end of a window counts.
+    pub fn date_in<Tz: TimeZone> (&self, tz: &Tz) -> NaiveDate {
+        match self {
+            Self::Date(date) => *date,
+            Self::DateTime(at) | Self::Window { end: at, .. } => at.with_timezone(tz).date_naive()
+        }
+    }
+
+    /// The instant the deadline opens: the start of the day in `tz`, the
+    /// exact time or the start of the window.
+    pub fn starts_at<Tz: TimeZone> (&self, tz: &Tz) -> DateTime<Utc> {
+        match self {
+            Self::Date(date) => start_of_day(tz, *date),
+            Self::DateTime(at) | Self::Window { start: at, .. } => *at
+        }
+    }
+
+    /// The instant after which the task is late: the end of the day in `tz`
+    /// (exclusive), the exact time or the end of the window.
+    pub fn due_at<Tz: TimeZone> (&self, tz: &Tz) -> DateTime<Utc> {
+        match self {
+            Self::Date(date) => start_of_day(tz, *date + Days::new(1)),
+            Self::DateTime(at) | Self::Window { end: at, .. } => *at
+        }
+    }
+
+    /// Orders by due instant in `tz`, a timed deadline comes before an all-day
+    /// one ending at the same instant.
+    pub fn cmp_in<Tz: TimeZone> (&self, other: &Self, tz: &Tz) -> Ordering {
+        self.due_at(tz).cmp(&other.due_at(tz))
+            .then_with(|| self.is_all_day().cmp(&other.is_all_day()))
+            .then_with(|| self.starts_at(tz).cmp(&other.starts_at(tz)))
+    }
+
+    /// Moves the deadline later (or earlier for a negative `by`). An all-day
+    /// deadline moves by the whole days in `by`, a window keeps its length.
+    /// Fails when the deadline would move past the supported dates.
+    pub fn shift (&self, by: Duration) -> Result<Self, &'static str> {
+        let shift = |at: &DateTime<Utc>| at.checked_add_signed(by).ok_or("The deadline is out of range");
+
+        Ok(match self {
+            Self::Date(date) => Self::Date(date.checked_add_signed(Duration::days(by.num_days())).ok_or("The deadline is out of range")?),
+            Self::DateTime(at) => Self::DateTime(shift(at)?),
+            Self::Window { start, end } => Self::Window { start: shift(start)?, end: shift(end)? }
+        })
+    }
+
+    /// Moves the deadline to the first working day of `calendar` after its
+    /// current day in `tz`, keeping the local time of day.
+    pub fn next_working_day<Tz: TimeZone> (&self, calendar: &WorkingCalendar, tz: &Tz) -> Result<Self, &'static str> {
+        self.add_working_days(1, calendar, tz)
+    }
+
+    /// Moves the deadline `days` working days of `calendar` later, counted
+    /// from its current day in `tz` and keeping the local time of day.
+    pub fn add_working_days<Tz: TimeZone> (&self, days: u32, calendar: &WorkingCalendar, tz: &Tz) -> Result<Self, &'static str> {
+        let date = self.date_in(tz);
+        let next = calendar.add_working_days(date, days).ok_or("The deadline is out of range")?;
+
+        self.shift_days_in(tz, (next - date).num_days() as u64)
+    }
+
+    /// Moves the deadline by whole calendar days in `tz`, keeping the local
+    /// time of day across DST changes.
+    pub(crate) fn shift_days_in<Tz: TimeZone> (&self, tz: &Tz, days: u64) -> Result<Self, &'static str> {
+        let shift = |at: &DateTime<Utc>| at.with_timezone(tz)
+            .checked_add_days(Days::new(days))
+            .map(|at| at.with_timezone(&Utc))
+            .or_else(|| at.checked_add_days(Days::new(days)))
+            .ok_or("The deadline is out of range");
+
+        Ok(match self {
+            Self::Date(date) => Self::Date(date.checked_add_days(Days::new(days)).ok_or("The deadline is out of range")?),
+            Self::DateTime(at) => Self::DateTime(shift(at)?),
+            Self::Window { start, end } => Self::Window { start: shift(start)?, end: shift(end)? }
+        })
+    }
+}
+
+/// A change of a task's deadline, from `None` when one was set and to
+/// `None` when it was cleared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadlineChange {
+    changed_at: DateTime<Utc>,
+    from: Option<Deadline>,
+    to: Option<Deadline>
+}
+
+impl DeadlineChange {
+    pub fn new (changed_at: DateTime<Utc>, from: Option<Deadline>, to: Option<Deadline>) -> Self {
+        Self { changed_at, from, to }
+    }
+
+    pub fn get_changed_at (&self) -> &DateTime<Utc> {
+        &self.changed_at
+    }
+
+    pub fn get_from (&self) -> Option<&Deadline> {
+        self.from.as_ref()
+    }
+
+    pub fn get_to (&self) -> Option<&Deadline> {
+        self.to.as_ref()
+    }
+
+    /// How much later the task became due, all-day deadlines evaluated in
+    /// `tz`. `None` unless the deadline was moved later.
+    pub fn slip<Tz: TimeZone> (&self, tz: &Tz) -> Option<Duration> {
+        let (from, to) = (self.from?, self.to?);
+        let slip = to.due_at(tz) - from.due_at(tz);
+
+        (slip > Duration::zero()).then_some(slip)
+    }
+}
+
+/// The first instant of `date` in `tz`, skipping forward over a DST gap.
+pub(crate) fn start_of_day<Tz: TimeZone> (tz: &Tz, date: NaiveDate) -> DateTime<Utc> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+
+    (0..24)
+        .find_map(|hour| tz.from_local_datetime(&(midnight + chrono::Duration::hours(hour))).earliest())
+        .map(|at| at.with_timezone(&Utc))
+        .unwrap_or_else(|| Utc.from_utc_datetime(&midnight))
+}
+
+impl From<NaiveDate> for Deadline {
+    fn from(date: NaiveDate) -> Self {
+        Self::Date(date)
+    }
+}
+
+impl<Tz: TimeZone> From<DateTime<Tz>> for Deadline {
+    fn from(at: DateTime<Tz>) -> Self {
+        Self::DateTime(at.with_timezone(&Utc))
+    }
+}
+
+impl std::fmt::Display for Deadline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Date(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+            Self::DateTime(at) => write!(f, "{}", at.format("%Y-%m-%d %H:%M")),
+            Self::Window { start, end } => write!(f, "{} - {}", start.format("%Y-%m-%d %H:%M"), end.format("%Y-%m-%d %H:%M"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::FixedOffset;
+
+    use super::*;
+
+    fn date (day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, day).unwrap()
+    }
+
+    fn at (day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn all_day_deadline_follows_timezone () {
+        let deadline = Deadline::from(date(10));
+        let east = FixedOffset::east_opt(3 * 3600).unwrap();
+
+        assert!(deadline.is_all_day());
+        assert_eq!(deadline.date_in(&east), date(10));
+        assert_eq!(deadline.starts_at(&east), at(9, 21));
+        assert_eq!(deadline.due_at(&east), at(10, 21));
+        assert_eq!(deadline.due_at(&Utc), at(11, 0));
+    }
+
+    #[test]
+    fn timed_deadline_date_depends_on_timezone () {
+        let deadline = Deadline::from(at(10, 22));
+        let east = FixedOffset::east_opt(3 * 3600).unwrap();
+
+        assert_eq!(deadline.date_in(&Utc), date(10));
+        assert_eq!(deadline.date_in(&east), date(11));
+        assert_eq!(Deadline::from(at(10, 22).with_timezone(&east)), deadline);
+    }
+
+    #[test]
+    fn window () {
+        assert!(Deadline::window(at(10, 12), at(10, 9)).is_err());
+        assert!(serde_json::from_str::<Deadline>(r#"{"start": "2024-05-10T12:00:00Z", "end": "2024-05-10T09:00:00Z"}"#).is_err());
+
+        let window = Deadline::window(at(10, 9), at(10, 12)).unwrap();
+        assert_eq!(window.starts_at(&Utc), at(10, 9));
+        assert_eq!(window.due_at(&Utc), at(10, 12));
+    }
+
+    #[test]
+    fn ordering () {
+        let mut deadlines = vec![
+            Deadline::from(date(11)),
+            Deadline::from(at(11, 0)),
+            Deadline::from(at(10, 12)),
+            Deadline::from(date(10))
+        ];
+
+        deadlines.sort_by(|a, b| a.cmp_in(b, &Utc));
+
+        assert_eq!(deadlines, vec![
+            Deadline::from(at(10, 12)),
+            Deadline::from(at(11, 0)),
+            Deadline::from(date(10)),
+            Deadline::from(date(11))
+        ]);
+    }
+
+    #[test]
+    fn shift () {
+        let window = Deadline::window(at(10, 9), at(10, 12)).unwrap();
+
+        assert_eq!(Deadline::from(date(10)).shift(Duration::hours(30)), Ok(Deadline::from(date(11))));
+        assert_eq!(Deadline::from(date(10)).shift(Duration::days(-2)), Ok(Deadline::from(date(8))));
+        assert_eq!(Deadline::from(at(10, 9)).shift(Duration::hours(30)), Ok(Deadline::from(at(11, 15))));
+        assert_eq!(window.shift(Duration::days(1)), Deadline::window(at(11, 9), at(11, 12)));
+        assert!(window.shift(Duration::MAX).is_err());
+        assert!(Deadline::from(date(10)).shift(Duration::MIN).is_err());
+    }
+
+    #[test]
+    fn next_working_day () {
+        let calendar = WorkingCalendar::new();
+        let east = FixedOffset::east_opt(3 * 3600).unwrap();
+
+        // Friday the 10th
+        assert_eq!(Deadline::from(date(10)).next_working_day(&calendar, &Utc).unwrap(), Deadline::from(date(13)));
+        assert_eq!(Deadline::from(date(8)).next_working_day(&calendar, &Utc).unwrap(), Deadline::from(date(9)));
+        assert_eq!(Deadline::from(at(10, 17)).next_working_day(&calendar, &Utc).unwrap(), Deadline::from(at(13, 17)));
+        // Thursday 22:00 in UTC is already Friday in UTC+3
+        assert_eq!(Deadline::from(at(9, 22)).next_working_day(&calendar, &east).unwrap(), Deadline::from(at(12, 22)));
+    }
+
+    #[test]
+    fn add_working_days () {
+        let calendar = WorkingCalendar::new().holiday(date(14));
+
+        assert_eq!(Deadline::from(date(10)).add_working_days(2, &calendar, &Utc).unwrap(), Deadline::from(date(15)));
+        assert_eq!(Deadline::window(at(10, 9), at(10, 12)).unwrap().add_working_days(1, &calendar, &Utc).unwrap(),
+            Deadline::window(at(13, 9), at(13, 12)).unwrap());
+    }
+
+    #[test]
+    fn serialization () {
+        let window = Deadline::window(at(10, 9), at(10, 12)).unwrap();
+
+        for (deadline, json) in [
+            (Deadline::from(date(10)), r#""2024-05-10""#),
+            (Deadline::from(at(10, 9)), r#""2024-05-10T09:00:00Z""#),
+            (window, r#"{"start":"2024-05-10T09:00:00Z","end":"2024-05-10T12:00:00Z"}"#)
+        ] {
+            assert_eq!(serde_json::to_string(&deadline).unwrap(), json);
+            assert_eq!(serde_json::from_str::<Deadline>(json).unwrap(), deadline);
+        }
+    }
+}
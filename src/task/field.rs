@@ -0,0 +1,19 @@
+use std::cmp::{PartialEq, Eq};
+
+/// A field of a task an import can set, see `ImportPreview`.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum TaskField {
+    Title,
+    Description,
+    Status,
+    Category,
+    Tags,
+    Parent,
+    Priority,
+    CreatedAt,
+    UpdatedAt,
+    Deadline,
+    Scheduled,
+    HiddenUntil,
+    Reminders
+}
@@ -1,7 +1,9 @@
 use std::cmp::{PartialEq, PartialOrd};
+use serde::{Serialize, Deserialize};
 
-#[derive(PartialEq, PartialOrd, Debug, Clone, Copy)]
+#[derive(PartialEq, PartialOrd, Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub enum Priority {
+    #[default]
     Common,
     Green,
     Yellow,
@@ -27,12 +29,6 @@ impl Priority {
     }
 }
 
-impl Default for Priority {
-    fn default() -> Self {
-        Priority::Common
-    }
-}
-
 impl std::fmt::Display for Priority {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", match self {
@@ -44,6 +40,21 @@ impl std::fmt::Display for Priority {
     }
 }
 
+/// Parses the names written by `Display`, in any case.
+impl std::str::FromStr for Priority {
+    type Err = &'static str;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "common" => Ok(Self::Common),
+            "green" => Ok(Self::Green),
+            "yellow" => Ok(Self::Yellow),
+            "red" => Ok(Self::Red),
+            _ => Err("Unknown priority")
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -108,4 +119,11 @@ mod test {
         let priority = Priority::Red;
         assert_eq!(priority.down(), Priority::Yellow);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn parse () {
+        assert_eq!("RED".parse::<Priority>(), Ok(Priority::Red));
+        assert_eq!(" green ".parse::<Priority>(), Ok(Priority::Green));
+        assert!("urgent".parse::<Priority>().is_err());
+    }
+}
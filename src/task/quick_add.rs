@@ -0,0 +1,457 @@
+use chrono::{DateTime, Datelike, Days, Duration, NaiveDate, NaiveTime, Offset, TimeZone, Utc, Weekday};
+
+use super::{Category, Deadline, Priority, Tag, Task, TaskBuilder};
+use crate::calendar::WorkingCalendar;
+
+/// A part of a quick-add line that was turned into a task field,
+/// holding the source text it was read from.
+#[derive(Debug, PartialEq, Clone)]
+pub enum QuickAddToken {
+    Tag(String),
+    Category(String),
+    Priority(String),
+    Deadline(String)
+}
+
+/// Result of `Task::quick_add`.
+pub struct QuickAdd {
+    builder: TaskBuilder,
+    tokens: Vec<QuickAddToken>
+}
+
+impl QuickAdd {
+    pub fn tokens (&self) -> &[QuickAddToken] {
+        &self.tokens
+    }
+
+    pub fn builder (self) -> TaskBuilder {
+        self.builder
+    }
+
+    pub fn build (self) -> Task {
+        self.builder.build()
+    }
+
+    pub(crate) fn map_builder (self, map: impl FnOnce(TaskBuilder) -> TaskBuilder) -> Self {
+        Self { builder: map(self.builder), tokens: self.tokens }
+    }
+}
+
+enum DateWord {
+    Date(NaiveDate),
+    Time(NaiveTime),
+    DateTime(NaiveDate, NaiveTime),
+    Instant(DateTime<Utc>)
+}
+
+fn end_of_day () -> NaiveTime {
+    NaiveTime::from_hms_opt(23, 59, 59).unwrap()
+}
+
+/// A weekday by its full name, or also by its short one when `short`, which
+/// alone are common words like "sun" or "wed".
+fn parse_weekday (word: &str, short: bool) -> Option<Weekday> {
+    let weekday = match word {
+        "mon" | "monday" => Weekday::Mon,
+        "tue" | "tuesday" => Weekday::Tue,
+        "wed" | "wednesday" => Weekday::Wed,
+        "thu" | "thursday" => Weekday::Thu,
+        "fri" | "friday" => Weekday::Fri,
+        "sat" | "saturday" => Weekday::Sat,
+        "sun" | "sunday" => Weekday::Sun,
+        _ => return None
+    };
+
+    (short || word.len() > 3).then_some(weekday)
+}
+
+/// The first `weekday` strictly after `today`.
+fn upcoming (today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    today + Days::new(if ahead == 0 { 7 } else { ahead as u64 })
+}
+
+fn parse_time (word: &str) -> Option<NaiveTime> {
+    if let Ok(time) = NaiveTime::parse_from_str(word, "%H:%M") {
+        return Some(time);
+    }
+
+    let (hour, offset) = if let Some(hour) = word.strip_suffix("am") {
+        (hour, 0)
+    } else if let Some(hour) = word.strip_suffix("pm") {
+        (hour, 12)
+    } else {
+        return None;
+    };
+
+    match hour.parse::<u32>() {
+        Ok(hour) if (1..=12).contains(&hour) => NaiveTime::from_hms_opt(hour % 12 + offset, 0, 0),
+        _ => None
+    }
+}
+
+/// Reads a date or time phrase at the start of `words`, returning it with
+/// the number of words it spans.
+fn parse_date_word<Tz: TimeZone> (words: &[String], now: &DateTime<Tz>, calendar: &WorkingCalendar) -> Option<(DateWord, usize)> {
+    let today = now.date_naive();
+    let word = words[0].as_str();
+
+    match word {
+        "today" => return Some((DateWord::Date(today), 1)),
+        "tomorrow" => return Some((DateWord::Date(today + Days::new(1)), 1)),
+        "eod" => return Some((DateWord::DateTime(today, end_of_day()), 1)),
+        _ => {}
+    }
+
+    if let Some(weekday) = parse_weekday(word, false) {
+        return Some((DateWord::Date(upcoming(today, weekday)), 1));
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+        return Some((DateWord::Date(date), 1));
+    }
+
+    if let Some(time) = parse_time(word) {
+        return Some((DateWord::Time(time), 1));
+    }
+
+    let next = words.get(1).map(String::as_str)?;
+
+    if word == "at" {
+        return parse_time(next).map(|time| (DateWord::Time(time), 2));
+    }
+
+    if word == "next" {
+        if next == "week" {
+            return Some((DateWord::Date(upcoming(today, Weekday::Mon)), 2));
+        }
+
+        return parse_weekday(next, true).map(|weekday| (DateWord::Date(upcoming(today, weekday) + Days::new(7)), 2));
+    }
+
+    if word == "on" || word == "due" {
+        return parse_weekday(next, true).map(|weekday| (DateWord::Date(upcoming(today, weekday)), 2));
+    }
+
+    if word == "in" {
+        let amount: u32 = next.parse().ok()?;
+        let unit = words.get(2)?.as_str();
+
+        if unit == "working" || unit == "business" {
+            let unit = words.get(3)?.as_str();
+
+            let date_word = match unit.strip_suffix('s').unwrap_or(unit) {
+                "day" => DateWord::Date(calendar.add_working_days(today, amount)?),
+                "hour" => DateWord::Instant(calendar.add_working_time(now, Duration::hours(amount.into()))?),
+                _ => return None
+            };
+
+            return Some((date_word, 4));
+        }
+
+        let now = now.with_timezone(&Utc);
+
+        let date_word = match unit.strip_suffix('s').unwrap_or(unit) {
+            "min" | "minute" => DateWord::Instant(now.checked_add_signed(Duration::minutes(amount.into()))?),
+            "hour" => DateWord::Instant(now.checked_add_signed(Duration::hours(amount.into()))?),
+            "day" => DateWord::Date(today.checked_add_days(Days::new(amount.into()))?),
+            "week" => DateWord::Date(today.checked_add_days(Days::new(7 * u64::from(amount)))?),
+            _ => return None
+        };
+
+        return Some((date_word, 3));
+    }
+
+    None
+}
+
+fn parse_priority (word: &str) -> Option<Priority> {
+    match word {
+        "common" => Some(Priority::Common),
+        "green" => Some(Priority::Green),
+        "yellow" => Some(Priority::Yellow),
+        "red" => Some(Priority::Red),
+        _ => None
+    }
+}
+
+/// Converts a local civil time to UTC, falling back to the current offset
+/// for times skipped by a DST transition.
+fn resolve<Tz: TimeZone> (now: &DateTime<Tz>, date: NaiveDate, time: NaiveTime) -> DateTime<Utc> {
+    let local = date.and_time(time);
+
+    match now.timezone().from_local_datetime(&local).earliest() {
+        Some(datetime) => datetime.with_timezone(&Utc),
+        None => Utc.from_utc_datetime(&(local - Duration::seconds(now.offset().fix().local_minus_utc() as i64)))
+    }
+}
+
+impl Task {
+    /// Parses a single line like `Fix login bug tomorrow 17:00 #auth @Work !red`.
+    ///
+    /// * `#tag` adds a tag, `@Category` sets the category, `!red` (or `!yellow`,
+    ///   `!green`, `!common`) sets the priority.
+    /// * The first date phrase sets the deadline: `today`, `tomorrow`, `eod`,
+    ///   a weekday (`friday`, or `on fri`, `due fri`), `next fri`, `next week`,
+    ///   `in 3 days|weeks|hours|minutes`, `in 2 working days|hours` or `2024-05-10`.
+    /// * The first time (`17:00`, `at 17:00`, `5pm`) sets the time of day. A date
+    ///   without time gives an all-day deadline, a time without date is due at
+    ///   its next occurrence. `eod` is today at 23:59:59.
+    ///
+    /// Relative dates resolve against `now`, and in its timezone. Working days
+    /// and hours follow the default `WorkingCalendar`, see `quick_add_with`.
+    /// Every other word is kept in the title.
+    pub fn quick_add<Tz: TimeZone> (line: &str, now: &DateTime<Tz>) -> Result<QuickAdd, &'static str> {
+        Self::quick_add_with(line, now, &WorkingCalendar::default())
+    }
+
+    /// Same as `quick_add`, counting working days and hours in `calendar`.
+    pub fn quick_add_with<Tz: TimeZone> (line: &str, now: &DateTime<Tz>, calendar: &WorkingCalendar) -> Result<QuickAdd, &'static str> {
+        let words: Vec<String> = line.split_whitespace().map(String::from).collect();
+
+        let mut title = vec![];
+        let mut tokens = vec![];
+
+        let mut tags: Vec<Tag> = vec![];
+        let mut category = None;
+        let mut priority = None;
+
+        let mut date = None;
+        let mut time = None;
+        let mut instant = None;
+
+        let today = now.date_naive();
+        let mut index = 0;
+
+        while index < words.len() {
+            let word = &words[index];
+            let lower = word.to_lowercase();
+
+            if let Some(tag) = word.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+                let tag = Tag::new(tag)?;
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+                tokens.push(QuickAddToken::Tag(word.clone()));
+                index += 1;
+                continue;
+            }
+
+            if let Some(title) = word.strip_prefix('@').filter(|title| !title.is_empty()) {
+                category = Some(Category::new(title)?);
+                tokens.push(QuickAddToken::Category(word.clone()));
+                index += 1;
+                continue;
+            }
+
+            if let Some(value) = lower.strip_prefix('!').and_then(parse_priority) {
+                priority = Some(value);
+                tokens.push(QuickAddToken::Priority(word.clone()));
+                index += 1;
+                continue;
+            }
+
+            let lowered: Vec<String> = words[index..].iter().take(4).map(|word| word.to_lowercase()).collect();
+
+            if let Some((date_word, length)) = parse_date_word(&lowered, now, calendar) {
+                let accepted = match date_word {
+                    DateWord::Date(value) if date.is_none() && instant.is_none() => {
+                        date = Some(value);
+                        true
+                    }
+                    DateWord::Time(value) if time.is_none() && instant.is_none() => {
+                        time = Some(value);
+                        true
+                    }
+                    DateWord::DateTime(day, at) if date.is_none() && time.is_none() && instant.is_none() => {
+                        date = Some(day);
+                        time = Some(at);
+                        true
+                    }
+                    DateWord::Instant(value) if date.is_none() && time.is_none() && instant.is_none() => {
+                        instant = Some(value);
+                        true
+                    }
+                    _ => false
+                };
+
+                if accepted {
+                    tokens.push(QuickAddToken::Deadline(words[index..index + length].join(" ")));
+                    index += length;
+                    continue;
+                }
+            }
+
+            title.push(word.as_str());
+            index += 1;
+        }
+
+        let mut builder = Task::new(&title.join(" "))?;
+
+        if !tags.is_empty() {
+            builder = builder.tags(&tags);
+        }
+        if let Some(category) = category {
+            builder = builder.category(category);
+        }
+        if let Some(priority) = priority {
+            builder = builder.priority(priority);
+        }
+
+        let deadline = match (instant, date, time) {
+            (Some(instant), _, _) => Some(Deadline::DateTime(instant)),
+            (None, Some(date), None) => Some(Deadline::Date(date)),
+            (None, Some(date), Some(time)) => Some(Deadline::DateTime(resolve(now, date, time))),
+            (None, None, Some(time)) => {
+                let date = if time > now.time() { today } else { today + Days::new(1) };
+                Some(Deadline::DateTime(resolve(now, date, time)))
+            }
+            (None, None, None) => None
+        };
+
+        if let Some(deadline) = deadline {
+            builder = builder.deadline(deadline);
+        }
+
+        Ok(QuickAdd { builder, tokens })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::FixedOffset;
+
+    use super::*;
+
+    // Monday
+    fn now () -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 6, 10, 0, 0).unwrap()
+    }
+
+    fn day (day: u32) -> Deadline {
+        Deadline::Date(NaiveDate::from_ymd_opt(2024, 5, day).unwrap())
+    }
+
+    fn at (day: u32, hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, hour, minute, second).unwrap()
+    }
+
+    #[test]
+    fn full_line () {
+        let quick = Task::quick_add("Fix login bug tomorrow 17:00 #auth #backend @Work !red", &now()).unwrap();
+
+        assert_eq!(quick.tokens(), &[
+            QuickAddToken::Deadline("tomorrow".to_owned()),
+            QuickAddToken::Deadline("17:00".to_owned()),
+            QuickAddToken::Tag("#auth".to_owned()),
+            QuickAddToken::Tag("#backend".to_owned()),
+            QuickAddToken::Category("@Work".to_owned()),
+            QuickAddToken::Priority("!red".to_owned())
+        ]);
+
+        let task = quick.build();
+        assert_eq!(task.get_title(), "Fix login bug");
+        assert_eq!(task.get_tags(), &vec![Tag::new("auth").unwrap(), Tag::new("backend").unwrap()]);
+        assert_eq!(task.get_category(), &Category::new("Work").unwrap());
+        assert_eq!(task.get_priority(), &Priority::Red);
+        assert_eq!(task.get_deadline(), Some(&at(7, 17, 0, 0).into()));
+    }
+
+    #[test]
+    fn plain_title () {
+        let quick = Task::quick_add("  Buy milk ", &now()).unwrap();
+
+        assert!(quick.tokens().is_empty());
+        assert!(!quick.build().has_deadline());
+    }
+
+    #[test]
+    fn empty_title_is_rejected () {
+        assert!(Task::quick_add("#auth tomorrow", &now()).is_err());
+    }
+
+    #[test]
+    fn relative_dates () {
+        let deadline = |line: &str| Task::quick_add(line, &now()).unwrap().build().get_deadline().copied();
+
+        assert_eq!(deadline("Call today"), Some(day(6)));
+        assert_eq!(deadline("Call eod"), Some(at(6, 23, 59, 59).into()));
+        assert_eq!(deadline("Call friday"), Some(day(10)));
+        assert_eq!(deadline("Call Monday"), Some(day(13)));
+        assert_eq!(deadline("Call next friday"), Some(day(17)));
+        assert_eq!(deadline("Call next fri"), Some(day(17)));
+        assert_eq!(deadline("Call on fri"), Some(day(10)));
+        assert_eq!(deadline("Call due Wed"), Some(day(8)));
+        assert_eq!(deadline("Call next week"), Some(day(13)));
+        assert_eq!(deadline("Call in 3 days at 9am"), Some(at(9, 9, 0, 0).into()));
+        assert_eq!(deadline("Call in 2 hours"), Some(at(6, 12, 0, 0).into()));
+        assert_eq!(deadline("Call 2024-05-20 5pm"), Some(at(20, 17, 0, 0).into()));
+    }
+
+    #[test]
+    fn time_only_rolls_over () {
+        let deadline = |line: &str| Task::quick_add(line, &now()).unwrap().build().get_deadline().copied();
+
+        assert_eq!(deadline("Standup 11:00"), Some(at(6, 11, 0, 0).into()));
+        assert_eq!(deadline("Standup 09:00"), Some(at(7, 9, 0, 0).into()));
+    }
+
+    #[test]
+    fn second_date_stays_in_title () {
+        let task = Task::quick_add("Move friday meeting to monday", &now()).unwrap().build();
+
+        assert_eq!(task.get_title(), "Move meeting to monday");
+        assert_eq!(task.get_deadline(), Some(&day(10)));
+    }
+
+    #[test]
+    fn short_weekdays_alone_stay_in_title () {
+        for line in ["Read the Sun article", "Email Sat about the car", "Plan wed anniversary"] {
+            let task = Task::quick_add(line, &now()).unwrap().build();
+
+            assert_eq!(task.get_title(), line);
+            assert_eq!(task.get_deadline(), None);
+        }
+    }
+
+    #[test]
+    fn unknown_markers_stay_in_title () {
+        let task = Task::quick_add("Say hi ! !loud # @", &now()).unwrap().build();
+
+        assert_eq!(task.get_title(), "Say hi ! !loud # @");
+        assert_eq!(task.get_priority(), &Priority::Common);
+    }
+
+    #[test]
+    fn resolves_in_the_timezone_of_now () {
+        let now = FixedOffset::east_opt(3 * 3600).unwrap().with_ymd_and_hms(2024, 5, 6, 23, 30, 0).unwrap();
+        let task = Task::quick_add("Report tomorrow 09:00", &now).unwrap().build();
+
+        assert_eq!(task.get_deadline(), Some(&at(7, 6, 0, 0).into()));
+    }
+
+    #[test]
+    fn working_days_and_hours () {
+        let friday = Utc.with_ymd_and_hms(2024, 5, 10, 15, 0, 0).unwrap();
+        let deadline = |line: &str, calendar: &WorkingCalendar| Task::quick_add_with(line, &friday, calendar).unwrap().build().get_deadline().copied();
+
+        let calendar = WorkingCalendar::new();
+        assert_eq!(deadline("Ship in 2 working days", &calendar), Some(day(14)));
+        assert_eq!(deadline("Ship in 1 business day 17:00", &calendar), Some(at(13, 17, 0, 0).into()));
+        assert_eq!(deadline("Ship in 4 working hours", &calendar), Some(at(13, 11, 0, 0).into()));
+
+        let calendar = calendar.holiday(NaiveDate::from_ymd_opt(2024, 5, 13).unwrap());
+        assert_eq!(deadline("Ship in 2 working days", &calendar), Some(day(15)));
+        assert_eq!(Task::quick_add("Ship in 2 working weeks", &friday).unwrap().build().get_title(), "Ship in 2 working weeks");
+    }
+
+    #[test]
+    fn out_of_range_amounts_stay_in_title () {
+        for line in ["Ship in 4000000000 hours", "Ship in 4000000000 days", "Ship in 4000000000 working days", "Ship in 4000000000 working hours"] {
+            let task = Task::quick_add(line, &now()).unwrap().build();
+
+            assert_eq!(task.get_title(), line);
+            assert_eq!(task.get_deadline(), None);
+        }
+    }
+}
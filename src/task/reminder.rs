@@ -0,0 +1,115 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Serialize, Deserialize};
+
+use super::Deadline;
+
+/// When to remind about a task.
+///
+/// Serialized as `{"at": "2024-05-10T09:00:00Z"}` or
+/// `{"before_deadline": 3600}` (seconds).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reminder {
+    /// At an exact instant.
+    At(DateTime<Utc>),
+    /// A number of seconds before the deadline is due, see `Deadline::due_at`.
+    BeforeDeadline(i64)
+}
+
+impl Reminder {
+    pub fn at<Tz: TimeZone> (at: DateTime<Tz>) -> Self {
+        Self::At(at.with_timezone(&Utc))
+    }
+
+    pub fn before_deadline (offset: Duration) -> Self {
+        Self::BeforeDeadline(offset.num_seconds())
+    }
+
+    /// Parses an offset relative to the deadline like `1h before`,
+    /// `30 min before` or `2 days`: an amount, a unit (m, min, minutes, h,
+    /// hours, d, days, w, weeks) and an optional `before`.
+    pub fn parse (text: &str) -> Result<Self, &'static str> {
+        let text = text.trim().to_lowercase();
+        let text = text.strip_suffix("before").unwrap_or(&text).trim();
+
+        let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+        let (amount, unit) = text.split_at(split);
+
+        let amount: i64 = amount.parse().map_err(|_| "The reminder has no amount")?;
+
+        let offset = match unit.trim() {
+            "m" | "min" | "mins" | "minute" | "minutes" => Duration::try_minutes(amount),
+            "h" | "hour" | "hours" => Duration::try_hours(amount),
+            "d" | "day" | "days" => Duration::try_days(amount),
+            "w" | "week" | "weeks" => Duration::try_weeks(amount),
+            _ => return Err("Unknown reminder unit")
+        };
+
+        Ok(Self::before_deadline(offset.ok_or("The reminder is out of range")?))
+    }
+
+    /// The instant the reminder fires, all-day deadlines are evaluated in
+    /// `tz`. A reminder relative to the deadline never fires without one, or
+    /// when it would fire past the supported dates.
+    pub fn fires_at<Tz: TimeZone> (&self, deadline: Option<&Deadline>, tz: &Tz) -> Option<DateTime<Utc>> {
+        match self {
+            Self::At(at) => Some(*at),
+            Self::BeforeDeadline(seconds) => deadline?.due_at(tz).checked_sub_signed(Duration::try_seconds(*seconds)?)
+        }
+    }
+}
+
+impl std::fmt::Display for Reminder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::At(at) => write!(f, "{}", at.format("%Y-%m-%d %H:%M")),
+            Self::BeforeDeadline(seconds) if seconds % 86400 == 0 => write!(f, "{}d before", seconds / 86400),
+            Self::BeforeDeadline(seconds) if seconds % 3600 == 0 => write!(f, "{}h before", seconds / 3600),
+            Self::BeforeDeadline(seconds) => write!(f, "{}m before", seconds / 60)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn at (day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn parse () {
+        assert_eq!(Reminder::parse("1h before"), Ok(Reminder::before_deadline(Duration::hours(1))));
+        assert_eq!(Reminder::parse("30 min before"), Ok(Reminder::before_deadline(Duration::minutes(30))));
+        assert_eq!(Reminder::parse("2 days"), Ok(Reminder::before_deadline(Duration::days(2))));
+        assert!(Reminder::parse("soon").is_err());
+        assert!(Reminder::parse("3 fortnights before").is_err());
+        assert!(Reminder::parse("9000000000000000 weeks").is_err());
+    }
+
+    #[test]
+    fn fires_at () {
+        let hour = Reminder::before_deadline(Duration::hours(1));
+        let all_day = Deadline::from(NaiveDate::from_ymd_opt(2024, 5, 10).unwrap());
+
+        assert_eq!(hour.fires_at(Some(&at(10, 12).into()), &Utc), Some(at(10, 11)));
+        assert_eq!(hour.fires_at(Some(&all_day), &Utc), Some(at(10, 23)));
+        assert_eq!(hour.fires_at(None, &Utc), None);
+        assert_eq!(Reminder::at(at(9, 8)).fires_at(None, &Utc), Some(at(9, 8)));
+        assert_eq!(Reminder::BeforeDeadline(i64::MAX).fires_at(Some(&all_day), &Utc), None);
+        assert_eq!(Reminder::BeforeDeadline(-9_000_000_000_000).fires_at(Some(&all_day), &Utc), None);
+    }
+
+    #[test]
+    fn display_and_serialization () {
+        assert_eq!(Reminder::parse("90 min").unwrap().to_string(), "90m before");
+        assert_eq!(Reminder::parse("2d").unwrap().to_string(), "2d before");
+
+        let reminder = Reminder::parse("1h").unwrap();
+        assert_eq!(serde_json::to_string(&reminder).unwrap(), r#"{"before_deadline":3600}"#);
+        assert_eq!(serde_json::from_str::<Reminder>(r#"{"before_deadline":3600}"#).unwrap(), reminder);
+    }
+}
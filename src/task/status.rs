@@ -1,19 +1,16 @@
 use std::cmp::{PartialEq, Eq};
+use chrono::{DateTime, Utc};
+use serde::{Serialize, Deserialize};
 
-#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub enum TaskStatus {
+    #[default]
     Created,
     Progress,
     Completed,
     Archived
 }
 
-impl Default for TaskStatus {
-    fn default() -> Self {
-        TaskStatus::Created
-    }
-}
-
 impl std::fmt::Display for TaskStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", match self {
@@ -25,6 +22,47 @@ impl std::fmt::Display for TaskStatus {
     }
 }
 
+/// Parses the names written by `Display`, in any case.
+impl std::str::FromStr for TaskStatus {
+    type Err = &'static str;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "created" => Ok(Self::Created),
+            "progress" => Ok(Self::Progress),
+            "completed" => Ok(Self::Completed),
+            "archived" => Ok(Self::Archived),
+            _ => Err("Unknown status")
+        }
+    }
+}
+
+/// A change of a task's status, kept in its status history.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusChange {
+    changed_at: DateTime<Utc>,
+    from: TaskStatus,
+    to: TaskStatus
+}
+
+impl StatusChange {
+    pub fn new (changed_at: DateTime<Utc>, from: TaskStatus, to: TaskStatus) -> Self {
+        Self { changed_at, from, to }
+    }
+
+    pub fn get_changed_at (&self) -> &DateTime<Utc> {
+        &self.changed_at
+    }
+
+    pub fn get_from (&self) -> &TaskStatus {
+        &self.from
+    }
+
+    pub fn get_to (&self) -> &TaskStatus {
+        &self.to
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -50,4 +88,11 @@ mod tests {
 
         assert_ne!(created, archived);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn parse () {
+        assert_eq!("progress".parse::<TaskStatus>(), Ok(TaskStatus::Progress));
+        assert_eq!(TaskStatus::Archived.to_string().parse::<TaskStatus>(), Ok(TaskStatus::Archived));
+        assert!("done".parse::<TaskStatus>().is_err());
+    }
+}
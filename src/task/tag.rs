@@ -1,7 +1,9 @@
 use uuid::Uuid;
 use std::cmp::PartialEq;
+use serde::{Serialize, Deserialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "TagFields")]
 pub struct Tag {
     #[allow(unused)]
     id: Uuid,
@@ -35,6 +37,21 @@ impl Tag {
     }
 }
 
+/// A tag as serialized, checked like a new one.
+#[derive(Deserialize)]
+struct TagFields {
+    id: Uuid,
+    title: String
+}
+
+impl TryFrom<TagFields> for Tag {
+    type Error = &'static str;
+
+    fn try_from (fields: TagFields) -> Result<Self, Self::Error> {
+        Ok(Self { id: fields.id, title: Self::prepare_title(&fields.title)? })
+    }
+}
+
 impl std::fmt::Display for Tag {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "#{}", self.title)
@@ -45,10 +62,6 @@ impl PartialEq for Tag {
     fn eq(&self, other: &Self) -> bool {
         self.title == other.title
     }
-
-    fn ne(&self, other: &Self) -> bool {
-        self.title != other.title
-    }
 }
 
 impl Default for Tag {
@@ -77,6 +90,14 @@ mod tests {
         assert_eq!("new_title", tag.get_title());
     }
 
+    #[test]
+    fn deserialize_checks_title () {
+        let tag: Tag = serde_json::from_str(r#"{"id": "00000000-0000-0000-0000-000000000000", "title": " to do "}"#).unwrap();
+        assert_eq!(tag.get_title(), "to_do");
+
+        assert!(serde_json::from_str::<Tag>(r#"{"id": "00000000-0000-0000-0000-000000000000", "title": " "}"#).is_err());
+    }
+
     #[test]
     fn default_tag () {
         let tag = Tag::default();
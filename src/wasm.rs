@@ -0,0 +1,364 @@
+//! JavaScript bindings for `wasm32-unknown-unknown`.
+//!
+//! Tasks cross the boundary as JSON. Input objects use camelCase keys:
+//!
+//! ```json
+//! { "title": "Fix login", "description": "...", "status": "Progress",
+//!   "category": "Work", "tags": ["auth"], "priority": "Red",
+//!   "deadline": "2030-01-01T12:00:00Z" }
+//! ```
+//!
+//! `deadline` is a date (`"2030-01-01"`) for an all-day deadline, a date-time,
+//! or a window `{"start": ..., "end": ...}`.
+//!
+//! Only `title` is required. `TodoList.toJson` / `TodoList.fromJson` exchange
+//! the whole list state and round-trip exactly.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use wasm_bindgen::prelude::*;
+
+use crate::prelude::*;
+use crate::task::TaskBuilder;
+use crate::{By, TodoList};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct TaskInput {
+    title: String,
+    description: Option<String>,
+    status: Option<Status>,
+    category: Option<String>,
+    tags: Option<Vec<String>>,
+    priority: Option<Priority>,
+    deadline: Option<Deadline>
+}
+
+/// Partial update, absent keys are left untouched.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct TaskPatch {
+    title: Option<String>,
+    description: Option<String>,
+    status: Option<Status>,
+    category: Option<String>,
+    tags: Option<Vec<String>>,
+    priority: Option<Priority>,
+    deadline: Option<Deadline>
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskOutput<'a> {
+    id: &'a Uuid,
+    title: &'a str,
+    description: &'a str,
+    status: &'a Status,
+    category: &'a str,
+    tags: Vec<&'a str>,
+    priority: &'a Priority,
+    created_at: &'a DateTime<Utc>,
+    updated_at: &'a DateTime<Utc>,
+    deadline: Option<&'a Deadline>
+}
+
+impl<'a> From<&'a Task> for TaskOutput<'a> {
+    fn from(task: &'a Task) -> Self {
+        Self {
+            id: task.get_id(),
+            title: task.get_title(),
+            description: task.get_description(),
+            status: task.get_status(),
+            category: task.get_category().get_title(),
+            tags: task.get_tags().iter().map(|tag| tag.get_title()).collect(),
+            priority: task.get_priority(),
+            created_at: task.get_created_at(),
+            updated_at: task.get_updated_at(),
+            deadline: task.get_deadline()
+        }
+    }
+}
+
+fn parse_tags (tags: &[String]) -> Result<Vec<Tag>, String> {
+    tags.iter().map(|tag| Tag::new(tag).map_err(String::from)).collect()
+}
+
+fn build_task (json: &str) -> Result<Task, String> {
+    let input: TaskInput = serde_json::from_str(json).map_err(|err| err.to_string())?;
+    let mut builder: TaskBuilder = Task::new(&input.title)?;
+
+    if let Some(description) = input.description {
+        builder = builder.description(&description);
+    }
+    if let Some(status) = input.status {
+        builder = builder.status(status);
+    }
+    if let Some(category) = input.category {
+        builder = builder.category(Category::new(&category)?);
+    }
+    if let Some(tags) = input.tags {
+        builder = builder.tags(&parse_tags(&tags)?);
+    }
+    if let Some(priority) = input.priority {
+        builder = builder.priority(priority);
+    }
+    if let Some(deadline) = input.deadline {
+        builder = builder.deadline(deadline);
+    }
+
+    Ok(builder.build())
+}
+
+fn apply_patch (task: &mut Task, json: &str) -> Result<(), String> {
+    let patch: TaskPatch = serde_json::from_str(json).map_err(|err| err.to_string())?;
+
+    // Validate everything first so a bad patch leaves the task untouched.
+    let category = patch.category.as_deref().map(Category::new).transpose()?;
+    let tags = patch.tags.as_deref().map(parse_tags).transpose()?;
+
+    if let Some(title) = patch.title {
+        task.set_title(&title)?;
+    }
+    if let Some(description) = patch.description {
+        task.set_description(&description);
+    }
+    if let Some(status) = patch.status {
+        task.set_status(status);
+    }
+    if let Some(category) = category {
+        task.set_category(category);
+    }
+    if let Some(tags) = tags {
+        task.set_tags(tags);
+    }
+    if let Some(priority) = patch.priority {
+        task.set_priority(priority);
+    }
+    if let Some(deadline) = patch.deadline {
+        task.set_deadline(deadline);
+    }
+
+    Ok(())
+}
+
+fn to_json<T: Serialize> (value: &T) -> Result<String, String> {
+    serde_json::to_string(value).map_err(|err| err.to_string())
+}
+
+fn parse_id (id: &str) -> Result<Uuid, String> {
+    Uuid::parse_str(id).map_err(|err| err.to_string())
+}
+
+fn tasks_by<'a> (list: &'a TodoList, kind: &str, value: &str) -> Result<Vec<&'a Task>, String> {
+    let quoted = format!("\"{value}\"");
+
+    Ok(match kind {
+        "category" => list.get_tasks_by(By::Category(&Category::new(value)?)),
+        "tag" => list.get_tasks_by(By::Tag(&Tag::new(value)?)),
+        "priority" => list.get_tasks_by(By::Priority(serde_json::from_str(&quoted).map_err(|err| err.to_string())?)),
+        "status" => list.get_tasks_by(By::Status(serde_json::from_str(&quoted).map_err(|err| err.to_string())?)),
+        _ => return Err(format!("unknown filter `{kind}`"))
+    })
+}
+
+fn js_error (message: String) -> JsError {
+    JsError::new(&message)
+}
+
+#[wasm_bindgen(js_name = Task)]
+pub struct WasmTask {
+    inner: Task
+}
+
+#[wasm_bindgen(js_class = Task)]
+impl WasmTask {
+    /// Builds a task from a JSON task input.
+    #[wasm_bindgen(constructor)]
+    pub fn new (json: &str) -> Result<WasmTask, JsError> {
+        Ok(Self { inner: build_task(json).map_err(js_error)? })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn id (&self) -> String {
+        self.inner.get_id().to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn title (&self) -> String {
+        self.inner.get_title().to_owned()
+    }
+
+    pub fn update (&mut self, json: &str) -> Result<(), JsError> {
+        apply_patch(&mut self.inner, json).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json (&self) -> Result<String, JsError> {
+        to_json(&TaskOutput::from(&self.inner)).map_err(js_error)
+    }
+}
+
+#[wasm_bindgen(js_name = TodoList)]
+pub struct WasmTodoList {
+    inner: TodoList
+}
+
+impl Default for WasmTodoList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen(js_class = TodoList)]
+impl WasmTodoList {
+    #[wasm_bindgen(constructor)]
+    pub fn new () -> WasmTodoList {
+        Self { inner: TodoList::default() }
+    }
+
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json (json: &str) -> Result<WasmTodoList, JsError> {
+        Ok(Self { inner: serde_json::from_str(json)? })
+    }
+
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json (&self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(&self.inner)?)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn length (&self) -> usize {
+        self.inner.get_tasks().len()
+    }
+
+    /// Moves the task into the list and returns its id.
+    #[wasm_bindgen(js_name = addTask)]
+    pub fn add_task (&mut self, task: WasmTask) -> Result<String, JsError> {
+        let id = task.id();
+        self.inner.add_task(task.inner).map_err(JsError::new)?;
+
+        Ok(id)
+    }
+
+    /// Builds a task from a JSON task input, adds it and returns its id.
+    #[wasm_bindgen(js_name = createTask)]
+    pub fn create_task (&mut self, json: &str) -> Result<String, JsError> {
+        self.add_task(WasmTask::new(json)?)
+    }
+
+    #[wasm_bindgen(js_name = updateTask)]
+    pub fn update_task (&mut self, id: &str, json: &str) -> Result<(), JsError> {
+        let id = parse_id(id).map_err(js_error)?;
+        let task = self.inner.get_task_mut(&id).ok_or_else(|| JsError::new("The task is not exists"))?;
+
+        apply_patch(task, json).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = removeTask)]
+    pub fn remove_task (&mut self, id: &str) -> Result<(), JsError> {
+        let id = parse_id(id).map_err(js_error)?;
+        self.inner.remove_task_by_id(&id).map_err(JsError::new)?;
+
+        Ok(())
+    }
+
+    /// JSON of the task with the given id, `undefined` if there is none.
+    pub fn task (&self, id: &str) -> Result<Option<String>, JsError> {
+        let id = parse_id(id).map_err(js_error)?;
+
+        self.inner.get_task(&id).map(|task| to_json(&TaskOutput::from(task)).map_err(js_error)).transpose()
+    }
+
+    /// JSON array of every task.
+    pub fn tasks (&self) -> Result<String, JsError> {
+        let tasks: Vec<TaskOutput> = self.inner.get_tasks().iter().map(TaskOutput::from).collect();
+        to_json(&tasks).map_err(js_error)
+    }
+
+    /// JSON array of the tasks matching a filter, `kind` is one of
+    /// `category`, `tag`, `priority` or `status`.
+    #[wasm_bindgen(js_name = tasksBy)]
+    pub fn tasks_by (&self, kind: &str, value: &str) -> Result<String, JsError> {
+        let tasks: Vec<TaskOutput> = tasks_by(&self.inner, kind, value).map_err(js_error)?
+            .into_iter()
+            .map(TaskOutput::from)
+            .collect();
+
+        to_json(&tasks).map_err(js_error)
+    }
+
+    #[wasm_bindgen(js_name = newCategory)]
+    pub fn new_category (&mut self, title: &str) -> Result<(), JsError> {
+        self.inner.new_category(title).map_err(JsError::new)
+    }
+
+    #[wasm_bindgen(js_name = newTag)]
+    pub fn new_tag (&mut self, title: &str) -> Result<(), JsError> {
+        self.inner.new_tag(title).map_err(JsError::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_task_from_json () {
+        let task = build_task(r#"{"title": " First ", "priority": "Red", "tags": ["rust"], "deadline": "2030-01-01T12:00:00Z"}"#).unwrap();
+
+        assert_eq!(task.get_title(), "First");
+        assert_eq!(task.get_priority(), &Priority::Red);
+        assert_eq!(task.get_tags(), &vec![Tag::new("rust").unwrap()]);
+        assert!(task.has_deadline());
+
+        let task = build_task(r#"{"title": "First", "deadline": "2030-01-01"}"#).unwrap();
+        assert!(task.get_deadline().unwrap().is_all_day());
+    }
+
+    #[test]
+    fn invalid_input_is_rejected () {
+        assert!(build_task(r#"{"title": ""}"#).is_err());
+        assert!(build_task(r#"{"title": "First", "priority": "Pink"}"#).is_err());
+        assert!(build_task(r#"{"title": "First", "unknown": 1}"#).is_err());
+    }
+
+    #[test]
+    fn bad_patch_leaves_task_untouched () {
+        let mut task = build_task(r#"{"title": "First"}"#).unwrap();
+
+        assert!(apply_patch(&mut task, r#"{"description": "changed", "tags": [""]}"#).is_err());
+        assert_eq!(task.get_description(), "");
+
+        apply_patch(&mut task, r#"{"status": "Completed", "category": "Work"}"#).unwrap();
+        assert_eq!(task.get_status(), &Status::Completed);
+        assert_eq!(task.get_category().get_title(), "Work");
+    }
+
+    #[test]
+    fn filter_and_output () {
+        let mut list = TodoList::default();
+        list.add_task(build_task(r#"{"title": "First", "priority": "Red"}"#).unwrap()).unwrap();
+        list.add_task(build_task(r#"{"title": "Second"}"#).unwrap()).unwrap();
+
+        let tasks = tasks_by(&list, "priority", "Red").unwrap();
+        assert_eq!(tasks.len(), 1);
+
+        let json = to_json(&TaskOutput::from(tasks[0])).unwrap();
+        assert!(json.contains(r#""title":"First""#));
+        assert!(json.contains(r#""category":"Default""#));
+        assert!(tasks_by(&list, "colour", "Red").is_err());
+    }
+
+    #[test]
+    fn list_state_round_trip () {
+        let mut list = TodoList::default();
+        list.add_task(build_task(r#"{"title": "First", "deadline": "2030-01-01T12:00:00Z"}"#).unwrap()).unwrap();
+        list.new_tag("rust").unwrap();
+
+        let restored: TodoList = serde_json::from_str(&serde_json::to_string(&list).unwrap()).unwrap();
+
+        assert!(restored.get_tasks() == list.get_tasks());
+        assert_eq!(restored.get_tags(), list.get_tags());
+    }
+}
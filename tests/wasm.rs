@@ -0,0 +1,39 @@
+//! Run under node with `wasm-pack test --node --features wasm`, or with
+//! `CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner cargo test --target wasm32-unknown-unknown --features wasm`.
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use todolist::wasm::{WasmTask, WasmTodoList};
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen_test]
+fn create_and_filter_tasks () {
+    let mut list = WasmTodoList::new();
+    let id = list.create_task(r#"{"title": "First", "priority": "Red", "tags": ["web"]}"#).unwrap();
+    list.add_task(WasmTask::new(r#"{"title": "Second"}"#).unwrap()).unwrap();
+
+    assert_eq!(list.length(), 2);
+    assert!(list.tasks_by("priority", "Red").unwrap().contains(&id));
+    assert_eq!(list.tasks_by("tag", "web").unwrap().matches("\"id\"").count(), 1);
+}
+
+#[wasm_bindgen_test]
+fn generated_ids_and_timestamps () {
+    let first = WasmTask::new(r#"{"title": "First"}"#).unwrap();
+    let second = WasmTask::new(r#"{"title": "Second"}"#).unwrap();
+
+    assert_ne!(first.id(), second.id());
+    assert!(first.to_json().unwrap().contains("\"createdAt\":\"20"));
+}
+
+#[wasm_bindgen_test]
+fn update_and_round_trip () {
+    let mut list = WasmTodoList::new();
+    let id = list.create_task(r#"{"title": "First"}"#).unwrap();
+    list.update_task(&id, r#"{"status": "Completed"}"#).unwrap();
+
+    let restored = WasmTodoList::from_json(&list.to_json().unwrap()).unwrap();
+    assert!(restored.task(&id).unwrap().unwrap().contains("\"status\":\"Completed\""));
+
+    list.remove_task(&id).unwrap();
+    assert_eq!(list.length(), 0);
+}